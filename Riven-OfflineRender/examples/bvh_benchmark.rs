@@ -0,0 +1,76 @@
+//! Compares the SAH BVH builder against the random axis median split it replaced.
+//!
+//! Run with `cargo run --release --example bvh_benchmark`.
+
+use std::time::{Duration, Instant};
+use Riven_OfflineRender::engine::base::constants::constants::{ranged_random_float, INFINITY};
+use Riven_OfflineRender::engine::base::interval::Interval;
+use Riven_OfflineRender::engine::base::point::Point3;
+use Riven_OfflineRender::engine::base::ray::Ray;
+use Riven_OfflineRender::engine::base::vector::Vector3;
+use Riven_OfflineRender::engine::bounding_model::bvh::Bvh;
+use Riven_OfflineRender::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
+use Riven_OfflineRender::engine::objects::hit_record::HitRecord;
+use Riven_OfflineRender::engine::objects::object::HitList;
+use Riven_OfflineRender::engine::objects::sphere::Sphere;
+use Riven_OfflineRender::engine::objects::Objects;
+
+const SPHERE_COUNT: usize = 4000;
+const RAY_COUNT: usize = 500_000;
+
+/// A bouncing spheres like field, scattered over a ground plane with a few clusters to reward good splits.
+///
+/// Only geometry is traced, so every sphere shares the same placeholder material.
+fn world() -> HitList {
+    let mut world = HitList::new();
+    world.add(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, MaterialId::default()));
+
+    for i in 0..SPHERE_COUNT {
+        let spread = if i % 4 == 0 { 5.0 } else { 60.0 };
+        let center = Point3::new(
+            ranged_random_float(-spread, spread),
+            ranged_random_float(0.2, 3.0),
+            ranged_random_float(-spread, spread)
+        );
+        world.add(Sphere::new(center, 0.2, MaterialId::default()));
+    }
+
+    world
+}
+
+fn rays() -> Vec<Ray> {
+    let origin = Point3::new(13.0, 2.0, 3.0);
+    (0..RAY_COUNT)
+        .map(|_| Ray::new(origin, Vector3::new(-1.0, ranged_random_float(-0.3, 0.1), ranged_random_float(-0.6, 0.6))))
+        .collect()
+}
+
+fn trace(bvh: &Objects, rays: &[Ray]) -> (Duration, usize) {
+    let start = Instant::now();
+    let hits = rays.iter()
+        .filter(|ray| bvh.hit(ray, &mut Interval::new(0.0001, INFINITY), &mut HitRecord::default()))
+        .count();
+    (start.elapsed(), hits)
+}
+
+fn main() {
+    let world = world();
+    let rays = rays();
+
+    let start = Instant::now();
+    let random_split = Bvh::from_world_random_split(world.clone());
+    let random_split_build = start.elapsed();
+
+    let start = Instant::now();
+    let sah = Bvh::from_world(world);
+    let sah_build = start.elapsed();
+
+    let (random_split_trace, random_split_hits) = trace(&random_split, &rays);
+    let (sah_trace, sah_hits) = trace(&sah, &rays);
+    assert_eq!(random_split_hits, sah_hits, "Both trees must find the same hits");
+
+    println!("{} objects, {} rays", SPHERE_COUNT + 1, RAY_COUNT);
+    println!("{:<14}{:>14}{:>14}", "builder", "build", "trace");
+    println!("{:<14}{:>14?}{:>14?}", "random split", random_split_build, random_split_trace);
+    println!("{:<14}{:>14?}{:>14?}", "SAH", sah_build, sah_trace);
+}
@@ -0,0 +1,79 @@
+//! Compares the any-hit `occluded` query against answering shadow rays with the closest-hit `hit`.
+//!
+//! Run with `cargo run --release --example shadow_ray_benchmark`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use Riven_OfflineRender::engine::base::constants::constants::ranged_random_float;
+use Riven_OfflineRender::engine::base::interval::Interval;
+use Riven_OfflineRender::engine::base::point::Point3;
+use Riven_OfflineRender::engine::base::ray::Ray;
+use Riven_OfflineRender::engine::base::transform::Transform;
+use Riven_OfflineRender::engine::base::vector::Vector3;
+use Riven_OfflineRender::engine::bounding_model::bvh::Bvh;
+use Riven_OfflineRender::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
+use Riven_OfflineRender::engine::objects::hit_record::HitRecord;
+use Riven_OfflineRender::engine::objects::instance::Instance;
+use Riven_OfflineRender::engine::objects::object::{HitList, SHADOW_EPSILON};
+use Riven_OfflineRender::engine::objects::quad::Quad;
+use Riven_OfflineRender::engine::objects::sphere::Sphere;
+use Riven_OfflineRender::engine::objects::Objects;
+
+const SPHERE_COUNT: usize = 4000;
+const BOX_COUNT: usize = 500;
+const RAY_COUNT: usize = 500_000;
+
+/// Spheres and rotated boxes scattered over a ground plane, lit by a light high above them.
+///
+/// Only geometry is traced, so every object shares the same placeholder material.
+fn world() -> HitList {
+    let mut world = HitList::new();
+    world.add(Quad::new(Point3::new(-100.0, 0.0, -100.0), Vector3::new(200.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 200.0), MaterialId::default()));
+
+    for _ in 0..SPHERE_COUNT {
+        let center = Point3::new(ranged_random_float(-60.0, 60.0), ranged_random_float(0.2, 3.0), ranged_random_float(-60.0, 60.0));
+        world.add(Sphere::new(center, 0.4, MaterialId::default()));
+    }
+
+    let cube = Arc::new(Quad::make_box(Point3::new(-0.5, 0.0, -0.5), Point3::new(0.5, 1.0, 0.5), MaterialId::default()));
+    for _ in 0..BOX_COUNT {
+        let offset = Vector3::new(ranged_random_float(-60.0, 60.0), 0.0, ranged_random_float(-60.0, 60.0));
+        world.add(Instance::new(cube.clone(), Transform::translate(offset) * Transform::rotate_y(ranged_random_float(0.0, 90.0))));
+    }
+
+    world
+}
+
+/// Rays from random points on the ground towards the light, the light sits at `t = 1`.
+fn shadow_rays() -> Vec<Ray> {
+    let light = Point3::new(10.0, 40.0, -5.0);
+    (0..RAY_COUNT)
+        .map(|_| {
+            let origin = Point3::new(ranged_random_float(-60.0, 60.0), 0.0, ranged_random_float(-60.0, 60.0));
+            Ray::new(origin, light - origin)
+        })
+        .collect()
+}
+
+fn trace(world: &Objects, rays: &[Ray], query: impl Fn(&Objects, &Ray) -> bool) -> (Duration, usize) {
+    let start = Instant::now();
+    let blocked = rays.iter().filter(|ray| query(world, ray)).count();
+    (start.elapsed(), blocked)
+}
+
+fn main() {
+    let world = Bvh::from_world(world());
+    let rays = shadow_rays();
+    let t_max = 1.0 - SHADOW_EPSILON;
+
+    let (hit_time, hit_blocked) = trace(&world, &rays, |world, ray| {
+        world.hit(ray, &mut Interval::new(SHADOW_EPSILON, t_max), &mut HitRecord::default())
+    });
+    let (occluded_time, occluded_blocked) = trace(&world, &rays, |world, ray| world.occluded(ray, t_max));
+    assert_eq!(hit_blocked, occluded_blocked, "Both queries must block the same rays");
+
+    println!("{} objects, {} shadow rays, {} blocked", SPHERE_COUNT + BOX_COUNT + 1, RAY_COUNT, hit_blocked);
+    println!("{:<10}{:>14}", "query", "trace");
+    println!("{:<10}{:>14?}", "hit", hit_time);
+    println!("{:<10}{:>14?}", "occluded", occluded_time);
+}
@@ -0,0 +1,183 @@
+/// A piecewise-constant distribution over `[0, 1)`, built from a tabulated function.
+#[derive(Clone, Debug)]
+pub struct Distribution1D {
+    func: Vec<f32>,
+    /// Running integral of `func`, normalized to end at one, with `func.len() + 1` entries.
+    cdf: Vec<f32>,
+    /// Integral of `func` over `[0, 1)`.
+    func_int: f32,
+}
+
+impl Distribution1D {
+    /// Creates a new `Distribution1D`.
+    ///
+    /// # Arguments
+    ///
+    /// * `func` - Non-negative values of the function on equally sized cells, there must be at least one.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Distribution1D`, uniform if every value is zero.
+    pub fn new(func: Vec<f32>) -> Self {
+        assert!(!func.is_empty(), "A distribution needs at least one cell");
+        let n = func.len();
+
+        let mut cdf = Vec::with_capacity(n + 1);
+        cdf.push(0.0);
+        for (i, value) in func.iter().enumerate() {
+            cdf.push(cdf[i] + value.max(0.0) / n as f32);
+        }
+
+        let func_int = cdf[n];
+        for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+            *c = if func_int > 0.0 { *c / func_int } else { i as f32 / n as f32 };
+        }
+
+        Self { func, cdf, func_int }
+    }
+
+    pub fn len(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.func.is_empty()
+    }
+
+    /// The integral of the tabulated function over `[0, 1)`.
+    pub fn integral(&self) -> f32 {
+        self.func_int
+    }
+
+    /// Maps a uniform random number to a point distributed proportionally to the function.
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - A uniform random number in `[0, 1)`.
+    ///
+    /// # Returns
+    ///
+    /// The sampled point in `[0, 1)`, its density and the index of the cell it fell in.
+    pub fn sample_continuous(&self, u: f32) -> (f32, f32, usize) {
+        // The last cell whose cdf is at or below u
+        let offset = self.cdf.partition_point(|&c| c <= u).clamp(1, self.len()) - 1;
+
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0.0 { (u - self.cdf[offset]) / width } else { 0.0 };
+        let x = ((offset as f32 + du.clamp(0.0, 1.0)) / self.len() as f32).min(1.0 - f32::EPSILON);
+
+        (x, self.pdf(offset), offset)
+    }
+
+    /// The density of a cell relative to the uniform distribution over `[0, 1)`.
+    pub fn pdf(&self, offset: usize) -> f32 {
+        if self.func_int > 0.0 {
+            self.func[offset].max(0.0) / self.func_int
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A piecewise-constant distribution over the unit square, sampled row first and then within the row.
+#[derive(Clone, Debug)]
+pub struct Distribution2D {
+    /// One distribution over `u` per row.
+    conditional: Vec<Distribution1D>,
+    /// The distribution over rows, `v`.
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// Creates a new `Distribution2D`.
+    ///
+    /// # Arguments
+    ///
+    /// * `func` - Non-negative function values, `width` per row, stored row after row.
+    /// * `width` - The number of cells along `u`.
+    /// * `height` - The number of cells along `v`.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Distribution2D`.
+    pub fn new(func: &[f32], width: usize, height: usize) -> Self {
+        assert_eq!(func.len(), width * height, "The function must have width * height values");
+
+        let conditional: Vec<Distribution1D> = func
+            .chunks(width)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|row| row.integral()).collect());
+
+        Self { conditional, marginal }
+    }
+
+    /// Maps two uniform random numbers to a point distributed proportionally to the function.
+    ///
+    /// # Returns
+    ///
+    /// The sampled `(u, v)` and its density over the unit square.
+    pub fn sample_continuous(&self, u0: f32, u1: f32) -> ((f32, f32), f32) {
+        let (v, pdf_v, row) = self.marginal.sample_continuous(u1);
+        let (u, pdf_u, _) = self.conditional[row].sample_continuous(u0);
+
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    /// The density with which `sample_continuous` picks the point `(u, v)`.
+    pub fn pdf(&self, u: f32, v: f32) -> f32 {
+        let row = ((v * self.marginal.len() as f32) as usize).min(self.marginal.len() - 1);
+        let column = ((u * self.conditional[row].len() as f32) as usize).min(self.conditional[row].len() - 1);
+
+        if self.marginal.integral() > 0.0 {
+            self.conditional[row].pdf(column) * self.conditional[row].integral() / self.marginal.integral()
+        } else {
+            1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_distribution {
+    use crate::engine::base::distribution::{Distribution1D, Distribution2D};
+
+    #[test]
+    fn samples_land_in_cells_proportionally_to_their_value(){
+        let distribution = Distribution1D::new(vec![1.0, 0.0, 3.0, 4.0]);
+        let samples = 8000;
+        let mut counts = [0usize; 4];
+        for i in 0..samples {
+            let (x, pdf, offset) = distribution.sample_continuous((i as f32 + 0.5) / samples as f32);
+            assert_eq!((x * 4.0) as usize, offset);
+            assert!((pdf - distribution.pdf(offset)).abs() < 1e-6);
+            counts[offset] += 1;
+        }
+
+        assert_eq!(counts[1], 0);
+        for (count, expected) in counts.iter().zip([1.0, 0.0, 3.0, 4.0]) {
+            assert!((*count as f32 / samples as f32 - expected / 8.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn two_dimensional_pdf_matches_sampling_and_integrates_to_one(){
+        let (width, height) = (8, 4);
+        let func: Vec<f32> = (0..width * height).map(|i| ((i * 7) % 5) as f32).collect();
+        let distribution = Distribution2D::new(&func, width, height);
+
+        let mut integral = 0.0;
+        for j in 0..height {
+            for i in 0..width {
+                let (u, v) = ((i as f32 + 0.5) / width as f32, (j as f32 + 0.5) / height as f32);
+                integral += distribution.pdf(u, v) / (width * height) as f32;
+            }
+        }
+        assert!((integral - 1.0).abs() < 1e-4);
+
+        for k in 0..100 {
+            let ((u, v), pdf) = distribution.sample_continuous((k as f32 * 0.618) % 1.0, (k as f32 * 0.377) % 1.0);
+            assert!(pdf > 0.0);
+            assert!((pdf - distribution.pdf(u, v)).abs() < 1e-4 * pdf.max(1.0));
+        }
+    }
+}
@@ -24,10 +24,11 @@ impl Interval {
         Self { min, max }
     }
 
+    /// Creates the smallest `Interval` enclosing both given intervals.
     pub fn from_interval(interval1 : Interval, interval2 : Interval) -> Interval {
         Self{
             min: if interval1.min <= interval2.min {interval1.min}else {interval2.min},
-            max: if interval1.max <= interval2.max {interval1.max}else {interval2.max},
+            max: if interval1.max >= interval2.max {interval1.max}else {interval2.max},
         }
     }
 
@@ -4,3 +4,6 @@ pub mod ray;
 mod co_ordinate;
 pub mod constants;
 pub mod interval;
+pub mod transform;
+pub mod onb;
+pub mod distribution;
@@ -0,0 +1,64 @@
+use crate::engine::base::vector::Vector3;
+
+/// An orthonormal basis built around a direction, used to sample directions relative to a surface.
+#[derive(Clone, Copy, Debug)]
+pub struct Onb {
+    pub(crate) u: Vector3,
+    pub(crate) v: Vector3,
+    /// The direction the basis was built around, the local z axis.
+    pub(crate) w: Vector3,
+}
+
+impl Onb {
+    /// Creates an orthonormal basis whose `w` axis points along `n`.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The direction of the local z axis, it doesn't need to be normalized.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Onb`.
+    pub fn new(n: Vector3) -> Self {
+        let w = n.unit_vector();
+        let helper = if w.x.abs() > 0.9 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+        let v = w.cross(&helper).unit_vector();
+        let u = w.cross(&v);
+
+        Self { u, v, w }
+    }
+
+    /// Creates an orthonormal basis whose `w` axis points along `n` and whose `u` axis follows `tangent`,
+    /// so anisotropic materials line up with the surface's parameterization.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The direction of the local z axis, it doesn't need to be normalized.
+    /// * `tangent` - The direction of the local x axis, projected onto the plane perpendicular to `n`.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Onb`, or the one of `Onb::new` when `tangent` is zero or parallel to `n`.
+    pub fn from_tangent(n: Vector3, tangent: Vector3) -> Self {
+        let w = n.unit_vector();
+        let u = tangent - w * w.dot(&tangent);
+        if u.near_zero() {
+            return Self::new(n);
+        }
+        let u = u.unit_vector();
+
+        Self { u, v: w.cross(&u), w }
+    }
+
+    /// Turns local coordinates into a world space vector.
+    #[inline]
+    pub fn transform(&self, local: Vector3) -> Vector3 {
+        self.u * local.x + self.v * local.y + self.w * local.z
+    }
+
+    /// Expresses a world space vector in the basis' local coordinates.
+    #[inline]
+    pub fn to_local(&self, world: Vector3) -> Vector3 {
+        Vector3::new(world.dot(&self.u), world.dot(&self.v), world.dot(&self.w))
+    }
+}
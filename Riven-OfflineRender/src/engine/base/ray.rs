@@ -1,5 +1,6 @@
 use crate::engine::base::point::Point3;
 use crate::engine::base::vector::Vector3;
+use crate::util::spectrum::SampledWavelengths;
 
 
 /// A struct representing a ray in 3D space.
@@ -9,6 +10,9 @@ pub struct Ray {
     pub(crate) origin: Point3,
     /// The direction vector of the ray.
     pub(crate) direction: Vector3,
+    /// The wavelengths the ray carries in spectral mode, or once a dispersive surface has split it.
+    /// RGB rays that carry every wavelength have none.
+    pub(crate) wavelengths: Option<SampledWavelengths>,
 }
 
 impl Ray {
@@ -23,11 +27,26 @@ impl Ray {
     ///
     /// A new instance of `Ray`.
     pub fn new(origin: Point3, direction: Vector3) -> Self {
-        Self { origin, direction }
+        Self { origin, direction, wavelengths: None }
+    }
+
+    /// Creates a new `Ray` carrying some wavelengths.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The origin point of the ray.
+    /// * `direction` - The direction vector of the ray.
+    /// * `wavelengths` - The wavelengths, or `None` for an RGB ray.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Ray`.
+    pub fn with_wavelengths(origin: Point3, direction: Vector3, wavelengths: Option<SampledWavelengths>) -> Self {
+        Self { origin, direction, wavelengths }
     }
 
     pub fn default() -> Self {
-        Self { origin: Point3::default(), direction: Vector3::default() }
+        Self { origin: Point3::default(), direction: Vector3::default(), wavelengths: None }
     }
 
     /// Computes the point at a given distance `t` along the ray.
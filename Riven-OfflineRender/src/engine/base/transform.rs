@@ -0,0 +1,289 @@
+use std::ops::Mul;
+use crate::engine::base::constants::constants::degrees_to_radians;
+use crate::engine::base::interval::Interval;
+use crate::engine::base::point::Point3;
+use crate::engine::base::vector::Vector3;
+use crate::engine::bounding_model::aabb::AABB;
+
+type Matrix4 = [[f32; 4]; 4];
+
+const IDENTITY: Matrix4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// An affine transform stored as a 4x4 matrix alongside its inverse.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    m: Matrix4,
+    inv: Matrix4,
+}
+
+impl Transform {
+    /// Creates a `Transform` from a matrix, computing its inverse.
+    ///
+    /// # Returns
+    ///
+    /// The transform, or `None` when the matrix is singular.
+    pub fn from_matrix(m: Matrix4) -> Option<Transform> {
+        Some(Self { m, inv: invert(&m)? })
+    }
+
+    pub fn identity() -> Transform {
+        Self { m: IDENTITY, inv: IDENTITY }
+    }
+
+    pub fn translate(delta: Vector3) -> Transform {
+        let mut m = IDENTITY;
+        let mut inv = IDENTITY;
+        for axis in 0..3 {
+            m[axis][3] = delta[axis as i32];
+            inv[axis][3] = -delta[axis as i32];
+        }
+        Self { m, inv }
+    }
+
+    /// Scales each axis independently, the factors must not be zero.
+    pub fn scale(x: f32, y: f32, z: f32) -> Transform {
+        assert!(x != 0.0 && y != 0.0 && z != 0.0, "Scale factors must not be zero");
+
+        let mut m = IDENTITY;
+        let mut inv = IDENTITY;
+        for (axis, factor) in [x, y, z].into_iter().enumerate() {
+            m[axis][axis] = factor;
+            inv[axis][axis] = 1.0 / factor;
+        }
+        Self { m, inv }
+    }
+
+    /// Rotates counter-clockwise by `degrees` around an arbitrary `axis`.
+    pub fn rotate(degrees: f32, axis: Vector3) -> Transform {
+        let a = axis.unit_vector();
+        let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+
+        let mut m = IDENTITY;
+        m[0][0] = a.x * a.x + (1.0 - a.x * a.x) * cos;
+        m[0][1] = a.x * a.y * (1.0 - cos) - a.z * sin;
+        m[0][2] = a.x * a.z * (1.0 - cos) + a.y * sin;
+        m[1][0] = a.x * a.y * (1.0 - cos) + a.z * sin;
+        m[1][1] = a.y * a.y + (1.0 - a.y * a.y) * cos;
+        m[1][2] = a.y * a.z * (1.0 - cos) - a.x * sin;
+        m[2][0] = a.x * a.z * (1.0 - cos) - a.y * sin;
+        m[2][1] = a.y * a.z * (1.0 - cos) + a.x * sin;
+        m[2][2] = a.z * a.z + (1.0 - a.z * a.z) * cos;
+
+        // Rotations are orthogonal, the inverse is the transpose
+        Self { m, inv: transpose(&m) }
+    }
+
+    pub fn rotate_x(degrees: f32) -> Transform {
+        Self::rotate(degrees, Vector3::new(1.0, 0.0, 0.0))
+    }
+
+    pub fn rotate_y(degrees: f32) -> Transform {
+        Self::rotate(degrees, Vector3::new(0.0, 1.0, 0.0))
+    }
+
+    pub fn rotate_z(degrees: f32) -> Transform {
+        Self::rotate(degrees, Vector3::new(0.0, 0.0, 1.0))
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Self { m: self.inv, inv: self.m }
+    }
+
+    /// The determinant of the linear part, how much the transform scales volumes.
+    pub fn determinant(&self) -> f32 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Transforms a point, translation applies.
+    #[inline]
+    pub fn point(&self, p: Point3) -> Point3 {
+        let m = &self.m;
+        Point3::new(
+            m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+            m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+            m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+        )
+    }
+
+    /// Transforms a direction, translation doesn't apply.
+    #[inline]
+    pub fn vector(&self, v: Vector3) -> Vector3 {
+        let m = &self.m;
+        Vector3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    /// Transforms a surface normal by the inverse transpose so it stays perpendicular to the surface.
+    #[inline]
+    pub fn normal(&self, n: Vector3) -> Vector3 {
+        let inv = &self.inv;
+        Vector3::new(
+            inv[0][0] * n.x + inv[1][0] * n.y + inv[2][0] * n.z,
+            inv[0][1] * n.x + inv[1][1] * n.y + inv[2][1] * n.z,
+            inv[0][2] * n.x + inv[1][2] * n.y + inv[2][2] * n.z,
+        )
+    }
+
+    /// Computes the box enclosing the eight transformed corners of `bbox`.
+    pub fn bounding_box(&self, bbox: &AABB) -> AABB {
+        let (x, y, z) = (bbox.get_axis_interval(0), bbox.get_axis_interval(1), bbox.get_axis_interval(2));
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+
+        for corner in 0..8 {
+            let p = self.point(Point3::new(
+                if corner & 1 == 0 { x.min } else { x.max },
+                if corner & 2 == 0 { y.min } else { y.max },
+                if corner & 4 == 0 { z.min } else { z.max },
+            ));
+
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis as i32]);
+                max[axis] = max[axis].max(p[axis as i32]);
+            }
+        }
+
+        AABB::from_intervals(
+            Interval::new(min[0], max[0]),
+            Interval::new(min[1], max[1]),
+            Interval::new(min[2], max[2]),
+        )
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    /// Composes two transforms, `a * b` applies `b` first and then `a`.
+    fn mul(self, rhs: Transform) -> Transform {
+        Transform {
+            m: multiply(&self.m, &rhs.m),
+            inv: multiply(&rhs.inv, &self.inv),
+        }
+    }
+}
+
+fn multiply(a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    let mut result = [[0f32; 4]; 4];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    result
+}
+
+fn transpose(m: &Matrix4) -> Matrix4 {
+    let mut result = [[0f32; 4]; 4];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = m[j][i];
+        }
+    }
+    result
+}
+
+/// Inverts a matrix with Gauss-Jordan elimination and partial pivoting.
+fn invert(m: &Matrix4) -> Option<Matrix4> {
+    let mut a = *m;
+    let mut inv = IDENTITY;
+
+    for column in 0..4 {
+        let pivot = (column..4)
+            .max_by(|&r1, &r2| a[r1][column].abs().total_cmp(&a[r2][column].abs()))
+            .unwrap_or(column);
+        if a[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(column, pivot);
+        inv.swap(column, pivot);
+
+        let scale = 1.0 / a[column][column];
+        for j in 0..4 {
+            a[column][j] *= scale;
+            inv[column][j] *= scale;
+        }
+
+        for row in 0..4 {
+            if row == column {
+                continue;
+            }
+            let factor = a[row][column];
+            for j in 0..4 {
+                a[row][j] -= factor * a[column][j];
+                inv[row][j] -= factor * inv[column][j];
+            }
+        }
+    }
+
+    Some(inv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn transform_and_inverse_round_trip() {
+        let t = Transform::translate(Vector3::new(1.0, 2.0, 3.0))
+            * Transform::rotate(30.0, Vector3::new(1.0, 1.0, 0.0))
+            * Transform::scale(2.0, 0.5, 4.0);
+        let p = Point3::new(0.3, -1.2, 5.0);
+
+        let back = t.inverse().point(t.point(p));
+        assert_close(back.x, p.x);
+        assert_close(back.y, p.y);
+        assert_close(back.z, p.z);
+    }
+
+    #[test]
+    fn from_matrix_matches_builders() {
+        let t = Transform::rotate_y(45.0) * Transform::scale(1.0, 3.0, 2.0);
+        let from_matrix = Transform::from_matrix(t.m).unwrap();
+
+        for i in 0..4 {
+            for j in 0..4 {
+                assert_close(from_matrix.inv[i][j], t.inv[i][j]);
+            }
+        }
+        assert!(Transform::from_matrix([[0.0; 4]; 4]).is_none());
+    }
+
+    #[test]
+    fn rotate_z_turns_x_into_y() {
+        let v = Transform::rotate_z(90.0).vector(Vector3::new(1.0, 0.0, 0.0));
+        assert_close(v.x, 0.0);
+        assert_close(v.y, 1.0);
+        assert_close(v.z, 0.0);
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        let t = Transform::scale(4.0, 1.0, 1.0);
+        let tangent = Vector3::new(1.0, -1.0, 0.0);
+        let normal = Vector3::new(1.0, 1.0, 0.0);
+
+        assert_close(t.vector(tangent).dot(&t.normal(normal)), 0.0);
+    }
+}
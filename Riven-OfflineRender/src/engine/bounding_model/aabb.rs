@@ -48,7 +48,7 @@ impl AABB{
             zl = z.expand(delta);
         }
 
-        return (x, y, z);
+        return (xl, yl, zl);
     }
 
     pub fn from_points(point1: Point3, point2 : Point3) -> Self {
@@ -90,6 +90,23 @@ impl AABB{
         }
     }
 
+    /// Surface area of the box, the cost metric of the SAH builder.
+    pub fn surface_area(&self) -> f32 {
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        if dx < 0.0 || dy < 0.0 || dz < 0.0 {
+            return 0.0; // Empty box
+        }
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::new(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max)
+        )
+    }
+
     pub fn hit(&self, ray : &Ray,  ray_t: &mut Interval) -> bool{
         for axis in 0..3{
             let ax = self.get_axis_interval(axis);
@@ -4,139 +4,504 @@ use crate::engine::base::interval::Interval;
 use crate::engine::base::ray::Ray;
 use crate::engine::bounding_model::aabb::AABB;
 use crate::engine::objects::hit_record::HitRecord;
-use crate::engine::objects::object::{GeometricObject, HitList};
+use crate::engine::objects::object::{GeometricObject, HitList, SHADOW_EPSILON};
 use crate::engine::objects::Objects;
 use crate::engine::objects::Objects::BVH;
 
+/// Number of centroid buckets the SAH builder evaluates splits between.
+const SAH_BIN_COUNT: usize = 16;
+/// Below this many objects a subtree is cheaper to build on the current thread.
+const PARALLEL_BUILD_THRESHOLD: usize = 4096;
+/// Deepest tree the traversal stack can hold.
+const MAX_DEPTH: usize = 64;
+/// Past this depth the builder halves the objects so the tree never outgrows the traversal stack.
+const SAH_MAX_DEPTH: usize = MAX_DEPTH / 2;
+
+/// A bounding volume hierarchy flattened into a depth-first array of nodes.
+///
+/// The first child of an interior node is stored right after it, so only the second child's index
+/// is kept. Leaves point at a run of `primitives`.
+#[derive(Clone)]
+pub struct Bvh {
+    nodes : Vec<LinearNode>,
+    primitives : Vec<Objects>,
+}
+
+/// A node of the flattened tree, 32 bytes so two of them fill a cache line.
+#[derive(Clone, Copy)]
+#[repr(C, align(32))]
+struct LinearNode {
+    min : [f32; 3],
+    /// Index of the first primitive for a leaf, of the second child for an interior node.
+    offset : u32,
+    max : [f32; 3],
+    /// Number of primitives, zero for an interior node.
+    count : u16,
+    /// Axis the node was split along, picks which child is nearer to the ray.
+    axis : u16,
+}
+
+/// The tree the builders produce before it's flattened.
+enum BuildNode {
+    Leaf(Objects),
+    Interior {
+        bbox : AABB,
+        axis : usize,
+        left : Box<BuildNode>,
+        right : Box<BuildNode>,
+    },
+}
+
+/// A SAH bucket, the objects whose centroid falls in a slice of the centroid bounds.
 #[derive(Clone)]
-pub struct BvhNode{
-    left : Objects,
-    right : Objects,
-    bbox : AABB
+struct Bin {
+    bbox : AABB,
+    count : usize,
 }
 
-impl BvhNode{
+impl Bvh{
 
+    /// Builds a BVH over every object of the world with the binned surface area heuristic.
+    ///
+    /// # Arguments
+    ///
+    /// * `hit_list` - The objects to build over, moved into the tree without being cloned.
+    ///
+    /// # Returns
+    ///
+    /// The flattened tree wrapped as an object.
     pub fn from_world(hit_list: HitList) -> Objects {
-        BVH(Box::new(
-            Self::bvh_new_node(hit_list.objects.to_owned(), 0, hit_list.objects.len())
-        ))
+        assert!(!hit_list.objects.is_empty(), "Can't build a BVH over an empty world");
+        Self::flatten(Self::build(hit_list.objects, 0))
     }
 
-    pub fn bvh_new_node(mut objects: Vec<Objects>, start: usize, end: usize) -> BvhNode {
-        let axis = ranged_random_int(0, 3);
+    /// The objects in the tree's leaves.
+    pub(crate) fn primitives(&self) -> impl Iterator<Item = &Objects> {
+        self.primitives.iter()
+    }
+
+    /// The objects in the tree's leaves, they can be changed as long as their bounds stay the same.
+    pub(crate) fn primitives_mut(&mut self) -> impl Iterator<Item = &mut Objects> {
+        self.primitives.iter_mut()
+    }
 
-        let comparator = match axis {
-            0 => |a: &Objects, b: &Objects| {
-                Self::box_compare(a, b, 0)
-            },
+    /// Builds a BVH by splitting a random axis at the median, the builder used before the SAH one.
+    ///
+    /// It's only kept to benchmark `from_world` against.
+    pub fn from_world_random_split(hit_list: HitList) -> Objects {
+        assert!(!hit_list.objects.is_empty(), "Can't build a BVH over an empty world");
+        Self::flatten(Self::build_random_split(hit_list.objects))
+    }
+
+    fn build(mut objects: Vec<Objects>, depth: usize) -> BuildNode {
+        if objects.len() == 1 {
+            return BuildNode::Leaf(objects.pop().unwrap());
+        }
 
-            1 => |a: &Objects, b: &Objects| {
-                Self::box_compare(a, b, 1)
-            },
+        let bboxes: Vec<AABB> = objects.iter().map(|object| object.bounding_box()).collect();
+        let centroids: Vec<[f32; 3]> = bboxes.iter()
+            .map(|bbox| {
+                let c = bbox.centroid();
+                [c[0], c[1], c[2]]
+            })
+            .collect();
 
-            _ => |a: &Objects, b: &Objects| {
-                Self::box_compare(a, b, 2)
-            },
+        let split = if depth < SAH_MAX_DEPTH { Self::find_sah_split(&centroids, &bboxes) } else { None };
+        let (axis, goes_left) = match split {
+            Some((axis, min, scale, split)) => (axis, centroids.iter()
+                .map(|c| Self::bin_index(c[axis], min, scale) <= split)
+                .collect::<Vec<bool>>()),
+            // Every centroid is in the same spot, or the tree is already deep, halve the list instead
+            None => (0, (0..objects.len()).map(|i| i < objects.len() / 2).collect()),
         };
-        let object_span = end - start;
 
+        let mut left_objects = Vec::with_capacity(objects.len());
+        let mut right_objects = Vec::with_capacity(objects.len());
+        for (object, left) in objects.into_iter().zip(goes_left) {
+            if left { left_objects.push(object) } else { right_objects.push(object) }
+        }
 
-        let (left, right) = if object_span == 1 {
-            // If there's only one object, both children point to the same object
-            (objects[start].to_owned(),objects[start].to_owned())
-        } else if object_span == 2 {
-            // If there are two objects, the left and right children each take one
-            (objects[start].to_owned() , objects[start + 1].to_owned())
+        let (left, right) = if left_objects.len() + right_objects.len() >= PARALLEL_BUILD_THRESHOLD {
+            rayon::join(|| Self::build(left_objects, depth + 1), || Self::build(right_objects, depth + 1))
         } else {
-            // Sort objects by bounding box along the specified axis
-            objects[start..end].sort_by(comparator);
+            (Self::build(left_objects, depth + 1), Self::build(right_objects, depth + 1))
+        };
+
+        BuildNode::interior(axis, left, right)
+    }
 
-            // Split the sorted objects in half and create child nodes
-            let mid = start + object_span / 2;
-            let left = BVH(Box::new(BvhNode::bvh_new_node(objects.to_owned(), start, mid)));
-            let right = BVH(Box::new(BvhNode::bvh_new_node(objects, mid, end)));
+    #[inline]
+    fn bin_index(centroid : f32, min : f32, scale : f32) -> usize {
+        (((centroid - min) * scale) as usize).min(SAH_BIN_COUNT - 1)
+    }
 
-            (left, right)
-        };
+    /// Finds the cheapest split over all three axes.
+    ///
+    /// # Returns
+    ///
+    /// The axis, the binning parameters `(min, scale)` and the last bin going left,
+    /// or `None` when the centroids can't be separated.
+    fn find_sah_split(centroids : &[[f32; 3]], bboxes : &[AABB]) -> Option<(usize, f32, f32, usize)> {
+        let mut best : Option<(usize, f32, f32, usize)> = None;
+        let mut best_cost = f32::INFINITY;
 
+        for axis in 0..3 {
+            let min = centroids.iter().map(|c| c[axis]).fold(f32::INFINITY, f32::min);
+            let max = centroids.iter().map(|c| c[axis]).fold(f32::NEG_INFINITY, f32::max);
+            if max - min <= f32::EPSILON * max.abs().max(1.0) {
+                continue;
+            }
+            let scale = SAH_BIN_COUNT as f32 / (max - min);
+
+            let mut bins = vec![Bin { bbox: AABB::default(), count: 0 }; SAH_BIN_COUNT];
+            for (centroid, bbox) in centroids.iter().zip(bboxes.iter()) {
+                let bin = &mut bins[Self::bin_index(centroid[axis], min, scale)];
+                bin.bbox = AABB::from_aabb(bin.bbox.clone(), bbox.clone());
+                bin.count += 1;
+            }
+
+            // Sweep from the right so each split's right side cost is known up front
+            let mut right_costs = [0f32; SAH_BIN_COUNT];
+            let mut right_box = AABB::default();
+            let mut right_count = 0;
+            for split in (1..SAH_BIN_COUNT).rev() {
+                right_box = AABB::from_aabb(right_box, bins[split].bbox.clone());
+                right_count += bins[split].count;
+                right_costs[split - 1] = right_count as f32 * right_box.surface_area();
+            }
+
+            let mut left_box = AABB::default();
+            let mut left_count = 0;
+            for split in 0..SAH_BIN_COUNT - 1 {
+                left_box = AABB::from_aabb(left_box, bins[split].bbox.clone());
+                left_count += bins[split].count;
+                if left_count == 0 || left_count == centroids.len() {
+                    continue;
+                }
+
+                let cost = left_count as f32 * left_box.surface_area() + right_costs[split];
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = Some((axis, min, scale, split));
+                }
+            }
+        }
+
+        best
+    }
+
+    fn build_random_split(mut objects: Vec<Objects>) -> BuildNode {
+        if objects.len() == 1 {
+            return BuildNode::Leaf(objects.pop().unwrap());
+        }
+
+        let axis = ranged_random_int(0, 3);
+        objects.sort_by(|a, b| Self::box_compare(a, b, axis));
+
+        let right_objects = objects.split_off(objects.len() / 2);
+        let left = Self::build_random_split(objects);
+        let right = Self::build_random_split(right_objects);
+
+        BuildNode::interior(axis as usize, left, right)
+    }
 
-        Self {
-            bbox: AABB::from_aabb(left.bounding_box(), right.bounding_box()),
-            left,
-            right,
-        }
-    }
-    //
-    // pub fn box_compare(&self, bbox1 : AABB, bbox2 : AABB, axis : i32) -> bool {
-    //     let box1_axis = bbox1.get_axis_interval(axis);
-    //     let box2_axis = bbox2.get_axis_interval(axis);
-    //
-    //     return box1_axis.min < box2_axis.min
-    // }
-    //
-    // fn box_x_compare(&self, bbox1 : AABB, bbox2 : AABB) -> bool {
-    //     return self.box_compare(bbox1, bbox2, 0)
-    // }
-    //
     fn box_compare(bbox1 : &Objects, bbox2 : &Objects, axis : i32) -> Ordering {
         bbox1.bounding_box().get_axis_interval(axis).min
                 .partial_cmp(&bbox2.bounding_box().get_axis_interval(axis).min)
                 .unwrap()
     }
-    //
-    // fn box_z_compare(&self, bbox1 : AABB, bbox2 : AABB) -> bool {
-    //     return self.box_compare(bbox1, bbox2, 2)
-    // }
 
+    fn flatten(root: BuildNode) -> Objects {
+        let mut bvh = Self { nodes: Vec::new(), primitives: Vec::new() };
+        bvh.push(root);
+        BVH(Box::new(bvh))
+    }
+
+    /// Appends a subtree in depth-first order, returning the index of its root.
+    fn push(&mut self, node: BuildNode) -> usize {
+        let index = self.nodes.len();
+
+        match node {
+            BuildNode::Leaf(object) => {
+                self.nodes.push(LinearNode::new(&object.bounding_box(), self.primitives.len(), 1, 0));
+                self.primitives.push(object);
+            }
+            BuildNode::Interior { bbox, axis, left, right } => {
+                // The second child's index isn't known yet, it's patched in once the first child is in
+                self.nodes.push(LinearNode::new(&bbox, 0, 0, axis));
+                self.push(*left);
+                let second = self.push(*right);
+                self.nodes[index].offset = second as u32;
+            }
+        }
+
+        index
+    }
+}
+
+impl BuildNode {
+    fn interior(axis: usize, left: BuildNode, right: BuildNode) -> BuildNode {
+        BuildNode::Interior {
+            bbox: AABB::from_aabb(left.bounding_box(), right.bounding_box()),
+            axis,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        match self {
+            BuildNode::Leaf(object) => object.bounding_box(),
+            BuildNode::Interior { bbox, .. } => bbox.clone(),
+        }
+    }
+}
+
+impl LinearNode {
+    fn new(bbox: &AABB, offset: usize, count: u16, axis: usize) -> Self {
+        let (x, y, z) = (bbox.get_axis_interval(0), bbox.get_axis_interval(1), bbox.get_axis_interval(2));
+        Self {
+            min: [x.min, y.min, z.min],
+            offset: offset as u32,
+            max: [x.max, y.max, z.max],
+            count,
+            axis: axis as u16,
+        }
+    }
+
+    /// Slab test against precomputed inverse directions.
+    #[inline]
+    fn hit(&self, origin: &[f32; 3], inv_direction: &[f32; 3], t_min: f32, t_max: f32) -> bool {
+        let (mut t_min, mut t_max) = (t_min, t_max);
+        for axis in 0..3 {
+            let t0 = (self.min[axis] - origin[axis]) * inv_direction[axis];
+            let t1 = (self.max[axis] - origin[axis]) * inv_direction[axis];
+            let (near, far) = if inv_direction[axis] < 0.0 { (t1, t0) } else { (t0, t1) };
 
+            t_min = if near > t_min { near } else { t_min };
+            t_max = if far < t_max { far } else { t_max };
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
 }
 
-impl GeometricObject for BvhNode{
+impl GeometricObject for Bvh{
     fn hit(&self, ray: &Ray, ray_t: &mut Interval, rec: &mut HitRecord) -> bool {
+        let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+        let inv_direction = [1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z];
+        let direction_is_negative = inv_direction.map(|inv| inv < 0.0);
+
+        let mut hit_anything = false;
+        let mut closest_so_far = ray_t.max;
+
+        let mut stack = [0usize; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut current = 0;
 
-        if !self.bbox.hit(ray, ray_t) {
-            return false
+        loop {
+            let node = &self.nodes[current];
+
+            if node.hit(&origin, &inv_direction, ray_t.min, closest_so_far) {
+                if node.count > 0 {
+                    // Primitives only write to the record on a hit, and every hit is closer than the last
+                    let first = node.offset as usize;
+                    for primitive in &self.primitives[first..first + node.count as usize] {
+                        if primitive.hit(ray, &mut Interval::new(ray_t.min, closest_so_far), rec) {
+                            hit_anything = true;
+                            closest_so_far = rec.t;
+                        }
+                    }
+                } else {
+                    // Visit the child on the ray's side of the split first, its hits prune the other one
+                    let (near, far) = if direction_is_negative[node.axis as usize] {
+                        (node.offset as usize, current + 1)
+                    } else {
+                        (current + 1, node.offset as usize)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    current = near;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
         }
 
-        let mut hit_left : bool = false;
-        let mut hit_right : bool = false;
+        hit_anything
+    }
 
-        let mut temp_rec = HitRecord::default();
+    fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+        let inv_direction = [1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z];
 
-       if self.left.hit(ray, ray_t, &mut temp_rec){
-            rec.point = temp_rec.point;
-            rec.normal = temp_rec.normal;
-            rec.t = temp_rec.t;
-            rec.front_face = temp_rec.front_face;
-            rec.mat = temp_rec.mat.to_owned();
-            rec.u = temp_rec.u;
-            rec.v = temp_rec.v.abs();
+        let mut stack = [0usize; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut current = 0;
 
-           hit_left = true;
-        };
+        loop {
+            let node = &self.nodes[current];
 
-        if self.right.hit(
-            ray,
-            &mut Interval::new( ray_t.min, if hit_left { rec.t } else { ray_t.max }),
-            &mut temp_rec
-        ){
-            rec.point = temp_rec.point;
-            rec.normal = temp_rec.normal;
-            rec.t = temp_rec.t;
-            rec.front_face = temp_rec.front_face;
-            rec.mat = temp_rec.mat.to_owned();
-            rec.u = temp_rec.u;
-            rec.v = temp_rec.v.abs();
-
-            hit_right = true
-        };
+            if node.hit(&origin, &inv_direction, SHADOW_EPSILON, t_max) {
+                if node.count > 0 {
+                    // Any blocker will do, so there's no closest hit to keep track of
+                    let first = node.offset as usize;
+                    if self.primitives[first..first + node.count as usize].iter().any(|primitive| primitive.occluded(ray, t_max)) {
+                        return true;
+                    }
+                } else {
+                    stack[stack_len] = node.offset as usize;
+                    stack_len += 1;
+                    current += 1;
+                    continue;
+                }
+            }
 
-        hit_right || hit_left
+            if stack_len == 0 {
+                return false;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
+    }
+
+    fn transmittance(&self, ray: &Ray, t_max: f32) -> f32 {
+        let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+        let inv_direction = [1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z];
+
+        let mut transmittance = 1.0;
+        let mut stack = [0usize; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut current = 0;
+
+        loop {
+            let node = &self.nodes[current];
+
+            if node.hit(&origin, &inv_direction, SHADOW_EPSILON, t_max) {
+                if node.count > 0 {
+                    // Every primitive along the ray dims the light, until one blocks it completely
+                    let first = node.offset as usize;
+                    for primitive in &self.primitives[first..first + node.count as usize] {
+                        transmittance *= primitive.transmittance(ray, t_max);
+                        if transmittance <= 0.0 {
+                            return 0.0;
+                        }
+                    }
+                } else {
+                    stack[stack_len] = node.offset as usize;
+                    stack_len += 1;
+                    current += 1;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                return transmittance;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
     }
 
     fn bounding_box(&self) -> AABB {
-         self.bbox.to_owned()
+        let root = &self.nodes[0];
+        AABB::from_intervals(
+            Interval::new(root.min[0], root.max[0]),
+            Interval::new(root.min[1], root.max[1]),
+            Interval::new(root.min[2], root.max[2]),
+        )
     }
 }
 
+#[cfg(test)]
+mod test_bvh {
+    use crate::engine::base::constants::constants;
+    use crate::engine::base::interval::Interval;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::transform::Transform;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::bounding_model::bvh::{Bvh, LinearNode};
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::objects::instance::Instance;
+    use crate::engine::objects::object::HitList;
+    use crate::engine::objects::quad::Quad;
+    use crate::engine::objects::sphere::Sphere;
+
+    #[test]
+    fn linear_nodes_are_32_bytes(){
+        assert_eq!(std::mem::size_of::<LinearNode>(), 32);
+        assert_eq!(std::mem::align_of::<LinearNode>(), 32);
+    }
+
+    #[test]
+    fn bvh_finds_same_hits_as_list(){
+        let mut world = HitList::new();
+        for i in 0..16 {
+            let center = Point3::new(i as f32 * 3.0, (i % 4) as f32, (i / 4) as f32 * 2.0);
+            world.add(Sphere::new(center, 1.0, MaterialId::default()));
+        }
+        let bvh = Bvh::from_world(world.clone());
+        let random_split = Bvh::from_world_random_split(world.clone());
+        let list = crate::engine::objects::Objects::List(world);
+
+        for i in 0..64 {
+            // Sweep both ways along x so both child orders get traversed
+            let (x, dx) = if i % 2 == 0 { (-5.0, 1.0) } else { (50.0, -1.0) };
+            let ray = Ray::new(
+                Point3::new(x, 0.5, 0.9),
+                Vector3::new(dx, (i as f32 / 64.0 - 0.5) * 0.2, (i % 8) as f32 * 0.02)
+            );
+            let mut list_rec = HitRecord::default();
+
+            let list_hit = list.hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut list_rec);
+
+            for tree in [&bvh, &random_split] {
+                let mut bvh_rec = HitRecord::default();
+                let bvh_hit = tree.hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut bvh_rec);
+
+                assert_eq!(list_hit, bvh_hit);
+                if list_hit {
+                    assert!((list_rec.t - bvh_rec.t).abs() < 1e-5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn occluded_agrees_with_closest_hit(){
+        let mut world = HitList::new();
+        for i in 0..16 {
+            let center = Point3::new(i as f32 * 3.0, (i % 4) as f32, (i / 4) as f32 * 2.0);
+            world.add(Sphere::new(center, 1.0, MaterialId::default()));
+        }
+        let cube = std::sync::Arc::new(Quad::make_box(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0), MaterialId::default()));
+        world.add(Instance::new(cube, Transform::translate(Vector3::new(20.0, 4.0, 3.0)) * Transform::rotate_y(30.0)));
+
+        let bvh = Bvh::from_world(world.clone());
+        let list = crate::engine::objects::Objects::List(world);
+
+        for i in 0..256 {
+            let ray = Ray::new(
+                Point3::new(-5.0, 0.5 + (i % 16) as f32 * 0.3, 0.9),
+                Vector3::new(1.0, (i as f32 / 256.0 - 0.5) * 0.3, (i % 8) as f32 * 0.02)
+            );
+            // Shadow rays ending before, inside and past the first object
+            let t_max = 2.0 + (i % 5) as f32 * 6.0;
+
+            let mut rec = HitRecord::default();
+            let blocked = list.hit(&ray, &mut Interval::new(0.0001, t_max), &mut rec);
+
+            assert_eq!(blocked, list.occluded(&ray, t_max));
+            assert_eq!(blocked, bvh.occluded(&ray, t_max));
+        }
+    }
+}
@@ -1,11 +1,10 @@
 use rayon::prelude::*;
 use crate::engine::base::constants::constants;
-use crate::engine::base::interval::Interval;
 use crate::engine::base::point::Point3;
 use crate::engine::base::ray::Ray;
 use crate::engine::base::vector::Vector3;
-use crate::engine::objects::hit_record::HitRecord;
-use crate::engine::objects::Objects;
+use crate::engine::scene::Scene;
+use crate::engine::tracers::{Tracer, TracerTypes};
 use crate::util::color::Color;
 use crate::util::image::Canvas;
 
@@ -30,8 +29,6 @@ pub struct RGBCamera {
     pixel_delta_v: Vector3,
     /// The scale factor for the sum of pixel samples.
     pub pixel_sample_scale: f32,
-    /// Maximum number of ray bounces into scene
-    pub max_depth: i32,
     /// Vertical view angle (field of view)
     pub vfov: f32,
     pub vup : Vector3, // camera relative up direction
@@ -39,6 +36,8 @@ pub struct RGBCamera {
     pub look_at : Point3, // Point camera looking at
     pub defocus_angle : f32, // Defocus blur angle
     pub focus_dist : f32,
+    /// The integrator computing the radiance along each camera ray
+    pub tracer : TracerTypes,
     u : Vector3, v : Vector3, w : Vector3, // camera basis frame vector
     defocus_disk_u : Vector3,
     defocus_disk_v : Vector3
@@ -84,40 +83,6 @@ impl RGBCamera {
         self.initialize()
     }
 
-    /// Computes the color of a ray based on its interaction with the world.
-    ///
-    /// # Arguments
-    ///
-    /// * `ray` - The ray to be traced.
-    /// * `world` - The world containing objects to be hit by the ray.
-    ///
-    /// # Returns
-    ///
-    /// A `Color` representing the color of the ray.
-    pub fn ray_color(ray: &Ray, world: &Objects, depth : i32) -> Color {
-        if depth <= 0 {
-            return Color::default();
-        }
-
-        let mut rec = HitRecord::default();
-        // The interval is used to avoid floating point approximation
-        if world.hit(ray, &mut Interval::new(0.0001f32, constants::INFINITY), &mut rec) {
-            let mut scatter_ray = Ray::default();
-            let mut attenuation = Color::default();
-
-            if rec.mat.scatter(&ray, &mut scatter_ray, &rec, &mut attenuation) {
-                return attenuation * Self::ray_color(&scatter_ray, world, depth - 1);
-            }
-
-            return Color::default()
-        }
-
-        // Background color
-        let unite_direction = ray.direction.unit_vector();
-        let a = (1f32 + unite_direction.y) * 0.5;
-        (1f32 - a) * Color::new(1f32, 1f32, 1f32) + a * Color::new(0.5, 0.7, 1.0)
-    }
-
     /// Samples a random point within a unit square.
     ///
     /// # Returns
@@ -152,13 +117,13 @@ impl RGBCamera {
         Ray::new(ray_origin, ray_direction)
     }
 
-    /// Renders the scene by tracing rays through each pixel and computing the color.
+    /// Renders the scene by tracing rays through each pixel with the camera's tracer.
     ///
     /// # Arguments
     ///
-    /// * `world` - The world containing objects to be hit by the rays.
+    /// * `scene` - The objects to be hit by the rays and their materials.
     /// * `canvas` - The canvas to write the pixel colors to.
-    pub fn render(&mut self, world: &Objects, mut canvas: Canvas) {
+    pub fn render(&mut self, scene: &Scene, mut canvas: Canvas) {
         self.initialize();
 
         // Compute time taken to render
@@ -172,7 +137,7 @@ impl RGBCamera {
 
                 for _ in 0..self.samples_per_pixel {
                     let ray = self.get_ray(x, y);
-                    pixel_color = pixel_color + Self::ray_color(&ray, world, self.max_depth);
+                    pixel_color = pixel_color + self.tracer.trace_ray(&ray, scene);
                 }
 
                 pixel_color = self.pixel_sample_scale * pixel_color;
@@ -203,13 +168,13 @@ impl Default for RGBCamera {
             pixel_delta_u: Vector3::default(),
             pixel_delta_v: Vector3::default(),
             pixel_sample_scale: 1.0,
-            max_depth : 10,
             vfov: 1.0,
             vup: Default::default(),
             look_from: Default::default(),
             look_at: Default::default(),
             defocus_angle: 0.0,
             focus_dist: 10.0,
+            tracer: TracerTypes::default(),
             u: Default::default(),
             v: Default::default(),
             w: Default::default(),
@@ -0,0 +1,56 @@
+use crate::engine::base::ray::Ray;
+use crate::engine::lighting::environment_map::EnvironmentMap;
+use crate::util::color::Color;
+
+/// The radiance a ray picks up when it leaves the scene without hitting anything.
+#[derive(Clone)]
+pub enum Background {
+    /// A vertical blend from `horizon` (looking down) to `zenith` (looking up).
+    Gradient { horizon: Color, zenith: Color },
+    /// The same color in every direction, black turns the background off as a light source.
+    Solid(Color),
+    /// An HDR image wrapped around the scene, which the path tracer also samples as a light.
+    Environment(EnvironmentMap),
+}
+
+impl Background {
+    /// The blue and white sky Riven has always rendered with.
+    pub fn sky() -> Self {
+        Background::Gradient {
+            horizon: Color::new(1.0, 1.0, 1.0),
+            zenith: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+
+    /// No light from the background, for interiors lit only by emissive materials.
+    pub fn black() -> Self {
+        Background::Solid(Color::default())
+    }
+
+    /// Computes the background color seen along a ray.
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - The ray that escaped the scene.
+    ///
+    /// # Returns
+    ///
+    /// A `Color` representing the background radiance.
+    pub fn value(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Gradient { horizon, zenith } => {
+                let unit_direction = ray.direction.unit_vector();
+                let a = (1f32 + unit_direction.y) * 0.5;
+                (1f32 - a) * *horizon + a * *zenith
+            }
+            Background::Solid(color) => *color,
+            Background::Environment(map) => map.value(ray.direction),
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::sky()
+    }
+}
@@ -0,0 +1,280 @@
+use crate::engine::base::point::Point3;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::textures::{Texture, TextureType};
+use crate::util::color::Color;
+
+/// A material shaded as if its surface had detail the geometry doesn't, like bricks, scratches or bark.
+///
+/// The shading normal is perturbed by a tangent-space normal map or by a scalar bump map, before the
+/// wrapped material scatters. Light still can't cross the true surface: directions on opposite sides
+/// of the geometric and the shading normal are absorbed, and a shading normal tipped below the surface
+/// is bent back above it. Surfaces without texture coordinates, and so without tangents, are left flat.
+#[derive(Clone)]
+pub struct Bump {
+    base: Box<MaterialType>,
+    perturbation: Perturbation,
+}
+
+#[derive(Clone)]
+enum Perturbation {
+    /// Normals along `dpdu`, `dpdv` and the surface normal, mapped from `[-1, 1]` to the red, green and blue channels.
+    NormalMap(TextureType),
+    /// Heights above the surface, read from the texture's red channel and multiplied by `scale`.
+    Height { height: TextureType, scale: f32 },
+}
+
+impl Bump {
+    /// The lowest cosine the shading normal may make with the geometric one.
+    const MIN_COSINE: f32 = 0.01;
+    /// The step in texture coordinates over which bump maps are differentiated.
+    const DELTA: f32 = 0.0005;
+
+    /// Creates a material whose shading normals are read from a tangent-space normal map.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The material being shaded.
+    /// * `normal_map` - The normal map, usually an `ImageTexture` in which flat is `(0.5, 0.5, 1.0)`.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Bump`.
+    pub fn normal_map(base: MaterialType, normal_map: TextureType) -> MaterialType {
+        MaterialType::Bump(Bump {
+            base: Box::new(base),
+            perturbation: Perturbation::NormalMap(normal_map),
+        })
+    }
+
+    /// Creates a material whose shading normals follow the slopes of a height field.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The material being shaded.
+    /// * `height` - The height field, read from the red channel, any texture like a `NoiseTexture` will do.
+    /// * `scale` - The height, in scene units, of a texture value of one.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Bump`.
+    pub fn height_map(base: MaterialType, height: TextureType, scale: f32) -> MaterialType {
+        MaterialType::Bump(Bump {
+            base: Box::new(base),
+            perturbation: Perturbation::Height { height, scale },
+        })
+    }
+
+    /// Perturbs the normal of a hit.
+    ///
+    /// # Returns
+    ///
+    /// The shading normal and the geometric normal, both on the side the ray came from.
+    /// Nothing when the hit has no tangents.
+    fn shading_normal(&self, hit_record: &HitRecord) -> Option<(Vector3, Vector3)> {
+        let geometric = hit_record.dpdu.cross(&hit_record.dpdv);
+        if geometric.near_zero() {
+            return None;
+        }
+        let geometric = geometric.unit_vector();
+        let geometric = if geometric.dot(&hit_record.normal) < 0.0 { -geometric } else { geometric };
+
+        // Maps describe the outside of the surface, the normal is faced like the hit's afterwards
+        let outward = if hit_record.front_face { hit_record.normal } else { -hit_record.normal };
+        let tangent = hit_record.dpdu - outward * outward.dot(&hit_record.dpdu);
+        let bitangent = hit_record.dpdv - outward * outward.dot(&hit_record.dpdv);
+        if tangent.near_zero() || bitangent.near_zero() {
+            return None;
+        }
+
+        let (u, v, point) = (hit_record.u, hit_record.v, hit_record.point);
+        let perturbed = match &self.perturbation {
+            Perturbation::NormalMap(map) => {
+                let value = map.value(u, v, point);
+                let tangent = tangent.unit_vector();
+                let side = outward.cross(&tangent);
+                let side = if side.dot(&bitangent) < 0.0 { -side } else { side };
+                tangent * (2.0 * value.r - 1.0) + side * (2.0 * value.g - 1.0) + outward * (2.0 * value.b - 1.0)
+            }
+            Perturbation::Height { height, scale } => {
+                let at = |du: f32, dv: f32| {
+                    let shifted = point + hit_record.dpdu * du + hit_record.dpdv * dv;
+                    scale * height.value(u + du, v + dv, shifted).r
+                };
+                let base = at(0.0, 0.0);
+                let slope_u = (at(Self::DELTA, 0.0) - base) / Self::DELTA;
+                let slope_v = (at(0.0, Self::DELTA) - base) / Self::DELTA;
+
+                // The displaced surface's tangents, crossed
+                let normal = (tangent + outward * slope_u).cross(&(bitangent + outward * slope_v));
+                if normal.dot(&outward) < 0.0 { -normal } else { normal }
+            }
+        };
+        if perturbed.near_zero() {
+            return None;
+        }
+
+        let perturbed = perturbed.unit_vector();
+        let mut shading = if hit_record.front_face { perturbed } else { -perturbed };
+        let cosine = shading.dot(&geometric);
+        if cosine < Self::MIN_COSINE {
+            shading = (shading + geometric * (Self::MIN_COSINE - cosine)).unit_vector();
+        }
+        Some((shading, geometric))
+    }
+
+    /// The hit as the wrapped material sees it, and its geometric normal.
+    fn shade(&self, hit_record: &HitRecord) -> (HitRecord, Vector3) {
+        let (normal, geometric) = self.shading_normal(hit_record).unwrap_or((hit_record.normal, hit_record.normal));
+        (HitRecord { normal, ..*hit_record }, geometric)
+    }
+}
+
+impl DiffuseMaterial for Bump {
+    fn scatter(&self, ray_in: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
+        let (shaded, geometric) = self.shade(hit_record);
+        if !self.base.scatter(ray_in, scattered_ray, &shaded, attenuation) {
+            return false;
+        }
+        same_side(&scattered_ray.direction, &shaded.normal, &geometric)
+    }
+
+    fn emitted(&self, u: f32, v: f32, point: Point3) -> Color {
+        self.base.emitted(u, v, point)
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> Color {
+        let (shaded, geometric) = self.shade(hit_record);
+        if !same_side(direction, &shaded.normal, &geometric) {
+            return Color::default();
+        }
+        self.base.eval(ray_in, &shaded, direction)
+    }
+
+    fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> f32 {
+        let (shaded, _) = self.shade(hit_record);
+        self.base.pdf(ray_in, &shaded, direction)
+    }
+
+    fn is_specular(&self) -> bool {
+        self.base.is_specular()
+    }
+
+    fn clone_box(&self) -> MaterialType {
+        MaterialType::Bump(self.clone())
+    }
+}
+
+/// Whether a direction leaves on the same side of the shading normal as of the true surface.
+fn same_side(direction: &Vector3, shading: &Vector3, geometric: &Vector3) -> bool {
+    direction.dot(shading) * direction.dot(geometric) > 0.0
+}
+
+#[cfg(test)]
+mod test_bump {
+    use crate::engine::base::constants::constants::INFINITY;
+    use crate::engine::base::interval::Interval;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::bump::Bump;
+    use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
+    use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+    use crate::engine::loaders::obj_loader::load_obj;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::objects::object::{GeometricObject, HitList};
+    use crate::engine::textures::solid_color::SolidColor;
+
+    fn floor_hit(front_face: bool) -> HitRecord {
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        HitRecord {
+            normal: if front_face { up } else { -up },
+            front_face,
+            dpdu: Vector3::new(2.0, 0.0, 0.0),
+            dpdv: Vector3::new(0.0, 0.0, -3.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normal_maps_tilt_the_shading_normal(){
+        let tilt = 0.5 + 0.5 * std::f32::consts::FRAC_1_SQRT_2;
+        let MaterialType::Bump(bump) = Bump::normal_map(Lambertian::new(0.5, 0.5, 0.5), SolidColor::from_rgb(tilt, 0.5, tilt)) else {
+            panic!("not a bump material");
+        };
+
+        // Halfway between the surface normal and dpdu, seen from either side
+        let expected = Vector3::new(1.0, 1.0, 0.0).unit_vector();
+        let (shading, _) = bump.shading_normal(&floor_hit(true)).unwrap();
+        assert!((shading - expected).len() < 1e-4, "shading normal {shading:?}");
+        let (shading, _) = bump.shading_normal(&floor_hit(false)).unwrap();
+        assert!((shading + expected).len() < 1e-4, "shading normal from below {shading:?}");
+
+        // No tangents, nothing to perturb
+        assert!(bump.shading_normal(&HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face: true, ..Default::default() }).is_none());
+
+        // A flat height field leaves the surface flat
+        let MaterialType::Bump(flat) = Bump::height_map(Lambertian::new(0.5, 0.5, 0.5), SolidColor::from_rgb(0.3, 0.3, 0.3), 1.0) else {
+            panic!("not a bump material");
+        };
+        let (shading, _) = flat.shading_normal(&floor_hit(true)).unwrap();
+        assert!((shading - Vector3::new(0.0, 1.0, 0.0)).len() < 1e-4);
+    }
+
+    #[test]
+    fn loaded_meshes_are_bumped(){
+        let dir = std::env::temp_dir().join(format!("riven_bump_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // A unit square facing +z, with u along x, once with texture coordinates and once without
+        let square = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+        std::fs::write(dir.join("mapped.obj"), format!("{square}vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nf 1/1 2/2 3/3 4/4\n")).unwrap();
+        std::fs::write(dir.join("bare.obj"), format!("{square}f 1 2 3 4\n")).unwrap();
+        let mapped = load_obj(dir.join("mapped.obj"), &mut MaterialArena::new()).unwrap();
+        let bare = load_obj(dir.join("bare.obj"), &mut MaterialArena::new()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let shoot = |list: &HitList| {
+            let mut rec = HitRecord::default();
+            let ray = Ray::new(Point3::new(0.3, 0.6, 1.0), Vector3::new(0.0, 0.0, -1.0));
+            assert!(list.hit(&ray, &mut Interval::new(0.0001, INFINITY), &mut rec));
+            rec
+        };
+
+        let tilt = 0.5 + 0.5 * std::f32::consts::FRAC_1_SQRT_2;
+        let MaterialType::Bump(bump) = Bump::normal_map(Lambertian::new(0.5, 0.5, 0.5), SolidColor::from_rgb(tilt, 0.5, tilt)) else {
+            panic!("not a bump material");
+        };
+        let (shading, _) = bump.shading_normal(&shoot(&mapped)).expect("the mesh's tangents were lost");
+        assert!((shading - Vector3::new(1.0, 0.0, 1.0).unit_vector()).len() < 1e-4, "shading normal {shading:?}");
+
+        // Without texture coordinates there is nothing to follow
+        assert!(bump.shading_normal(&shoot(&bare)).is_none());
+    }
+
+    #[test]
+    fn shading_normals_stay_above_the_surface(){
+        // This map points into the surface
+        let bump = Bump::normal_map(Lambertian::new(0.5, 0.5, 0.5), SolidColor::from_rgb(0.5, 0.9, 0.0));
+        let MaterialType::Bump(inner) = &bump else {
+            panic!("not a bump material");
+        };
+        let rec = floor_hit(true);
+        let (shading, geometric) = inner.shading_normal(&rec).unwrap();
+        assert!(shading.dot(&geometric) > 0.0 && (shading.len() - 1.0).abs() < 1e-4);
+
+        // Light never leaves through the back of the surface
+        let ray = Ray::new(Point3::new(0.0, 1.0, 1.0), Vector3::new(0.0, -1.0, -1.0));
+        for _ in 0..1000 {
+            let (mut scattered, mut attenuation) = (Ray::default(), Default::default());
+            if bump.scatter(&ray, &mut scattered, &rec, &mut attenuation) {
+                assert!(scattered.direction.dot(&geometric) > 0.0);
+            }
+        }
+        let below = bump.eval(&ray, &rec, &Vector3::new(0.0, -0.05, -1.0));
+        assert_eq!(below.r, 0.0);
+    }
+}
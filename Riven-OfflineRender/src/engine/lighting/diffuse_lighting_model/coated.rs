@@ -0,0 +1,261 @@
+use crate::engine::base::constants::constants::random_float;
+use crate::engine::base::onb::Onb;
+use crate::engine::base::point::Point3;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::lighting::diffuse_lighting_model::microfacet::{fresnel_dielectric, reflect, TrowbridgeReitz};
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::textures::solid_color::SolidColor;
+use crate::engine::textures::{Texture, TextureType};
+use crate::util::color::Color;
+
+/// Any material under a thin dielectric coat, like varnished wood, lacquered metal or car paint.
+///
+/// The coat reflects with GGX microfacets and the Fresnel term of its index of refraction. What it lets
+/// through reaches the base, and is absorbed by the coat's tint on the way in and out. The coat is on
+/// the outside: rays coming from within the object only see the base.
+#[derive(Clone, Default)]
+pub struct Coated {
+    base: Box<MaterialType>,
+    refraction_index: f32,
+    roughness: TextureType,
+    /// The fraction of light the coat absorbs across its thickness, straight through, per channel.
+    absorption: Color,
+}
+
+/// The coat's fields once its textures are read at a surface point, in the local shading frame.
+struct Coat {
+    frame: Onb,
+    wo: Vector3,
+    distribution: TrowbridgeReitz,
+    /// The chance of sampling the coat rather than the base.
+    probability: f32,
+}
+
+impl Coated {
+    /// Creates a new clear `Coated`.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The material under the coat.
+    /// * `refraction_index` - The coat's index of refraction, about 1.5 for varnish.
+    /// * `roughness` - The coat's perceptual roughness, between 0 for a polished coat and 1.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Coated`.
+    pub fn new(base: MaterialType, refraction_index: f32, roughness: f32) -> MaterialType {
+        Self::with_absorption(base, refraction_index, roughness, Color::new(0.0, 0.0, 0.0))
+    }
+
+    /// Creates a new tinted `Coated`.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The material under the coat.
+    /// * `refraction_index` - The coat's index of refraction, about 1.5 for varnish.
+    /// * `roughness` - The coat's perceptual roughness, between 0 for a polished coat and 1.
+    /// * `absorption` - The coat's optical depth straight through, per channel.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Coated`.
+    pub fn with_absorption(base: MaterialType, refraction_index: f32, roughness: f32, absorption: Color) -> MaterialType {
+        let roughness = roughness.clamp(0.0, 1.0);
+        MaterialType::Coated(Coated {
+            base: Box::new(base),
+            refraction_index,
+            roughness: SolidColor::from_rgb(roughness, roughness, roughness),
+            absorption,
+        })
+    }
+
+    fn coat(&self, ray_in: &Ray, hit_record: &HitRecord) -> Coat {
+        let frame = Onb::new(hit_record.normal);
+        let wo = frame.to_local(-ray_in.direction.unit_vector());
+
+        let roughness = self.roughness.value(hit_record.u, hit_record.v, hit_record.point).r;
+        let alpha = TrowbridgeReitz::roughness_to_alpha(roughness);
+
+        // Follow the coat about as often as it reflects, but never starve either layer
+        let probability = fresnel_dielectric(wo.z, self.refraction_index).clamp(0.1, 0.9);
+
+        Coat { frame, wo, distribution: TrowbridgeReitz::new(alpha, alpha), probability }
+    }
+
+    /// The coat's own reflection, times the cosine, and the density of sampling it.
+    fn reflection(&self, coat: &Coat, wi: &Vector3) -> (f32, f32) {
+        let wo = &coat.wo;
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return (0.0, 0.0);
+        }
+
+        let wm = (*wo + *wi).unit_vector();
+        let distribution = &coat.distribution;
+        let fresnel = fresnel_dielectric(wo.dot(&wm), self.refraction_index);
+        let f = distribution.d(&wm) * distribution.g(wo, wi) * fresnel / (4.0 * wo.z);
+        (f, distribution.visible_d(wo, &wm) / (4.0 * wo.dot(&wm)))
+    }
+
+    /// The part of the light that crosses the coat into the base and back out along `wi`.
+    fn transmittance(&self, coat: &Coat, wi: &Vector3) -> Color {
+        let mut optical_depth = 1.0 / self.refracted_cosine(coat.wo.z);
+        let mut transmitted = 1.0 - fresnel_dielectric(coat.wo.z, self.refraction_index);
+
+        // Light the base sends into the object doesn't cross the coat a second time
+        if wi.z > 0.0 {
+            optical_depth += 1.0 / self.refracted_cosine(wi.z);
+            transmitted *= 1.0 - fresnel_dielectric(wi.z, self.refraction_index);
+        }
+
+        Color::new(
+            transmitted * (-self.absorption.r * optical_depth).exp(),
+            transmitted * (-self.absorption.g * optical_depth).exp(),
+            transmitted * (-self.absorption.b * optical_depth).exp(),
+        )
+    }
+
+    /// The cosine with the normal of a direction once refracted into the coat.
+    fn refracted_cosine(&self, cos_theta: f32) -> f32 {
+        let sin2 = (1.0 - cos_theta * cos_theta).max(0.0) / (self.refraction_index * self.refraction_index);
+        (1.0 - sin2).max(1e-4).sqrt()
+    }
+}
+
+impl DiffuseMaterial for Coated {
+    fn scatter(&self, ray_in: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
+        if !hit_record.front_face {
+            return self.base.scatter(ray_in, scattered_ray, hit_record, attenuation);
+        }
+
+        let coat = self.coat(ray_in, hit_record);
+        if coat.wo.z <= 0.0 {
+            return false;
+        }
+
+        if random_float() < coat.probability {
+            let wm = coat.distribution.sample_wm(&coat.wo, random_float(), random_float());
+            let wi = reflect(&coat.wo, &wm);
+            if wi.z <= 0.0 {
+                return false;
+            }
+            *scattered_ray = Ray::new(hit_record.point, coat.frame.transform(wi));
+
+            if self.is_specular() {
+                // With visible normal sampling, f * cos / pdf leaves only the Fresnel term and the shadowing
+                let fresnel = fresnel_dielectric(coat.wo.dot(&wm), self.refraction_index);
+                let weight = coat.distribution.g(&coat.wo, &wi) / coat.distribution.g1(&coat.wo) * fresnel;
+                *attenuation = (weight / coat.probability) * Color::new(1.0, 1.0, 1.0);
+                return true;
+            }
+        } else {
+            let mut base_attenuation = Color::default();
+            if !self.base.scatter(ray_in, scattered_ray, hit_record, &mut base_attenuation) {
+                return false;
+            }
+
+            if self.is_specular() {
+                let wi = coat.frame.to_local(scattered_ray.direction.unit_vector());
+                *attenuation = (1.0 / (1.0 - coat.probability)) * (self.transmittance(&coat, &wi) * base_attenuation);
+                return true;
+            }
+        }
+
+        let pdf = self.pdf(ray_in, hit_record, &scattered_ray.direction);
+        if pdf <= 0.0 {
+            return false;
+        }
+        *attenuation = (1.0 / pdf) * self.eval(ray_in, hit_record, &scattered_ray.direction);
+        true
+    }
+
+    fn emitted(&self, u: f32, v: f32, point: Point3) -> Color {
+        self.base.emitted(u, v, point)
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> Color {
+        let base = self.base.eval(ray_in, hit_record, direction);
+        if !hit_record.front_face {
+            return base;
+        }
+
+        let coat = self.coat(ray_in, hit_record);
+        let wi = coat.frame.to_local(direction.unit_vector());
+        let (reflected, _) = self.reflection(&coat, &wi);
+        Color::new(reflected, reflected, reflected) + self.transmittance(&coat, &wi) * base
+    }
+
+    fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> f32 {
+        let base = self.base.pdf(ray_in, hit_record, direction);
+        if !hit_record.front_face {
+            return base;
+        }
+
+        let coat = self.coat(ray_in, hit_record);
+        let (_, pdf) = self.reflection(&coat, &coat.frame.to_local(direction.unit_vector()));
+        coat.probability * pdf + (1.0 - coat.probability) * base
+    }
+
+    fn is_specular(&self) -> bool {
+        self.base.is_specular()
+    }
+
+    fn clone_box(&self) -> MaterialType {
+        MaterialType::Coated(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test_coated {
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::coated::Coated;
+    use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+    use crate::engine::lighting::diffuse_lighting_model::metal::Metal;
+    use crate::engine::lighting::diffuse_lighting_model::microfacet::test_microfacet::assert_sampling_matches_eval;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::util::color::Color;
+
+    #[test]
+    fn coat_over_a_diffuse_base_keeps_energy(){
+        let ray = Ray::new(Point3::new(-1.0, 1.0, 0.0), Vector3::new(0.7, -0.5, 0.2));
+        let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face: true, ..Default::default() };
+        let direction = Vector3::new(0.3, 0.9, -0.2);
+
+        // A coat matching the air around it is invisible
+        let invisible = Coated::new(Lambertian::new(0.5, 0.5, 0.5), 1.0, 0.3);
+        let bare = Lambertian::new(0.5, 0.5, 0.5);
+        assert!((invisible.eval(&ray, &rec, &direction).r - bare.eval(&ray, &rec, &direction).r).abs() < 1e-6);
+
+        let varnished = Coated::new(Lambertian::new(1.0, 1.0, 1.0), 1.5, 0.2);
+        assert!(!varnished.is_specular());
+
+        // A white base under a clear coat can't reflect more than it receives
+        let (integrated, _) = assert_sampling_matches_eval(&varnished, &ray, &rec);
+        assert!(integrated < 1.0 && integrated > 0.7, "the coated surface reflects {integrated}");
+    }
+
+    #[test]
+    fn tinted_coat_over_a_mirror(){
+        let lacquered = Coated::with_absorption(Metal::new(0.9, 0.9, 0.9, 0.0), 1.5, 0.0, Color::new(0.05, 0.5, 1.0));
+        assert!(lacquered.is_specular());
+
+        let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face: true, ..Default::default() };
+
+        // On average, red light gets through the tint far more than blue light
+        let samples = 20_000;
+        let mut average = Color::default();
+        for _ in 0..samples {
+            let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+            if lacquered.scatter(&ray, &mut scattered, &rec, &mut attenuation) {
+                average = average + (1.0 / samples as f32) * attenuation;
+            }
+        }
+        assert!(average.r > 0.7 && average.r < 0.95, "red reflectance {}", average.r);
+        assert!(average.b < 0.25, "blue reflectance {}", average.b);
+    }
+}
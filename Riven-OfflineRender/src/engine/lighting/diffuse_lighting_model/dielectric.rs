@@ -1,54 +1,352 @@
-use crate::engine::base::constants::constants::random_float;
+use crate::engine::base::constants::constants::{random_float, PI};
 use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
 use crate::engine::lighting::diffuse_lighting_model::MaterialType;
 use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::lighting::diffuse_lighting_model::microfacet::fresnel_dielectric;
 use crate::engine::objects::hit_record::HitRecord;
 use crate::util::color::Color;
+use crate::util::spectrum::{sample_wavelength, wavelength_weight, SampledWavelengths, RGB_WAVELENGTHS};
 
+/// Smooth glass, water or gems, optionally under a thin film like a soap bubble or a coated lens.
+///
+/// When the index of refraction depends on the wavelength, an RGB ray hitting the surface is split:
+/// it picks a single wavelength, bends by that wavelength's index and carries it from then on.
+/// A spectral ray goes on with its hero wavelength alone.
 #[derive(Clone, Default)]
 pub struct Dielectric {
+    ior: Dispersion,
+    thin_film: Option<ThinFilm>,
+}
+
+/// How the index of refraction varies with the wavelength.
+///
+/// Wavelengths in the formulas are in micrometers, as coefficient tables list them.
+#[derive(Clone, Copy, Debug)]
+pub enum Dispersion {
+    /// The same index for every wavelength.
+    Constant(f32),
+    /// Cauchy's equation, `n = a + b / λ²`.
+    Cauchy { a: f32, b: f32 },
+    /// Sellmeier's equation, `n² = 1 + Σ b λ² / (λ² - c)`.
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+/// A transparent film a fraction of a wavelength thick, whose reflections interfere.
+#[derive(Clone, Copy, Debug)]
+pub struct ThinFilm {
+    /// The film's thickness, in nanometers.
+    thickness: f32,
     refraction_index: f32,
 }
 
 impl Dielectric {
     pub fn new(refraction_index: f32) -> MaterialType {
-       MaterialType::Dielectric(Dielectric {
-            refraction_index
-        })
+        Self::dispersive(Dispersion::Constant(refraction_index))
     }
 
-    fn reflectance(&self, cosine: f32, refraction_index: f32) -> f32 {
-        let mut r0 = (1f32 - refraction_index) / (1f32 + refraction_index);
-        r0 = r0 * r0;
-        return r0 + (1f32 - r0) * (1f32 - cosine).powi(5);
+    /// Creates a `Dielectric` whose index of refraction depends on the wavelength, so prisms split light.
+    ///
+    /// # Arguments
+    ///
+    /// * `ior` - The index of refraction inside the object, per wavelength.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Dielectric`.
+    pub fn dispersive(ior: Dispersion) -> MaterialType {
+        MaterialType::Dielectric(Dielectric { ior, thin_film: None })
+    }
+
+    /// Creates a `Dielectric` under a thin film, iridescent like soap bubbles or oil on water.
+    ///
+    /// # Arguments
+    ///
+    /// * `ior` - The index of refraction inside the object, `Dispersion::Constant(1.0)` for a bubble.
+    /// * `film` - The film on the outside of the object.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Dielectric`.
+    pub fn with_thin_film(ior: Dispersion, film: ThinFilm) -> MaterialType {
+        MaterialType::Dielectric(Dielectric { ior, thin_film: Some(film) })
     }
 }
 
-impl DiffuseMaterial for Dielectric {
-    fn scatter(&self, ray_in: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
-        attenuation.r = 1.0;
-        attenuation.g = 1.0;
-        attenuation.b = 1.0;
+impl Dispersion {
+    /// The wavelength, in nanometers, refractive indices are usually quoted at, the sodium d-line.
+    pub const REFERENCE_WAVELENGTH: f32 = 587.6;
 
-        let ri = if hit_record.front_face { 1.0 / self.refraction_index } else { self.refraction_index };
-        let unit_direction = ray_in.direction.unit_vector();
+    /// Schott N-BK7, the common crown glass of lenses and prisms.
+    pub fn bk7() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.039_612, 0.231_792_34, 1.010_469_5],
+            c: [0.006_000_699, 0.020_017_914, 103.560_65],
+        }
+    }
 
+    /// Diamond, whose strong dispersion gives its fire.
+    pub fn diamond() -> Self {
+        Dispersion::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.030_625, 0.011_236, 0.0],
+        }
+    }
+
+    /// The index of refraction at a wavelength.
+    ///
+    /// # Arguments
+    ///
+    /// * `wavelength` - The wavelength in nanometers.
+    ///
+    /// # Returns
+    ///
+    /// The index of refraction.
+    pub fn ior(&self, wavelength: f32) -> f32 {
+        let micrometers = wavelength / 1000.0;
+        let l2 = micrometers * micrometers;
+        match *self {
+            Dispersion::Constant(n) => n,
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f32 = (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum();
+                (1.0 + sum).max(1.0).sqrt()
+            }
+        }
+    }
+
+    /// Whether different wavelengths see different indices, and so rays have to be split.
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Dispersion::Constant(_))
+    }
+}
+
+impl Default for Dispersion {
+    fn default() -> Self {
+        Dispersion::Constant(1.5)
+    }
+}
+
+impl ThinFilm {
+    /// Creates a new `ThinFilm`.
+    ///
+    /// # Arguments
+    ///
+    /// * `thickness` - The film's thickness in nanometers, a few hundred for visible colors.
+    /// * `refraction_index` - The film's index of refraction, about 1.33 for soapy water.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `ThinFilm`.
+    pub fn new(thickness: f32, refraction_index: f32) -> Self {
+        Self { thickness: thickness.max(0.0), refraction_index }
+    }
+
+    /// The reflectance of the film between two media, summing every reflection inside it (Airy's formula).
+    ///
+    /// # Arguments
+    ///
+    /// * `cos_theta` - The cosine between the incoming direction and the normal.
+    /// * `outer` - The index of refraction on the incoming side.
+    /// * `inner` - The index of refraction on the far side of the film.
+    /// * `wavelength` - The wavelength in nanometers.
+    ///
+    /// # Returns
+    ///
+    /// The fraction of light reflected, averaged over both polarizations.
+    pub(crate) fn reflectance(&self, cos_theta: f32, outer: f32, inner: f32, wavelength: f32) -> f32 {
+        let cos1 = cos_theta.clamp(0.0, 1.0);
+        let sin2_1 = 1.0 - cos1 * cos1;
+        let cos_in = |n: f32| {
+            let sin2 = sin2_1 * (outer / n).powi(2);
+            (sin2 < 1.0).then(|| (1.0 - sin2).sqrt())
+        };
+
+        let Some(cos3) = cos_in(inner) else {
+            return 1.0;
+        };
+        // Light can't travel through the film at this angle, the interface behaves as if it wasn't there
+        let Some(cos2) = cos_in(self.refraction_index) else {
+            return fresnel_dielectric(cos1, inner / outer);
+        };
+
+        let n2 = self.refraction_index;
+        let phase = 4.0 * PI * n2 * self.thickness * cos2 / wavelength;
+        let airy = |r12: f32, r23: f32| {
+            let cross = 2.0 * r12 * r23 * phase.cos();
+            (r12 * r12 + r23 * r23 + cross) / (1.0 + r12 * r12 * r23 * r23 + cross)
+        };
+
+        let s = airy(
+            (outer * cos1 - n2 * cos2) / (outer * cos1 + n2 * cos2),
+            (n2 * cos2 - inner * cos3) / (n2 * cos2 + inner * cos3),
+        );
+        let p = airy(
+            (n2 * cos1 - outer * cos2) / (n2 * cos1 + outer * cos2),
+            (inner * cos2 - n2 * cos3) / (inner * cos2 + n2 * cos3),
+        );
+        (0.5 * (s + p)).clamp(0.0, 1.0)
+    }
+
+    /// Picks between reflecting off the film and going through it.
+    ///
+    /// # Returns
+    ///
+    /// The scattered direction and its weight, colored for RGB rays.
+    fn scatter(&self, refraction_index: f32, wavelength: Option<f32>, ray_in: &Ray, hit_record: &HitRecord) -> (Vector3, Color) {
+        let unit_direction = ray_in.direction.unit_vector();
         let cos_theta = -unit_direction.dot(&hit_record.normal).min(1.0);
-        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let (outer, inner) = if hit_record.front_face { (1.0, refraction_index) } else { (refraction_index, 1.0) };
 
-        if ri * sin_theta > 1.0 || self.reflectance(cos_theta, ri) > random_float() {
-            let reflected = unit_direction.reflect(&hit_record.normal);
-            *scattered_ray = Ray::new(hit_record.point, reflected);
+        // A single wavelength reflects or not, an RGB ray reflects each channel by its own amount
+        let reflected = match wavelength {
+            Some(lambda) => {
+                let r = self.reflectance(cos_theta, outer, inner, lambda);
+                Color::new(r, r, r)
+            }
+            None => {
+                let [r, g, b] = RGB_WAVELENGTHS.map(|lambda| self.reflectance(cos_theta, outer, inner, lambda));
+                Color::new(r, g, b)
+            }
+        };
+
+        let probability = (reflected.r + reflected.g + reflected.b) / 3.0;
+        if probability >= 1.0 || random_float() < probability {
+            (unit_direction.reflect(&hit_record.normal), (1.0 / probability) * reflected)
         } else {
-            let refracted = unit_direction.refract(&hit_record.normal, ri);
-            *scattered_ray = Ray::new(hit_record.point, refracted);
+            let transmitted = Color::new(1.0 - reflected.r, 1.0 - reflected.g, 1.0 - reflected.b);
+            (unit_direction.refract(&hit_record.normal, outer / inner), (1.0 / (1.0 - probability)) * transmitted)
+        }
+    }
+}
+
+impl DiffuseMaterial for Dielectric {
+    fn scatter(&self, ray_in: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
+        let mut weight = Color::new(1.0, 1.0, 1.0);
+
+        // An RGB ray entering dispersive matter is split, it goes on with a single wavelength.
+        // A spectral ray follows its hero, the other wavelengths would have gone elsewhere
+        let mut wavelengths = ray_in.wavelengths;
+        match &mut wavelengths {
+            None if self.ior.is_dispersive() => {
+                let lambda = sample_wavelength();
+                weight = wavelength_weight(lambda);
+                wavelengths = Some(SampledWavelengths::single(lambda));
+            }
+            Some(sampled) if self.ior.is_dispersive() || self.thin_film.is_some() => sampled.terminate_secondary(),
+            _ => {}
         }
+        let wavelength = wavelengths.map(|sampled| sampled.hero());
+        let refraction_index = self.ior.ior(wavelength.unwrap_or(Dispersion::REFERENCE_WAVELENGTH));
+
+        let direction = match &self.thin_film {
+            Some(film) => {
+                let (direction, film_weight) = film.scatter(refraction_index, wavelength, ray_in, hit_record);
+                weight = weight * film_weight;
+                direction
+            }
+            None => smooth_scatter(refraction_index, ray_in, hit_record),
+        };
 
+        *scattered_ray = Ray::with_wavelengths(hit_record.point, direction, wavelengths);
+        *attenuation = weight;
         true
     }
 
     fn clone_box(&self) -> MaterialType {
-        Dielectric::new(self.refraction_index)
+        MaterialType::Dielectric(self.clone())
+    }
+}
+
+/// Schlick's approximation of the Fresnel reflectance.
+fn reflectance(cosine: f32, refraction_index: f32) -> f32 {
+    let mut r0 = (1f32 - refraction_index) / (1f32 + refraction_index);
+    r0 = r0 * r0;
+    r0 + (1f32 - r0) * (1f32 - cosine).powi(5)
+}
+
+/// Picks between the mirror reflection and the refraction through a smooth interface.
+///
+/// # Arguments
+///
+/// * `refraction_index` - The index of refraction inside the object.
+/// * `ray_in` - The incoming ray.
+/// * `hit_record` - The surface point, `front_face` tells which side the ray comes from.
+///
+/// # Returns
+///
+/// The scattered direction.
+pub(crate) fn smooth_scatter(refraction_index: f32, ray_in: &Ray, hit_record: &HitRecord) -> Vector3 {
+    let ri = if hit_record.front_face { 1.0 / refraction_index } else { refraction_index };
+    let unit_direction = ray_in.direction.unit_vector();
+
+    let cos_theta = -unit_direction.dot(&hit_record.normal).min(1.0);
+    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+    if ri * sin_theta > 1.0 || reflectance(cos_theta, ri) > random_float() {
+        unit_direction.reflect(&hit_record.normal)
+    } else {
+        unit_direction.refract(&hit_record.normal, ri)
     }
 }
 
+#[cfg(test)]
+mod test_dielectric {
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::dielectric::{Dielectric, Dispersion, ThinFilm};
+    use crate::engine::lighting::diffuse_lighting_model::microfacet::fresnel_dielectric;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::util::color::Color;
+
+    #[test]
+    fn prisms_bend_blue_more_than_red(){
+        let glass = Dispersion::bk7();
+        assert!((glass.ior(Dispersion::REFERENCE_WAVELENGTH) - 1.5168).abs() < 1e-3);
+        assert!(glass.ior(450.0) > glass.ior(650.0));
+        assert!((Dispersion::diamond().ior(Dispersion::REFERENCE_WAVELENGTH) - 2.417).abs() < 0.01);
+
+        // An RGB ray is split, the refracted ray carries its wavelength and bends by it
+        let prism = Dielectric::dispersive(Dispersion::Cauchy { a: 1.5, b: 0.05 });
+        let ray = Ray::new(Point3::new(-1.0, 1.0, 0.0), Vector3::new(1.0, -1.0, 0.0));
+        let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face: true, ..Default::default() };
+        let mut bends = Vec::new();
+        for _ in 0..2000 {
+            let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+            assert!(prism.scatter(&ray, &mut scattered, &rec, &mut attenuation));
+            let wavelength = scattered.wavelengths.expect("the ray was split").hero();
+            let direction = scattered.direction.unit_vector();
+            if direction.y < 0.0 {
+                bends.push((wavelength, direction.x));
+            }
+        }
+        let mean_sine = |short: bool| {
+            let picked: Vec<f32> = bends.iter().filter(|(lambda, _)| (*lambda < 500.0) == short).map(|(_, x)| *x).collect();
+            picked.iter().sum::<f32>() / picked.len() as f32
+        };
+        // Shorter wavelengths end up closer to the normal
+        assert!(mean_sine(true) < mean_sine(false) - 0.01);
+    }
+
+    #[test]
+    fn thin_films_interfere(){
+        // A film without thickness leaves the plain interface
+        let bare = ThinFilm::new(0.0, 1.33);
+        for cos_theta in [1.0, 0.7, 0.2] {
+            assert!((bare.reflectance(cos_theta, 1.0, 1.5, 550.0) - fresnel_dielectric(cos_theta, 1.5)).abs() < 1e-4);
+        }
+
+        // A quarter wave film with the geometric mean index cancels the reflection, like a lens coating
+        let n = 1.5f32.sqrt();
+        let coating = ThinFilm::new(550.0 / (4.0 * n), n);
+        assert!(coating.reflectance(1.0, 1.0, 1.5, 550.0) < 1e-4);
+        assert!(coating.reflectance(1.0, 1.0, 1.5, 400.0) > 1e-3);
+
+        // A soap bubble reflects colors differently
+        let bubble = ThinFilm::new(380.0, 1.33);
+        let red = bubble.reflectance(1.0, 1.0, 1.0, 630.0);
+        let blue = bubble.reflectance(1.0, 1.0, 1.0, 465.0);
+        assert!((red - blue).abs() > 0.02, "red {red} blue {blue}");
+    }
+}
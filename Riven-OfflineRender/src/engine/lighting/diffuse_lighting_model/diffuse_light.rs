@@ -0,0 +1,42 @@
+use crate::engine::base::point::Point3;
+use crate::engine::base::ray::Ray;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::textures::solid_color::SolidColor;
+use crate::engine::textures::{Texture, TextureType};
+use crate::util::color::Color;
+
+/// A material that emits light and doesn't scatter any, turning the surface it's on into an area light.
+#[derive(Clone, Default)]
+pub struct DiffuseLight {
+    emit: TextureType,
+}
+
+impl DiffuseLight {
+    pub fn new(r: f32, g: f32, b: f32) -> MaterialType {
+        MaterialType::DiffuseLight(DiffuseLight {
+            emit: SolidColor::from_rgb(r, g, b)
+        })
+    }
+
+    pub fn from_texture(texture: TextureType) -> MaterialType {
+        MaterialType::DiffuseLight(DiffuseLight {
+            emit: texture
+        })
+    }
+}
+
+impl DiffuseMaterial for DiffuseLight {
+    fn scatter(&self, _: &Ray, _: &mut Ray, _: &HitRecord, _: &mut Color) -> bool {
+        false
+    }
+
+    fn emitted(&self, u: f32, v: f32, point: Point3) -> Color {
+        self.emit.value(u, v, point)
+    }
+
+    fn clone_box(&self) -> MaterialType {
+        MaterialType::DiffuseLight(self.clone())
+    }
+}
@@ -0,0 +1,147 @@
+use crate::engine::base::constants::constants::{random_float, PI};
+use crate::engine::base::onb::Onb;
+use crate::engine::base::point::Point3;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::textures::solid_color::SolidColor;
+use crate::engine::textures::{Texture, TextureType};
+use crate::util::color::Color;
+
+/// An anisotropic phase function for participating media, favoring forward or backward scattering.
+///
+/// It can also make the medium glow, for fire and explosions. Media hand the value of their
+/// emission grid to the material as the `u` coordinate, so a texture can map it to a color ramp.
+#[derive(Clone, Default)]
+pub struct HenyeyGreenstein {
+    /// Mean cosine of the scattering angle, positive scatters forward and negative backward.
+    g: f32,
+    /// The fraction of light that scatters instead of being absorbed, per scattering event.
+    albedo: TextureType,
+    /// Radiance the medium adds at every collision.
+    emission: TextureType,
+}
+
+impl HenyeyGreenstein {
+    /// Creates a new `HenyeyGreenstein` that doesn't glow.
+    ///
+    /// # Arguments
+    ///
+    /// * `r`, `g`, `b` - The scattering albedo.
+    /// * `anisotropy` - The mean cosine `g`, clamped to `(-1, 1)`.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::HenyeyGreenstein`.
+    pub fn new(r: f32, g: f32, b: f32, anisotropy: f32) -> MaterialType {
+        Self::from_textures(SolidColor::from_rgb(r, g, b), SolidColor::from_rgb(0.0, 0.0, 0.0), anisotropy)
+    }
+
+    /// Creates a new glowing or textured `HenyeyGreenstein`.
+    ///
+    /// # Arguments
+    ///
+    /// * `albedo` - The scattering albedo.
+    /// * `emission` - The radiance added at each collision, evaluated with the medium's emission as `u`.
+    /// * `anisotropy` - The mean cosine `g`, clamped to `(-1, 1)`.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::HenyeyGreenstein`.
+    pub fn from_textures(albedo: TextureType, emission: TextureType, anisotropy: f32) -> MaterialType {
+        MaterialType::HenyeyGreenstein(HenyeyGreenstein {
+            g: anisotropy.clamp(-0.99, 0.99),
+            albedo,
+            emission,
+        })
+    }
+
+    /// The phase function's density for a scattering angle with cosine `cos_theta`.
+    fn phase(&self, cos_theta: f32) -> f32 {
+        let denominator = 1.0 + self.g * self.g - 2.0 * self.g * cos_theta;
+        (1.0 - self.g * self.g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+}
+
+impl DiffuseMaterial for HenyeyGreenstein {
+    fn scatter(&self, ray_in: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
+        // Invert the phase function's cdf for the cosine from the incoming direction
+        let xi = random_float();
+        let cos_theta = if self.g.abs() < 1e-3 {
+            1.0 - 2.0 * xi
+        } else {
+            let s = (1.0 - self.g * self.g) / (1.0 - self.g + 2.0 * self.g * xi);
+            ((1.0 + self.g * self.g - s * s) / (2.0 * self.g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_float();
+
+        let direction = Onb::new(ray_in.direction).transform(Vector3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta));
+        *scattered_ray = Ray::new(hit_record.point, direction);
+        *attenuation = self.albedo.value(hit_record.u, hit_record.v, hit_record.point);
+        true
+    }
+
+    fn emitted(&self, u: f32, v: f32, point: Point3) -> Color {
+        self.emission.value(u, v, point)
+    }
+
+    /// Scattering inside a volume has no surface, so there's no cosine term.
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> Color {
+        let cos_theta = ray_in.direction.unit_vector().dot(&direction.unit_vector());
+        self.phase(cos_theta) * self.albedo.value(hit_record.u, hit_record.v, hit_record.point)
+    }
+
+    fn pdf(&self, ray_in: &Ray, _: &HitRecord, direction: &Vector3) -> f32 {
+        self.phase(ray_in.direction.unit_vector().dot(&direction.unit_vector()))
+    }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    fn clone_box(&self) -> MaterialType {
+        MaterialType::HenyeyGreenstein(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test_henyey_greenstein {
+    use crate::engine::base::constants::constants::PI;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::henyey_greenstein::HenyeyGreenstein;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::util::color::Color;
+
+    #[test]
+    fn sampled_directions_have_mean_cosine_g(){
+        let g = 0.6;
+        let material = HenyeyGreenstein::new(1.0, 1.0, 1.0, g);
+        let ray = Ray::new(Point3::default(), Vector3::new(0.3, -1.0, 0.2));
+        let forward = ray.direction.unit_vector();
+        let rec = HitRecord::default();
+
+        let samples = 20_000;
+        let mut mean_cosine = 0.0;
+        for _ in 0..samples {
+            let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+            assert!(material.scatter(&ray, &mut scattered, &rec, &mut attenuation));
+            mean_cosine += scattered.direction.unit_vector().dot(&forward) / samples as f32;
+        }
+        assert!((mean_cosine - g).abs() < 0.02, "mean cosine is {mean_cosine}");
+
+        // The density integrates to one over the sphere
+        let steps = 2000;
+        let integral: f32 = (0..steps).map(|i| {
+            let cos_theta = -1.0 + 2.0 * (i as f32 + 0.5) / steps as f32;
+            let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+            let direction = forward * cos_theta + Vector3::new(1.0, 0.3, 0.0).cross(&forward).unit_vector() * sin_theta;
+            material.pdf(&ray, &rec, &direction) * 2.0 * PI * 2.0 / steps as f32
+        }).sum();
+        assert!((integral - 1.0).abs() < 1e-3, "phase function integrates to {integral}");
+    }
+}
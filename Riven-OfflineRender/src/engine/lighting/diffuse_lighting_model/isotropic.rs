@@ -0,0 +1,55 @@
+use crate::engine::base::constants::constants::PI;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::textures::solid_color::SolidColor;
+use crate::engine::textures::{Texture, TextureType};
+use crate::util::color::Color;
+
+/// The phase function of a participating medium that scatters light equally in every direction.
+#[derive(Clone, Default)]
+pub struct Isotropic {
+    /// The fraction of light that scatters instead of being absorbed, per scattering event.
+    albedo: TextureType,
+}
+
+impl Isotropic {
+    pub fn new(r: f32, g: f32, b: f32) -> MaterialType {
+        MaterialType::Isotropic(Isotropic {
+            albedo: SolidColor::from_rgb(r, g, b)
+        })
+    }
+
+    pub fn from_texture(texture: TextureType) -> MaterialType {
+        MaterialType::Isotropic(Isotropic {
+            albedo: texture
+        })
+    }
+}
+
+impl DiffuseMaterial for Isotropic {
+    fn scatter(&self, _: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
+        *scattered_ray = Ray::new(hit_record.point, Vector3::random_unit_vector());
+        *attenuation = self.albedo.value(hit_record.u, hit_record.v, hit_record.point);
+        true
+    }
+
+    /// Scattering inside a volume has no surface, so there's no cosine term.
+    fn eval(&self, _: &Ray, hit_record: &HitRecord, _: &Vector3) -> Color {
+        (1.0 / (4.0 * PI)) * self.albedo.value(hit_record.u, hit_record.v, hit_record.point)
+    }
+
+    fn pdf(&self, _: &Ray, _: &HitRecord, _: &Vector3) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    fn clone_box(&self) -> MaterialType {
+        MaterialType::Isotropic(self.clone())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::engine::base::constants::constants::PI;
 use crate::engine::base::point::Point3;
 use crate::engine::base::ray::Ray;
 use crate::engine::base::vector::Vector3;
@@ -49,6 +50,20 @@ impl DiffuseMaterial for Lambertian {
         return true;
     }
 
+    fn eval(&self, _: &Ray, hit_record: &HitRecord, direction: &Vector3) -> Color {
+        let cosine = hit_record.normal.dot(&direction.unit_vector()).max(0.0);
+        (cosine / PI) * self.albedo.value(hit_record.u, hit_record.v, hit_record.point)
+    }
+
+    fn pdf(&self, _: &Ray, hit_record: &HitRecord, direction: &Vector3) -> f32 {
+        // `scatter` offsets the normal by a random unit vector, which is cosine distributed
+        hit_record.normal.dot(&direction.unit_vector()).max(0.0) / PI
+    }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+
     fn clone_box(&self) -> MaterialType {
         Lambertian::new(
             self.albedo.value(0.0, 0.0, Point3::default()).r,
@@ -1,4 +1,6 @@
+use crate::engine::base::point::Point3;
 use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
 use crate::engine::lighting::diffuse_lighting_model::MaterialType;
 use crate::engine::objects::hit_record::HitRecord;
 use crate::util::color::Color;
@@ -7,6 +9,31 @@ use crate::util::color::Color;
 pub trait DiffuseMaterial : Sync + Send {
     fn scatter(&self, ray_in: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool;
 
+    /// The light given off by the material at a surface point, black for anything but light sources.
+    fn emitted(&self, _u: f32, _v: f32, _point: Point3) -> Color {
+        Color::default()
+    }
+
+    /// The BSDF for light leaving along `direction`, multiplied by the cosine of `direction` with the normal.
+    ///
+    /// Only meaningful for materials that aren't specular, the others return black.
+    fn eval(&self, _ray_in: &Ray, _hit_record: &HitRecord, _direction: &Vector3) -> Color {
+        Color::default()
+    }
+
+    /// The density, per unit solid angle, with which `scatter` picks `direction`.
+    ///
+    /// Only meaningful for materials that aren't specular, the others return zero.
+    fn pdf(&self, _ray_in: &Ray, _hit_record: &HitRecord, _direction: &Vector3) -> f32 {
+        0.0
+    }
+
+    /// Whether `scatter` follows a single deterministic direction, or one that can't be evaluated,
+    /// in which case the material can't be lit by sampling lights directly.
+    fn is_specular(&self) -> bool {
+        true
+    }
+
     fn clone_box(&self) -> MaterialType;
 }
 
@@ -0,0 +1,107 @@
+use std::ops::Index;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::objects::alpha_mask::AlphaMask;
+use crate::engine::textures::TextureType;
+
+/// A handle to a material stored in a `MaterialArena`, cheap to copy into every `HitRecord`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MaterialId(u32);
+
+/// Owns every material of a scene, primitives refer to them by `MaterialId`.
+#[derive(Clone, Default)]
+pub struct MaterialArena {
+    materials: Vec<MaterialType>,
+    /// The opacity mask of each material, if it has one.
+    masks: Vec<Option<AlphaMask>>,
+}
+
+impl MaterialArena {
+    pub fn new() -> Self {
+        Self { materials: Vec::new(), masks: Vec::new() }
+    }
+
+    /// Stores a material in the arena.
+    ///
+    /// # Arguments
+    ///
+    /// * `material` - The material to store.
+    ///
+    /// # Returns
+    ///
+    /// The `MaterialId` primitives use to refer to the material.
+    pub fn add(&mut self, material: MaterialType) -> MaterialId {
+        let id = MaterialId(self.materials.len() as u32);
+        self.materials.push(material);
+        self.masks.push(None);
+        id
+    }
+
+    /// Stores a material with holes cut out of it, like foliage or a chain-link fence.
+    ///
+    /// Primitives using the material are bound to its mask when they're put in a `Scene`, from then on
+    /// rays and shadow rays go through the holes.
+    ///
+    /// # Arguments
+    ///
+    /// * `material` - The material to store.
+    /// * `alpha` - The opacity, read from the red channel, zero where the surface is cut out.
+    ///
+    /// # Returns
+    ///
+    /// The `MaterialId` primitives use to refer to the material.
+    pub fn add_masked(&mut self, material: MaterialType, alpha: TextureType) -> MaterialId {
+        let id = self.add(material);
+        self.masks[id.0 as usize] = Some(AlphaMask::new(alpha));
+        id
+    }
+
+    /// The opacity mask of a material, `None` for opaque ones.
+    pub fn alpha_mask(&self, id: MaterialId) -> Option<&AlphaMask> {
+        self.masks.get(id.0 as usize).and_then(Option::as_ref)
+    }
+
+    /// Whether any material has an opacity mask.
+    pub fn has_masks(&self) -> bool {
+        self.masks.iter().any(Option::is_some)
+    }
+
+    pub fn get(&self, id: MaterialId) -> Option<&MaterialType> {
+        self.materials.get(id.0 as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+}
+
+impl Index<MaterialId> for MaterialArena {
+    type Output = MaterialType;
+
+    fn index(&self, id: MaterialId) -> &MaterialType {
+        &self.materials[id.0 as usize]
+    }
+}
+
+#[cfg(test)]
+mod test_material_arena {
+    use crate::engine::lighting::diffuse_lighting_model::dielectric::Dielectric;
+    use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
+    use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+
+    #[test]
+    fn ids_resolve_to_their_material(){
+        let mut arena = MaterialArena::new();
+        let diffuse = arena.add(Lambertian::new(1.0, 0.0, 0.0));
+        let glass = arena.add(Dielectric::new(1.5));
+
+        assert_ne!(diffuse, glass);
+        assert_eq!(arena.len(), 2);
+        assert!(matches!(arena[diffuse], MaterialType::Lambertian(_)));
+        assert!(matches!(arena[glass], MaterialType::Dielectric(_)));
+    }
+}
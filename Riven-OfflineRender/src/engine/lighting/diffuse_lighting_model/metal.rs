@@ -1,53 +1,265 @@
+use crate::engine::base::constants::constants::random_float;
+use crate::engine::base::onb::Onb;
 use crate::engine::base::ray::Ray;
 use crate::engine::base::vector::Vector3;
 use crate::engine::lighting::diffuse_lighting_model::MaterialType;
 use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::lighting::diffuse_lighting_model::microfacet::{fresnel_conductor, reflect, TrowbridgeReitz};
 use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::textures::solid_color::SolidColor;
+use crate::engine::textures::{Texture, TextureType};
 use crate::util::color::Color;
 
+/// A conductor, rough or polished, with GGX microfacets and the Fresnel term of a complex index of refraction.
+///
+/// Roughness is perceptual, squared into the distribution's alpha. Surfaces built with a roughness
+/// this close to zero are perfect mirrors.
 #[derive(Clone, Default)]
 pub struct Metal {
-    albedo: Color,
-    fuzz: f32,
+    /// Real part of the index of refraction, per channel.
+    eta: Color,
+    /// Absorption coefficient, the imaginary part of the index of refraction, per channel.
+    k: Color,
+    roughness: TextureType,
+    /// Whether the surface is a perfect mirror.
+    polished: bool,
 }
 
 impl Metal {
-    pub fn new(r : f32, g : f32, b : f32, fuzz: f32) -> MaterialType {
-        if fuzz < 1.0 {
-            MaterialType::Metal(Metal {
-                albedo: Color::new(r, g, b),
-                fuzz,
-            })
-        } else {
-            MaterialType::Metal(Metal {
-                albedo: Color::new(r, g, b),
-                fuzz: 1.0,
-            })
-        }
+    /// Roughness below this makes a perfect mirror.
+    const POLISHED_ROUGHNESS: f32 = 0.03;
+
+    /// Creates a metal reflecting `(r, g, b)` at normal incidence.
+    ///
+    /// # Arguments
+    ///
+    /// * `r`, `g`, `b` - The reflectance looking straight at the surface, fitted to a complex index of refraction.
+    /// * `roughness` - The perceptual roughness, between 0 for a mirror and 1.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Metal`.
+    pub fn new(r : f32, g : f32, b : f32, roughness: f32) -> MaterialType {
+        let ((eta_r, k_r), (eta_g, k_g), (eta_b, k_b)) = (fit_ior(r), fit_ior(g), fit_ior(b));
+        Self::from_ior(Color::new(eta_r, eta_g, eta_b), Color::new(k_r, k_g, k_b), roughness)
+    }
+
+    /// Creates a metal from its measured index of refraction.
+    ///
+    /// # Arguments
+    ///
+    /// * `eta` - The real part of the index of refraction, per channel.
+    /// * `k` - The absorption coefficient, per channel.
+    /// * `roughness` - The perceptual roughness, between 0 for a mirror and 1.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Metal`.
+    pub fn from_ior(eta: Color, k: Color, roughness: f32) -> MaterialType {
+        let roughness = roughness.clamp(0.0, 1.0);
+        MaterialType::Metal(Metal {
+            eta,
+            k,
+            roughness: SolidColor::from_rgb(roughness, roughness, roughness),
+            polished: roughness < Self::POLISHED_ROUGHNESS,
+        })
+    }
+
+    /// Creates a metal whose roughness varies over the surface, like scratched or worn metal.
+    ///
+    /// # Arguments
+    ///
+    /// * `eta` - The real part of the index of refraction, per channel.
+    /// * `k` - The absorption coefficient, per channel.
+    /// * `roughness` - The perceptual roughness, read from the texture's red channel.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Metal`.
+    pub fn from_roughness_texture(eta: Color, k: Color, roughness: TextureType) -> MaterialType {
+        MaterialType::Metal(Metal {
+            eta,
+            k,
+            roughness,
+            polished: false,
+        })
+    }
+
+    /// Gold, with its index of refraction measured at the red, green and blue wavelengths.
+    pub fn gold(roughness: f32) -> MaterialType {
+        Self::from_ior(Color::new(0.143, 0.374, 1.442), Color::new(3.983, 2.385, 1.603), roughness)
+    }
+
+    /// Copper, with its index of refraction measured at the red, green and blue wavelengths.
+    pub fn copper(roughness: f32) -> MaterialType {
+        Self::from_ior(Color::new(0.200, 0.924, 1.102), Color::new(3.912, 2.452, 2.142), roughness)
+    }
+
+    /// Aluminium, with its index of refraction measured at the red, green and blue wavelengths.
+    pub fn aluminium(roughness: f32) -> MaterialType {
+        Self::from_ior(Color::new(1.657, 0.880, 0.521), Color::new(9.224, 6.270, 4.837), roughness)
+    }
+
+    /// Silver, with its index of refraction measured at the red, green and blue wavelengths.
+    pub fn silver(roughness: f32) -> MaterialType {
+        Self::from_ior(Color::new(0.155, 0.117, 0.138), Color::new(4.828, 3.122, 2.147), roughness)
+    }
+
+    fn fresnel(&self, cos_theta: f32) -> Color {
+        Color::new(
+            fresnel_conductor(cos_theta, self.eta.r, self.k.r),
+            fresnel_conductor(cos_theta, self.eta.g, self.k.g),
+            fresnel_conductor(cos_theta, self.eta.b, self.k.b),
+        )
+    }
+
+    fn distribution(&self, hit_record: &HitRecord) -> TrowbridgeReitz {
+        let roughness = self.roughness.value(hit_record.u, hit_record.v, hit_record.point).r;
+        let alpha = TrowbridgeReitz::roughness_to_alpha(roughness);
+        TrowbridgeReitz::new(alpha, alpha)
     }
 }
 
 impl DiffuseMaterial for Metal {
-    fn scatter(&self, _: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
-        let mut reflected = hit_record.normal.reflect(&scattered_ray.direction);
-        reflected = reflected.unit_vector() + (self.fuzz * Vector3::random_unit_vector());
+    fn scatter(&self, ray_in: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
+        let unit_direction = ray_in.direction.unit_vector();
 
-        *scattered_ray = Ray::new(hit_record.point, reflected);
+        if self.polished {
+            let reflected = unit_direction.reflect(&hit_record.normal);
+            *scattered_ray = Ray::new(hit_record.point, reflected);
+            *attenuation = self.fresnel(-unit_direction.dot(&hit_record.normal));
+            return true;
+        }
 
-        attenuation.r = self.albedo.r;
-        attenuation.g = self.albedo.g;
-        attenuation.b = self.albedo.b;
+        let frame = Onb::new(hit_record.normal);
+        let wo = frame.to_local(-unit_direction);
+        if wo.z <= 0.0 {
+            return false;
+        }
 
-        return scattered_ray.direction.dot(&hit_record.normal) > 0.0;
+        let distribution = self.distribution(hit_record);
+        let wm = distribution.sample_wm(&wo, random_float(), random_float());
+        let wi = reflect(&wo, &wm);
+        if wi.z <= 0.0 {
+            return false;
+        }
+
+        *scattered_ray = Ray::new(hit_record.point, frame.transform(wi));
+        // With visible normal sampling, f * cos / pdf leaves only the Fresnel term and the shadowing
+        *attenuation = (distribution.g(&wo, &wi) / distribution.g1(&wo)) * self.fresnel(wo.dot(&wm));
+        true
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> Color {
+        if self.polished {
+            return Color::default();
+        }
+
+        let frame = Onb::new(hit_record.normal);
+        let wo = frame.to_local(-ray_in.direction.unit_vector());
+        let wi = frame.to_local(direction.unit_vector());
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::default();
+        }
+
+        let wm = (wo + wi).unit_vector();
+        let distribution = self.distribution(hit_record);
+        let scale = distribution.d(&wm) * distribution.g(&wo, &wi) / (4.0 * wo.z);
+        scale * self.fresnel(wo.dot(&wm))
+    }
+
+    fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> f32 {
+        if self.polished {
+            return 0.0;
+        }
+
+        let frame = Onb::new(hit_record.normal);
+        let wo = frame.to_local(-ray_in.direction.unit_vector());
+        let wi = frame.to_local(direction.unit_vector());
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+
+        let wm = (wo + wi).unit_vector();
+        self.distribution(hit_record).visible_d(&wo, &wm) / (4.0 * wo.dot(&wm).abs())
+    }
+
+    fn is_specular(&self) -> bool {
+        self.polished
     }
 
     fn clone_box(&self) -> MaterialType {
-        Metal::new(
-            self.albedo.r,
-            self.albedo.g,
-            self.albedo.b,
-            self.fuzz
-        )
+        MaterialType::Metal(self.clone())
     }
 }
 
+/// Fits a complex index of refraction, `(eta, k)`, to a reflectance at normal incidence.
+///
+/// Follows Gulbrandsen's artist friendly metallic Fresnel, using the reflectance as the edge tint too.
+fn fit_ior(reflectance: f32) -> (f32, f32) {
+    let r = reflectance.clamp(0.0, 0.99);
+    let edge_tint = r;
+
+    let eta_min = (1.0 - r) / (1.0 + r);
+    let eta_max = (1.0 + r.sqrt()) / (1.0 - r.sqrt());
+    let eta = edge_tint * eta_min + (1.0 - edge_tint) * eta_max;
+
+    let k2 = ((eta + 1.0).powi(2) * r - (eta - 1.0).powi(2)) / (1.0 - r);
+    (eta, k2.max(0.0).sqrt())
+}
+
+#[cfg(test)]
+mod test_metal {
+    use crate::engine::base::constants::constants::{random_float, PI};
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::metal::Metal;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::util::color::Color;
+
+    #[test]
+    fn fitted_ior_keeps_the_normal_reflectance(){
+        let metal = Metal::new(0.9, 0.6, 0.2, 0.0);
+        let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), ..Default::default() };
+
+        let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+        assert!(metal.scatter(&ray, &mut scattered, &rec, &mut attenuation));
+        assert!((scattered.direction.unit_vector().y - 1.0).abs() < 1e-5);
+        assert!((attenuation.r - 0.9).abs() < 1e-3 && (attenuation.g - 0.6).abs() < 1e-3 && (attenuation.b - 0.2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sampling_agrees_with_eval(){
+        let metal = Metal::gold(0.5);
+        let ray = Ray::new(Point3::new(-1.0, 1.0, 0.0), Vector3::new(0.8, -0.6, 0.1));
+        let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), ..Default::default() };
+
+        // The reflected energy estimated with the material's own sampling
+        let samples = 100_000;
+        let mut sampled = 0.0;
+        for _ in 0..samples {
+            let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+            if metal.scatter(&ray, &mut scattered, &rec, &mut attenuation) {
+                let pdf = metal.pdf(&ray, &rec, &scattered.direction);
+                let expected = metal.eval(&ray, &rec, &scattered.direction).r / pdf;
+                assert!((attenuation.r - expected).abs() < 1e-3 * expected.max(1.0), "{} vs {expected}", attenuation.r);
+                sampled += attenuation.r / samples as f32;
+            }
+        }
+
+        // And with uniform hemisphere directions
+        let mut uniform = 0.0;
+        for _ in 0..samples {
+            let y = random_float();
+            let r = (1.0 - y * y).sqrt();
+            let phi = 2.0 * PI * random_float();
+            let direction = Vector3::new(r * phi.cos(), y, r * phi.sin());
+            uniform += metal.eval(&ray, &rec, &direction).r * 2.0 * PI / samples as f32;
+        }
+
+        assert!(sampled < 1.0);
+        assert!((sampled - uniform).abs() < 0.03, "sampling gives {sampled}, uniform directions {uniform}");
+    }
+}
@@ -0,0 +1,316 @@
+use crate::engine::base::constants::constants::PI;
+use crate::engine::base::vector::Vector3;
+
+/// The GGX, or Trowbridge-Reitz, distribution of microfacet normals.
+///
+/// Every direction is in the local shading frame, where the macro surface normal is `+z`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TrowbridgeReitz {
+    alpha_x: f32,
+    alpha_y: f32,
+}
+
+impl TrowbridgeReitz {
+    /// Below this, a distribution is too peaked to evaluate and the surface is treated as a perfect mirror.
+    pub(crate) const MIN_ALPHA: f32 = 1e-3;
+
+    /// Creates a new `TrowbridgeReitz`.
+    ///
+    /// # Arguments
+    ///
+    /// * `alpha_x`, `alpha_y` - The slope spread along the tangent and bitangent, clamped to `MIN_ALPHA`.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `TrowbridgeReitz`.
+    pub(crate) fn new(alpha_x: f32, alpha_y: f32) -> Self {
+        Self {
+            alpha_x: alpha_x.max(Self::MIN_ALPHA),
+            alpha_y: alpha_y.max(Self::MIN_ALPHA),
+        }
+    }
+
+    /// Maps the perceptual roughness artists pick to the distribution's alpha.
+    #[inline]
+    pub(crate) fn roughness_to_alpha(roughness: f32) -> f32 {
+        let roughness = roughness.clamp(0.0, 1.0);
+        roughness * roughness
+    }
+
+    /// The density of microfacet normals `wm`, per unit of projected area.
+    pub(crate) fn d(&self, wm: &Vector3) -> f32 {
+        if wm.z <= 0.0 {
+            return 0.0;
+        }
+        let (x, y) = (wm.x / self.alpha_x, wm.y / self.alpha_y);
+        let e = x * x + y * y + wm.z * wm.z;
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    /// Smith's auxiliary function, the area of microfacets seen from `w` that are hidden by others.
+    pub(crate) fn lambda(&self, w: &Vector3) -> f32 {
+        let cos2 = w.z * w.z;
+        if cos2 <= 0.0 {
+            return f32::INFINITY;
+        }
+        let spread = (self.alpha_x * w.x).powi(2) + (self.alpha_y * w.y).powi(2);
+        ((1.0 + spread / cos2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Smith's masking function, the fraction of microfacets visible from `w`.
+    pub(crate) fn g1(&self, w: &Vector3) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// The height-correlated masking-shadowing function, the fraction of microfacets visible from both directions.
+    pub(crate) fn g(&self, wo: &Vector3, wi: &Vector3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// The density of the normals `sample_wm` picks, the normals visible from `wo`.
+    pub(crate) fn visible_d(&self, wo: &Vector3, wm: &Vector3) -> f32 {
+        if wo.z == 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) / wo.z.abs() * self.d(wm) * wo.dot(wm).max(0.0)
+    }
+
+    /// Samples a microfacet normal visible from `wo`, following Heitz's 2018 method.
+    ///
+    /// # Arguments
+    ///
+    /// * `wo` - The outgoing direction.
+    /// * `u1`, `u2` - Two uniform random numbers in `[0, 1)`.
+    ///
+    /// # Returns
+    ///
+    /// A microfacet normal in the upper hemisphere.
+    pub(crate) fn sample_wm(&self, wo: &Vector3, u1: f32, u2: f32) -> Vector3 {
+        // Stretch the view so the distribution becomes the hemisphere of a unit roughness
+        let mut wh = Vector3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).unit_vector();
+        if wh.z < 0.0 {
+            wh = -wh;
+        }
+
+        let length2 = wh.x * wh.x + wh.y * wh.y;
+        let t1 = if length2 > 0.0 {
+            Vector3::new(-wh.y, wh.x, 0.0) / length2.sqrt()
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = wh.cross(&t1);
+
+        // A uniform disk point, squashed onto the part of the hemisphere visible from `wh`
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + wh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+        let pz = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        let nh = t1 * p1 + t2 * p2 + wh * pz;
+
+        Vector3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).unit_vector()
+    }
+}
+
+/// The Fresnel reflectance of a conductor with a complex index of refraction, for unpolarized light.
+///
+/// # Arguments
+///
+/// * `cos_theta` - The cosine between the incoming direction and the normal.
+/// * `eta` - The real part of the index of refraction.
+/// * `k` - The imaginary part, the absorption coefficient.
+///
+/// # Returns
+///
+/// The fraction of light reflected.
+pub(crate) fn fresnel_conductor(cos_theta: f32, eta: f32, k: f32) -> f32 {
+    let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let (eta2, k2) = (eta * eta, k * k);
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).max(0.0).sqrt();
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+
+    let t1 = a2_plus_b2 + cos2;
+    let t2 = 2.0 * cos2.sqrt() * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    0.5 * (rp + rs)
+}
+
+/// The Fresnel reflectance of a smooth interface between two dielectrics, for unpolarized light.
+///
+/// # Arguments
+///
+/// * `cos_theta` - The cosine between the incoming direction and the normal, negative from the inside.
+/// * `eta` - The index of refraction inside over the one outside.
+///
+/// # Returns
+///
+/// The fraction of light reflected, one under total internal reflection.
+pub(crate) fn fresnel_dielectric(cos_theta: f32, eta: f32) -> f32 {
+    let (mut cos_i, mut eta) = (cos_theta.clamp(-1.0, 1.0), eta);
+    if cos_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+    }
+
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).max(0.0).sqrt();
+
+    let parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (parallel * parallel + perpendicular * perpendicular)
+}
+
+/// Reflects `w` about the microfacet normal `wm`, both pointing away from the surface.
+#[inline]
+pub(crate) fn reflect(w: &Vector3, wm: &Vector3) -> Vector3 {
+    -*w + *wm * (2.0 * w.dot(wm))
+}
+
+/// Refracts `w` through the microfacet normal `wm`, on the same side as `w`.
+///
+/// # Arguments
+///
+/// * `w` - The direction, pointing away from the surface.
+/// * `wm` - The microfacet normal.
+/// * `eta` - The index of refraction on the far side over the one on `w`'s side.
+///
+/// # Returns
+///
+/// The refracted direction, or `None` under total internal reflection.
+pub(crate) fn refract(w: &Vector3, wm: &Vector3, eta: f32) -> Option<Vector3> {
+    let cos_i = w.dot(wm);
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-*w / eta + *wm * (cos_i / eta - cos_t))
+}
+
+/// The microfacet normal that scatters `wo` into `wi`, by reflection or refraction.
+///
+/// # Arguments
+///
+/// * `wo` - The outgoing direction, above the surface.
+/// * `wi` - The incoming direction, below the surface for refraction.
+/// * `eta` - The index of refraction below the surface over the one above.
+///
+/// # Returns
+///
+/// The normal in the upper hemisphere, or `None` when the pair can't be connected by a facet both see the front of.
+pub(crate) fn half_vector(wo: &Vector3, wi: &Vector3, eta: f32) -> Option<Vector3> {
+    if wo.z <= 0.0 || wi.z == 0.0 {
+        return None;
+    }
+
+    let wm = if wi.z > 0.0 { *wi + *wo } else { *wi * eta + *wo };
+    if wm.len_squared() == 0.0 {
+        return None;
+    }
+    let mut wm = wm.unit_vector();
+    if wm.z < 0.0 {
+        wm = -wm;
+    }
+
+    // Back facing microfacets don't contribute
+    if wm.dot(wi) * wi.z < 0.0 || wm.dot(wo) <= 0.0 {
+        return None;
+    }
+    Some(wm)
+}
+
+#[cfg(test)]
+pub(crate) mod test_microfacet {
+    use crate::engine::base::constants::constants::{random_float, PI};
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::microfacet::{fresnel_conductor, TrowbridgeReitz};
+    use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::util::color::Color;
+
+    /// Checks a glossy material's sampling against its `eval` and `pdf`, for the materials built on microfacets.
+    ///
+    /// Every scattered direction must be weighted by `eval / pdf`, the weights must average to the BSDF
+    /// integrated over the sphere, and the density can't integrate to more than one.
+    ///
+    /// # Returns
+    ///
+    /// The integrated BSDF, the green channel's albedo, and the integrated density.
+    pub(crate) fn assert_sampling_matches_eval(material: &MaterialType, ray: &Ray, rec: &HitRecord) -> (f32, f32) {
+        let samples = 50_000;
+        let mut sampled = 0.0;
+        for _ in 0..samples {
+            let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+            if material.scatter(ray, &mut scattered, rec, &mut attenuation) {
+                let expected = material.eval(ray, rec, &scattered.direction).g / material.pdf(ray, rec, &scattered.direction);
+                assert!((attenuation.g - expected).abs() < 1e-3 * expected.max(1.0), "{} vs {expected}", attenuation.g);
+                sampled += attenuation.g / samples as f32;
+            }
+        }
+
+        // The reflected and transmitted light, and the density, with a midpoint rule over the sphere
+        let steps = 600;
+        let solid_angle = 4.0 * PI / (2 * steps * steps) as f32;
+        let (mut integrated, mut pdf_integral) = (0.0, 0.0);
+        for i in 0..steps {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / steps as f32;
+            let r = (1.0 - y * y).sqrt();
+            for j in 0..2 * steps {
+                let phi = PI * (j as f32 + 0.5) / steps as f32;
+                let direction = Vector3::new(r * phi.cos(), y, r * phi.sin());
+                integrated += material.eval(ray, rec, &direction).g * solid_angle;
+                pdf_integral += material.pdf(ray, rec, &direction) * solid_angle;
+            }
+        }
+
+        assert!((sampled - integrated).abs() < 0.03, "sampling gives {sampled}, integration {integrated}");
+        assert!(pdf_integral < 1.02, "the density integrates to {pdf_integral}");
+        (integrated, pdf_integral)
+    }
+
+    #[test]
+    fn visible_normals_are_a_normalized_density(){
+        let distribution = TrowbridgeReitz::new(0.3, 0.6);
+        let wo = Vector3::new(0.5, -0.2, 0.6).unit_vector();
+
+        // The visible normal density integrates to one, with a midpoint rule over the hemisphere
+        let steps = 800;
+        let mut integral = 0.0;
+        for i in 0..steps {
+            let z = (i as f32 + 0.5) / steps as f32;
+            let r = (1.0 - z * z).sqrt();
+            for j in 0..steps {
+                let phi = 2.0 * PI * (j as f32 + 0.5) / steps as f32;
+                let wm = Vector3::new(r * phi.cos(), r * phi.sin(), z);
+                integral += distribution.visible_d(&wo, &wm) * 2.0 * PI / (steps * steps) as f32;
+            }
+        }
+        assert!((integral - 1.0).abs() < 0.01, "visible normals integrate to {integral}");
+
+        for _ in 0..1000 {
+            let wm = distribution.sample_wm(&wo, random_float(), random_float());
+            assert!(wm.z > 0.0 && wo.dot(&wm) >= -1e-4);
+        }
+    }
+
+    #[test]
+    fn conductor_fresnel_reduces_to_a_dielectric(){
+        // Without absorption, normal incidence gives ((n - 1) / (n + 1))^2
+        assert!((fresnel_conductor(1.0, 1.5, 0.0) - 0.04).abs() < 1e-5);
+        // And grazing light is always fully reflected
+        assert!((fresnel_conductor(0.0, 0.2, 3.9) - 1.0).abs() < 1e-4);
+    }
+}
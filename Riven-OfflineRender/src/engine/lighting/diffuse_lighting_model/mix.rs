@@ -0,0 +1,149 @@
+use crate::engine::base::constants::constants::random_float;
+use crate::engine::base::point::Point3;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::textures::solid_color::SolidColor;
+use crate::engine::textures::{Texture, TextureType};
+use crate::util::color::Color;
+
+/// A blend of two materials, like rust over paint or dust on glass.
+///
+/// When both materials can be evaluated, the blend's BSDF and density are the weighted sums of theirs,
+/// so it is lit by light sampling like any other surface. Otherwise every scattering event picks one
+/// of the two at random, in proportion to the blend.
+#[derive(Clone, Default)]
+pub struct Mix {
+    first: Box<MaterialType>,
+    second: Box<MaterialType>,
+    /// How much of `second` there is, read from the texture's red channel.
+    amount: TextureType,
+}
+
+impl Mix {
+    /// Creates a uniform blend of two materials.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The material showing where `amount` is 0.
+    /// * `second` - The material showing where `amount` is 1.
+    /// * `amount` - How much of `second` there is.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Mix`.
+    pub fn new(first: MaterialType, second: MaterialType, amount: f32) -> MaterialType {
+        let amount = amount.clamp(0.0, 1.0);
+        Self::from_texture(first, second, SolidColor::from_rgb(amount, amount, amount))
+    }
+
+    /// Creates a blend of two materials that varies over the surface, like a rust mask.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The material showing where `amount` is 0.
+    /// * `second` - The material showing where `amount` is 1.
+    /// * `amount` - How much of `second` there is, read from the texture's red channel.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Mix`.
+    pub fn from_texture(first: MaterialType, second: MaterialType, amount: TextureType) -> MaterialType {
+        MaterialType::Mix(Mix {
+            first: Box::new(first),
+            second: Box::new(second),
+            amount,
+        })
+    }
+
+    fn amount(&self, u: f32, v: f32, point: Point3) -> f32 {
+        self.amount.value(u, v, point).r.clamp(0.0, 1.0)
+    }
+}
+
+impl DiffuseMaterial for Mix {
+    fn scatter(&self, ray_in: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
+        let amount = self.amount(hit_record.u, hit_record.v, hit_record.point);
+        let chosen = if random_float() < amount { &self.second } else { &self.first };
+        if !chosen.scatter(ray_in, scattered_ray, hit_record, attenuation) {
+            return false;
+        }
+
+        // Picking in proportion to the blend already weights a specular sample correctly
+        if self.is_specular() {
+            return true;
+        }
+
+        let pdf = self.pdf(ray_in, hit_record, &scattered_ray.direction);
+        if pdf <= 0.0 {
+            return false;
+        }
+        *attenuation = (1.0 / pdf) * self.eval(ray_in, hit_record, &scattered_ray.direction);
+        true
+    }
+
+    fn emitted(&self, u: f32, v: f32, point: Point3) -> Color {
+        let amount = self.amount(u, v, point);
+        (1.0 - amount) * self.first.emitted(u, v, point) + amount * self.second.emitted(u, v, point)
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> Color {
+        let amount = self.amount(hit_record.u, hit_record.v, hit_record.point);
+        (1.0 - amount) * self.first.eval(ray_in, hit_record, direction)
+            + amount * self.second.eval(ray_in, hit_record, direction)
+    }
+
+    fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> f32 {
+        let amount = self.amount(hit_record.u, hit_record.v, hit_record.point);
+        (1.0 - amount) * self.first.pdf(ray_in, hit_record, direction)
+            + amount * self.second.pdf(ray_in, hit_record, direction)
+    }
+
+    fn is_specular(&self) -> bool {
+        self.first.is_specular() || self.second.is_specular()
+    }
+
+    fn clone_box(&self) -> MaterialType {
+        MaterialType::Mix(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test_mix {
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::dielectric::Dielectric;
+    use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+    use crate::engine::lighting::diffuse_lighting_model::metal::Metal;
+    use crate::engine::lighting::diffuse_lighting_model::microfacet::test_microfacet::assert_sampling_matches_eval;
+    use crate::engine::lighting::diffuse_lighting_model::mix::Mix;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::util::color::Color;
+
+    #[test]
+    fn blend_weights_both_materials(){
+        let mix = Mix::new(Lambertian::new(0.8, 0.2, 0.2), Metal::new(0.9, 0.9, 0.9, 0.4), 0.3);
+        let ray = Ray::new(Point3::new(-1.0, 1.0, 0.0), Vector3::new(0.6, -0.8, 0.0));
+        let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face: true, ..Default::default() };
+        assert!(!mix.is_specular());
+
+        // Samples are weighted by the blended BSDF over the blended density
+        assert_sampling_matches_eval(&mix, &ray, &rec);
+
+        // Blending with a specular material falls back to picking one of the two
+        let dusty_glass = Mix::new(Dielectric::new(1.5), Lambertian::new(0.5, 0.5, 0.5), 0.5);
+        assert!(dusty_glass.is_specular());
+        let (mut picked_glass, samples) = (0, 10_000);
+        for _ in 0..samples {
+            let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+            assert!(dusty_glass.scatter(&ray, &mut scattered, &rec, &mut attenuation));
+            if attenuation.r == 1.0 {
+                picked_glass += 1;
+            }
+        }
+        assert!((picked_glass as f32 / samples as f32 - 0.5).abs() < 0.03);
+    }
+}
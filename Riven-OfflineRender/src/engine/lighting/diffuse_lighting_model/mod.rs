@@ -1,8 +1,19 @@
+use crate::engine::base::point::Point3;
 use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::diffuse_lighting_model::bump::Bump;
+use crate::engine::lighting::diffuse_lighting_model::coated::Coated;
 use crate::engine::lighting::diffuse_lighting_model::dielectric::Dielectric;
+use crate::engine::lighting::diffuse_lighting_model::diffuse_light::DiffuseLight;
+use crate::engine::lighting::diffuse_lighting_model::henyey_greenstein::HenyeyGreenstein;
+use crate::engine::lighting::diffuse_lighting_model::isotropic::Isotropic;
 use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
 use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
 use crate::engine::lighting::diffuse_lighting_model::metal::Metal;
+use crate::engine::lighting::diffuse_lighting_model::mix::Mix;
+use crate::engine::lighting::diffuse_lighting_model::principled::Principled;
+use crate::engine::lighting::diffuse_lighting_model::rough_dielectric::RoughDielectric;
+use crate::engine::lighting::diffuse_lighting_model::subsurface::Subsurface;
 use crate::engine::objects::hit_record::HitRecord;
 use crate::util::color::Color;
 
@@ -10,6 +21,17 @@ pub mod material;
 pub mod lambertian;
 pub mod metal;
 pub mod dielectric;
+pub mod rough_dielectric;
+pub mod principled;
+pub mod mix;
+pub mod coated;
+pub mod subsurface;
+pub mod bump;
+pub mod diffuse_light;
+pub mod material_arena;
+pub mod isotropic;
+pub mod henyey_greenstein;
+pub(crate) mod microfacet;
 
 
 #[derive(Clone)]
@@ -18,6 +40,15 @@ pub enum MaterialType {
     Lambertian(Lambertian),
     Metal(Metal),
     Dielectric(Dielectric),
+    RoughDielectric(RoughDielectric),
+    Principled(Principled),
+    Mix(Mix),
+    Coated(Coated),
+    Subsurface(Subsurface),
+    Bump(Bump),
+    DiffuseLight(DiffuseLight),
+    Isotropic(Isotropic),
+    HenyeyGreenstein(HenyeyGreenstein),
 }
 
 
@@ -27,6 +58,83 @@ impl MaterialType{
             MaterialType::Lambertian(lambertian) => lambertian.scatter(ray_in, scattered_ray, hit_record, attenuation),
             MaterialType::Metal(metal) => metal.scatter(ray_in, scattered_ray, hit_record, attenuation),
             MaterialType::Dielectric(dielectric) => dielectric.scatter(ray_in, scattered_ray, hit_record, attenuation),
+            MaterialType::RoughDielectric(glass) => glass.scatter(ray_in, scattered_ray, hit_record, attenuation),
+            MaterialType::Principled(principled) => principled.scatter(ray_in, scattered_ray, hit_record, attenuation),
+            MaterialType::Mix(mix) => mix.scatter(ray_in, scattered_ray, hit_record, attenuation),
+            MaterialType::Coated(coated) => coated.scatter(ray_in, scattered_ray, hit_record, attenuation),
+            MaterialType::Subsurface(subsurface) => subsurface.scatter(ray_in, scattered_ray, hit_record, attenuation),
+            MaterialType::Bump(bump) => bump.scatter(ray_in, scattered_ray, hit_record, attenuation),
+            MaterialType::DiffuseLight(light) => light.scatter(ray_in, scattered_ray, hit_record, attenuation),
+            MaterialType::Isotropic(isotropic) => isotropic.scatter(ray_in, scattered_ray, hit_record, attenuation),
+            MaterialType::HenyeyGreenstein(phase) => phase.scatter(ray_in, scattered_ray, hit_record, attenuation),
+        }
+    }
+
+    pub fn emitted(&self, u: f32, v: f32, point: Point3) -> Color {
+        match self {
+            MaterialType::Lambertian(lambertian) => lambertian.emitted(u, v, point),
+            MaterialType::Metal(metal) => metal.emitted(u, v, point),
+            MaterialType::Dielectric(dielectric) => dielectric.emitted(u, v, point),
+            MaterialType::RoughDielectric(glass) => glass.emitted(u, v, point),
+            MaterialType::Principled(principled) => principled.emitted(u, v, point),
+            MaterialType::Mix(mix) => mix.emitted(u, v, point),
+            MaterialType::Coated(coated) => coated.emitted(u, v, point),
+            MaterialType::Subsurface(subsurface) => subsurface.emitted(u, v, point),
+            MaterialType::Bump(bump) => bump.emitted(u, v, point),
+            MaterialType::DiffuseLight(light) => light.emitted(u, v, point),
+            MaterialType::Isotropic(isotropic) => isotropic.emitted(u, v, point),
+            MaterialType::HenyeyGreenstein(phase) => phase.emitted(u, v, point),
+        }
+    }
+
+    pub fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> Color {
+        match self {
+            MaterialType::Lambertian(lambertian) => lambertian.eval(ray_in, hit_record, direction),
+            MaterialType::Metal(metal) => metal.eval(ray_in, hit_record, direction),
+            MaterialType::Dielectric(dielectric) => dielectric.eval(ray_in, hit_record, direction),
+            MaterialType::RoughDielectric(glass) => glass.eval(ray_in, hit_record, direction),
+            MaterialType::Principled(principled) => principled.eval(ray_in, hit_record, direction),
+            MaterialType::Mix(mix) => mix.eval(ray_in, hit_record, direction),
+            MaterialType::Coated(coated) => coated.eval(ray_in, hit_record, direction),
+            MaterialType::Subsurface(subsurface) => subsurface.eval(ray_in, hit_record, direction),
+            MaterialType::Bump(bump) => bump.eval(ray_in, hit_record, direction),
+            MaterialType::DiffuseLight(light) => light.eval(ray_in, hit_record, direction),
+            MaterialType::Isotropic(isotropic) => isotropic.eval(ray_in, hit_record, direction),
+            MaterialType::HenyeyGreenstein(phase) => phase.eval(ray_in, hit_record, direction),
+        }
+    }
+
+    pub fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> f32 {
+        match self {
+            MaterialType::Lambertian(lambertian) => lambertian.pdf(ray_in, hit_record, direction),
+            MaterialType::Metal(metal) => metal.pdf(ray_in, hit_record, direction),
+            MaterialType::Dielectric(dielectric) => dielectric.pdf(ray_in, hit_record, direction),
+            MaterialType::RoughDielectric(glass) => glass.pdf(ray_in, hit_record, direction),
+            MaterialType::Principled(principled) => principled.pdf(ray_in, hit_record, direction),
+            MaterialType::Mix(mix) => mix.pdf(ray_in, hit_record, direction),
+            MaterialType::Coated(coated) => coated.pdf(ray_in, hit_record, direction),
+            MaterialType::Subsurface(subsurface) => subsurface.pdf(ray_in, hit_record, direction),
+            MaterialType::Bump(bump) => bump.pdf(ray_in, hit_record, direction),
+            MaterialType::DiffuseLight(light) => light.pdf(ray_in, hit_record, direction),
+            MaterialType::Isotropic(isotropic) => isotropic.pdf(ray_in, hit_record, direction),
+            MaterialType::HenyeyGreenstein(phase) => phase.pdf(ray_in, hit_record, direction),
+        }
+    }
+
+    pub fn is_specular(&self) -> bool {
+        match self {
+            MaterialType::Lambertian(lambertian) => lambertian.is_specular(),
+            MaterialType::Metal(metal) => metal.is_specular(),
+            MaterialType::Dielectric(dielectric) => dielectric.is_specular(),
+            MaterialType::RoughDielectric(glass) => glass.is_specular(),
+            MaterialType::Principled(principled) => principled.is_specular(),
+            MaterialType::Mix(mix) => mix.is_specular(),
+            MaterialType::Coated(coated) => coated.is_specular(),
+            MaterialType::Subsurface(subsurface) => subsurface.is_specular(),
+            MaterialType::Bump(bump) => bump.is_specular(),
+            MaterialType::DiffuseLight(light) => light.is_specular(),
+            MaterialType::Isotropic(isotropic) => isotropic.is_specular(),
+            MaterialType::HenyeyGreenstein(phase) => phase.is_specular(),
         }
     }
 
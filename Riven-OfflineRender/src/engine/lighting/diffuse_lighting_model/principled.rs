@@ -0,0 +1,423 @@
+use crate::engine::base::constants::constants::{random_float, PI};
+use crate::engine::base::onb::Onb;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::lighting::diffuse_lighting_model::microfacet::{fresnel_dielectric, half_vector, reflect, refract, TrowbridgeReitz};
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::textures::solid_color::SolidColor;
+use crate::engine::textures::{Texture, TextureType};
+use crate::util::color::Color;
+
+/// The parameters of a `Principled` material, each one read from a texture.
+///
+/// Scalar parameters are read from the texture's red channel and are expected between 0 and 1.
+#[derive(Clone)]
+pub struct PrincipledParameters {
+    pub base_color: TextureType,
+    /// 0 for a dielectric, 1 for a metal tinted by the base color.
+    pub metallic: TextureType,
+    /// Perceptual roughness of the specular and transmission lobes.
+    pub roughness: TextureType,
+    /// The dielectric reflectance at normal incidence, 0.5 maps to 4% and an index of refraction of 1.5.
+    pub specular: TextureType,
+    /// A soft, half tinted retro-reflection at grazing angles, for cloth.
+    pub sheen: TextureType,
+    /// The strength of a clear varnish over the surface.
+    pub clearcoat: TextureType,
+    /// 0 for an opaque surface, 1 for glass tinted by the base color.
+    pub transmission: TextureType,
+    /// Stretches the specular highlight along the surface's `u` direction, `dpdu`.
+    pub anisotropy: TextureType,
+}
+
+impl Default for PrincipledParameters {
+    fn default() -> Self {
+        Self {
+            base_color: SolidColor::from_rgb(0.8, 0.8, 0.8),
+            metallic: scalar(0.0),
+            roughness: scalar(0.5),
+            specular: scalar(0.5),
+            sheen: scalar(0.0),
+            clearcoat: scalar(0.0),
+            transmission: scalar(0.0),
+            anisotropy: scalar(0.0),
+        }
+    }
+}
+
+/// The Disney principled BSDF, one material covering plastics, metals, cloth, varnish and glass.
+///
+/// Follows Burley's 2015 extension: a diffuse lobe with retro-reflection and sheen, an anisotropic
+/// GGX specular lobe, a GTR1 clearcoat lobe and a GGX transmission lobe. Directions are sampled by
+/// picking a lobe, and weighted against the combined density of every lobe.
+#[derive(Clone)]
+pub struct Principled {
+    parameters: PrincipledParameters,
+}
+
+/// Every parameter evaluated at one surface point, with the lobe weights derived from them.
+struct Lobes {
+    base_color: Color,
+    roughness: f32,
+    metallic: f32,
+    sheen: f32,
+    clearcoat: f32,
+    /// Index of refraction across the surface over the one on the incoming side.
+    eta: f32,
+    /// Reflectance at normal incidence of the specular lobe.
+    specular_color: Color,
+    sheen_color: Color,
+    transmission_color: Color,
+    distribution: TrowbridgeReitz,
+    diffuse_weight: f32,
+    transmission_weight: f32,
+    /// Probabilities of sampling the diffuse, specular, clearcoat and transmission lobes.
+    probabilities: [f32; 4],
+}
+
+impl Principled {
+    /// Alpha of the clearcoat's GTR1 distribution, a glossy varnish.
+    const CLEARCOAT_ALPHA: f32 = 0.01;
+
+    /// Creates a new `Principled` with constant parameters, the rest at their defaults.
+    ///
+    /// # Arguments
+    ///
+    /// * `r`, `g`, `b` - The base color.
+    /// * `metallic` - 0 for a dielectric, 1 for a metal.
+    /// * `roughness` - The perceptual roughness.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Principled`.
+    pub fn new(r: f32, g: f32, b: f32, metallic: f32, roughness: f32) -> MaterialType {
+        Self::from_parameters(PrincipledParameters {
+            base_color: SolidColor::from_rgb(r, g, b),
+            metallic: scalar(metallic),
+            roughness: scalar(roughness),
+            ..Default::default()
+        })
+    }
+
+    /// Creates a new `Principled` from textured parameters, as exported by glTF or USD.
+    ///
+    /// # Arguments
+    ///
+    /// * `parameters` - The material's parameters.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Principled`.
+    pub fn from_parameters(parameters: PrincipledParameters) -> MaterialType {
+        MaterialType::Principled(Principled { parameters })
+    }
+
+    fn lobes(&self, hit_record: &HitRecord) -> Lobes {
+        let value = |texture: &TextureType| texture.value(hit_record.u, hit_record.v, hit_record.point);
+        let parameter = |texture: &TextureType| value(texture).r.clamp(0.0, 1.0);
+
+        let base_color = value(&self.parameters.base_color);
+        let metallic = parameter(&self.parameters.metallic);
+        let roughness = parameter(&self.parameters.roughness);
+        let sheen = parameter(&self.parameters.sheen);
+        let clearcoat = parameter(&self.parameters.clearcoat);
+        let transmission = parameter(&self.parameters.transmission);
+        let anisotropy = parameter(&self.parameters.anisotropy);
+
+        // Specular's normal reflectance gives the index of refraction, shared by transmission
+        let f0 = (0.08 * parameter(&self.parameters.specular)).min(0.99);
+        let ior = (1.0 + f0.sqrt()) / (1.0 - f0.sqrt());
+        // An opaque surface has no inside, it looks the same from both sides
+        let eta = if hit_record.front_face || transmission == 0.0 { ior } else { 1.0 / ior };
+
+        let aspect = (1.0 - 0.9 * anisotropy).sqrt();
+        let alpha = TrowbridgeReitz::roughness_to_alpha(roughness);
+
+        let luminance = base_color.luminance();
+        let tint = if luminance > 0.0 { (1.0 / luminance) * base_color } else { Color::new(1.0, 1.0, 1.0) };
+
+        let diffuse_weight = (1.0 - metallic) * (1.0 - transmission);
+        let transmission_weight = (1.0 - metallic) * transmission;
+        let weights = [diffuse_weight, 1.0 - 0.5 * diffuse_weight, 0.25 * clearcoat, transmission_weight];
+        let total: f32 = weights.iter().sum();
+
+        Lobes {
+            base_color,
+            roughness,
+            metallic,
+            sheen,
+            clearcoat,
+            eta,
+            specular_color: lerp(Color::new(f0, f0, f0), base_color, metallic),
+            sheen_color: lerp(Color::new(1.0, 1.0, 1.0), tint, 0.5),
+            transmission_color: Color::new(base_color.r.sqrt(), base_color.g.sqrt(), base_color.b.sqrt()),
+            distribution: TrowbridgeReitz::new(alpha / aspect, alpha * aspect),
+            diffuse_weight,
+            transmission_weight,
+            probabilities: weights.map(|weight| weight / total),
+        }
+    }
+
+    /// The BSDF times the cosine, and the density `scatter` picks `wi` with, in the local shading frame.
+    fn evaluate(&self, lobes: &Lobes, wo: &Vector3, wi: &Vector3) -> (Color, f32) {
+        let [p_diffuse, p_specular, p_clearcoat, p_transmission] = lobes.probabilities;
+        let mut f = Color::new(0.0, 0.0, 0.0);
+        let mut pdf = 0.0;
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return (f, pdf);
+        }
+
+        if wi.z < 0.0 {
+            if lobes.transmission_weight <= 0.0 {
+                return (f, pdf);
+            }
+            let Some(wm) = half_vector(wo, wi, lobes.eta) else {
+                return (f, pdf);
+            };
+
+            let distribution = &lobes.distribution;
+            let transmitted = 1.0 - fresnel_dielectric(wo.dot(&wm), lobes.eta);
+            let denominator = (wi.dot(&wm) + wo.dot(&wm) / lobes.eta).powi(2);
+            let scale = distribution.d(&wm) * distribution.g(wo, wi) * transmitted
+                * (wi.dot(&wm) * wo.dot(&wm)).abs() / (wo.z * denominator);
+
+            f = (lobes.transmission_weight * scale) * lobes.transmission_color;
+            pdf = p_transmission * distribution.visible_d(wo, &wm) * wi.dot(&wm).abs() / denominator;
+            return (f, pdf);
+        }
+
+        let wh = (*wo + *wi).unit_vector();
+        let cos_d = wi.dot(&wh);
+
+        if lobes.diffuse_weight > 0.0 {
+            let (fl, fv) = (schlick_weight(wi.z), schlick_weight(wo.z));
+            let retro = 2.0 * lobes.roughness * cos_d * cos_d;
+            let lambert = (1.0 - 0.5 * fl) * (1.0 - 0.5 * fv);
+            let retro_reflection = retro * (fl + fv + fl * fv * (retro - 1.0));
+
+            let diffuse = ((lambert + retro_reflection) / PI) * lobes.base_color;
+            let sheen = (lobes.sheen * schlick_weight(cos_d)) * lobes.sheen_color;
+            // Light reaches the base only through the specular interface, or grazing light would reflect twice
+            let through = (1.0 - fresnel_dielectric(wo.z, lobes.eta)) * (1.0 - fresnel_dielectric(wi.z, lobes.eta));
+            f = f + (lobes.diffuse_weight * through * wi.z) * (diffuse + sheen);
+            pdf += p_diffuse * wi.z / PI;
+        }
+
+        let distribution = &lobes.distribution;
+        let cos_h = wo.dot(&wh);
+        let fresnel = lerp(
+            fresnel_dielectric(cos_h, lobes.eta) * Color::new(1.0, 1.0, 1.0),
+            schlick(lobes.specular_color, cos_h),
+            lobes.metallic,
+        );
+        let specular = distribution.d(&wh) * distribution.g(wo, wi) / (4.0 * wo.z);
+        f = f + specular * fresnel;
+        pdf += p_specular * distribution.visible_d(wo, &wh) / (4.0 * cos_h);
+
+        if lobes.clearcoat > 0.0 {
+            let varnish = TrowbridgeReitz::new(0.25, 0.25);
+            let d = gtr1(wh.z, Self::CLEARCOAT_ALPHA);
+            let fresnel = 0.04 + 0.96 * schlick_weight(cos_h);
+            let coat = 0.25 * lobes.clearcoat * d * fresnel * varnish.g1(wo) * varnish.g1(wi) / (4.0 * wo.z);
+            f = f + Color::new(coat, coat, coat);
+            pdf += p_clearcoat * d * wh.z / (4.0 * cos_h);
+        }
+
+        (f, pdf)
+    }
+
+    /// Samples an incoming direction from one of the lobes, in the local shading frame.
+    fn sample(&self, lobes: &Lobes, wo: &Vector3) -> Option<Vector3> {
+        let [p_diffuse, p_specular, p_clearcoat, _] = lobes.probabilities;
+        let choice = random_float();
+
+        let wi = if choice < p_diffuse {
+            let (r1, r2) = (random_float(), random_float());
+            let phi = 2.0 * PI * r1;
+            Vector3::new(phi.cos() * r2.sqrt(), phi.sin() * r2.sqrt(), (1.0 - r2).sqrt())
+        } else if choice < p_diffuse + p_specular {
+            let wm = lobes.distribution.sample_wm(wo, random_float(), random_float());
+            reflect(wo, &wm)
+        } else if choice < p_diffuse + p_specular + p_clearcoat {
+            reflect(wo, &sample_gtr1(Self::CLEARCOAT_ALPHA))
+        } else {
+            let wm = lobes.distribution.sample_wm(wo, random_float(), random_float());
+            refract(wo, &wm, lobes.eta)?
+        };
+
+        (wi.z != 0.0).then_some(wi)
+    }
+}
+
+impl DiffuseMaterial for Principled {
+    fn scatter(&self, ray_in: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
+        let frame = Onb::from_tangent(hit_record.normal, hit_record.dpdu);
+        let wo = frame.to_local(-ray_in.direction.unit_vector());
+        if wo.z <= 0.0 {
+            return false;
+        }
+
+        let lobes = self.lobes(hit_record);
+        let Some(wi) = self.sample(&lobes, &wo) else {
+            return false;
+        };
+        let (f, pdf) = self.evaluate(&lobes, &wo, &wi);
+        if pdf <= 0.0 {
+            return false;
+        }
+
+        *scattered_ray = Ray::new(hit_record.point, frame.transform(wi));
+        *attenuation = (1.0 / pdf) * f;
+        true
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> Color {
+        let frame = Onb::from_tangent(hit_record.normal, hit_record.dpdu);
+        let wo = frame.to_local(-ray_in.direction.unit_vector());
+        let wi = frame.to_local(direction.unit_vector());
+        self.evaluate(&self.lobes(hit_record), &wo, &wi).0
+    }
+
+    fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> f32 {
+        let frame = Onb::from_tangent(hit_record.normal, hit_record.dpdu);
+        let wo = frame.to_local(-ray_in.direction.unit_vector());
+        let wi = frame.to_local(direction.unit_vector());
+        self.evaluate(&self.lobes(hit_record), &wo, &wi).1
+    }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    fn clone_box(&self) -> MaterialType {
+        MaterialType::Principled(self.clone())
+    }
+}
+
+fn scalar(value: f32) -> TextureType {
+    SolidColor::from_rgb(value, value, value)
+}
+
+#[inline]
+fn lerp(a: Color, b: Color, t: f32) -> Color {
+    (1.0 - t) * a + t * b
+}
+
+/// `(1 - cos)^5`, the shape of Schlick's Fresnel approximation.
+#[inline]
+fn schlick_weight(cos_theta: f32) -> f32 {
+    (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+#[inline]
+fn schlick(f0: Color, cos_theta: f32) -> Color {
+    lerp(f0, Color::new(1.0, 1.0, 1.0), schlick_weight(cos_theta))
+}
+
+/// Burley's GTR1 distribution, with the long tails of the clearcoat's highlight.
+fn gtr1(cos_theta: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    (alpha2 - 1.0) / (PI * alpha2.ln() * (1.0 + (alpha2 - 1.0) * cos_theta * cos_theta))
+}
+
+/// Samples a normal from the GTR1 distribution, proportionally to `gtr1(cos) * cos`.
+fn sample_gtr1(alpha: f32) -> Vector3 {
+    let alpha2 = alpha * alpha;
+    let cos_theta = ((1.0 - alpha2.powf(1.0 - random_float())) / (1.0 - alpha2)).max(0.0).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * random_float();
+    Vector3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+#[cfg(test)]
+mod test_principled {
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::microfacet::test_microfacet::assert_sampling_matches_eval;
+    use crate::engine::lighting::diffuse_lighting_model::principled::{scalar, Principled, PrincipledParameters};
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::textures::chess_board_texture::ChessBoardTexture;
+    use crate::engine::textures::solid_color::SolidColor;
+    use crate::util::color::Color;
+
+    #[test]
+    fn every_lobe_samples_consistently(){
+        let materials = [
+            Principled::new(0.8, 0.2, 0.1, 0.0, 0.5),
+            Principled::new(0.9, 0.7, 0.3, 1.0, 0.4),
+            Principled::from_parameters(PrincipledParameters {
+                base_color: SolidColor::from_rgb(0.3, 0.5, 0.9),
+                roughness: scalar(0.6),
+                sheen: scalar(1.0),
+                clearcoat: scalar(1.0),
+                anisotropy: scalar(0.8),
+                ..Default::default()
+            }),
+            Principled::from_parameters(PrincipledParameters {
+                base_color: SolidColor::from_rgb(0.9, 0.9, 0.9),
+                roughness: scalar(0.5),
+                transmission: scalar(1.0),
+                ..Default::default()
+            }),
+        ];
+        let ray = Ray::new(Point3::default(), Vector3::new(0.5, -0.8, 0.2));
+        let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face: true, dpdu: Vector3::new(0.6, 0.0, 0.8), ..Default::default() };
+
+        for material in &materials {
+            let (_, pdf_integral) = assert_sampling_matches_eval(material, &ray, &rec);
+            assert!(pdf_integral > 0.85, "the density integrates to {pdf_integral}");
+        }
+    }
+
+    #[test]
+    fn anisotropy_follows_the_tangent(){
+        let brushed = Principled::from_parameters(PrincipledParameters {
+            metallic: scalar(1.0),
+            roughness: scalar(0.3),
+            anisotropy: scalar(0.9),
+            ..Default::default()
+        });
+        let hit = |dpdu: Vector3| HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face: true, dpdu, ..Default::default() };
+        let (along_x, along_z) = (hit(Vector3::new(2.0, 0.0, 0.0)), hit(Vector3::new(0.0, 0.0, 3.0)));
+
+        // Turning the tangent and the light together by a quarter turn leaves the highlight unchanged
+        let ray = Ray::new(Point3::default(), Vector3::new(0.6, -0.8, 0.0));
+        let turned = Ray::new(Point3::default(), Vector3::new(0.0, -0.8, 0.6));
+        let direction = Vector3::new(-0.3, 0.8, 0.5);
+        let turned_direction = Vector3::new(-0.5, 0.8, -0.3);
+        let reference = brushed.eval(&ray, &along_x, &direction).g;
+        assert!((brushed.eval(&turned, &along_z, &turned_direction).g - reference).abs() < 1e-4 * reference.max(1.0));
+
+        // While turning only the tangent changes it
+        let off_mirror = Vector3::new(0.6, 0.8, 0.25);
+        let (a, b) = (brushed.eval(&ray, &along_x, &off_mirror).g, brushed.eval(&ray, &along_z, &off_mirror).g);
+        assert!(a > 1.5 * b || b > 1.5 * a, "{a} and {b}");
+    }
+
+    #[test]
+    fn textures_drive_the_parameters(){
+        let split = |low: f32, high: f32| ChessBoardTexture::new(
+            0.5, SolidColor::from_rgb(low, low, low), SolidColor::from_rgb(high, high, high),
+        );
+        let material = Principled::from_parameters(PrincipledParameters {
+            base_color: SolidColor::from_rgb(1.0, 0.2, 0.2),
+            metallic: split(0.0, 1.0),
+            roughness: scalar(0.3),
+            ..Default::default()
+        });
+
+        // Looking along the mirror direction, the metallic squares have a red highlight and the rest a white one
+        let ray = Ray::new(Point3::default(), Vector3::new(0.6, -0.8, 0.0));
+        let mirror = Vector3::new(0.6, 0.8, 0.0);
+        let colors: Vec<Color> = [Point3::new(0.25, 0.0, 0.25), Point3::new(0.75, 0.0, 0.25)].iter().map(|&point| {
+            let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face: true, point, ..Default::default() };
+            material.eval(&ray, &rec, &mirror)
+        }).collect();
+
+        let tint = |color: &Color| color.r / color.g;
+        assert!(tint(&colors[0]) > 2.0 * tint(&colors[1]) || tint(&colors[1]) > 2.0 * tint(&colors[0]));
+    }
+}
@@ -0,0 +1,335 @@
+use crate::engine::base::constants::constants::random_float;
+use crate::engine::base::onb::Onb;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::dielectric::smooth_scatter;
+use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::lighting::diffuse_lighting_model::microfacet::{fresnel_dielectric, half_vector, reflect, refract, TrowbridgeReitz};
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::textures::solid_color::SolidColor;
+use crate::engine::textures::{Texture, TextureType};
+use crate::util::color::Color;
+
+/// Frosted or ground glass, GGX microfacets that both reflect and refract, after Walter et al. 2007.
+///
+/// Light travelling inside the object is absorbed following Beer-Lambert's law, so thick parts
+/// get a deeper tint than thin ones. The object must be closed for the absorbed distance to make sense.
+/// With zero roughness it scatters exactly like `Dielectric`.
+#[derive(Clone, Default)]
+pub struct RoughDielectric {
+    refraction_index: f32,
+    roughness: TextureType,
+    /// The fraction of light absorbed per unit of distance inside, per channel.
+    absorption: Color,
+    /// Whether the surface is perfectly smooth.
+    polished: bool,
+}
+
+impl RoughDielectric {
+    /// Roughness below this makes a perfectly smooth interface.
+    const POLISHED_ROUGHNESS: f32 = 0.03;
+
+    /// Creates a new clear `RoughDielectric`.
+    ///
+    /// # Arguments
+    ///
+    /// * `refraction_index` - The index of refraction inside the object.
+    /// * `roughness` - The perceptual roughness, between 0 for polished glass and 1.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::RoughDielectric`.
+    pub fn new(refraction_index: f32, roughness: f32) -> MaterialType {
+        Self::with_absorption(refraction_index, roughness, Color::new(0.0, 0.0, 0.0))
+    }
+
+    /// Creates a new tinted `RoughDielectric`.
+    ///
+    /// # Arguments
+    ///
+    /// * `refraction_index` - The index of refraction inside the object.
+    /// * `roughness` - The perceptual roughness, between 0 for polished glass and 1.
+    /// * `absorption` - The absorption coefficient per unit of distance, per channel.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::RoughDielectric`.
+    pub fn with_absorption(refraction_index: f32, roughness: f32, absorption: Color) -> MaterialType {
+        let roughness = roughness.clamp(0.0, 1.0);
+        MaterialType::RoughDielectric(RoughDielectric {
+            refraction_index,
+            roughness: SolidColor::from_rgb(roughness, roughness, roughness),
+            absorption,
+            polished: roughness < Self::POLISHED_ROUGHNESS,
+        })
+    }
+
+    /// Creates a `RoughDielectric` whose roughness varies over the surface, like partly frosted glass.
+    ///
+    /// # Arguments
+    ///
+    /// * `refraction_index` - The index of refraction inside the object.
+    /// * `roughness` - The perceptual roughness, read from the texture's red channel.
+    /// * `absorption` - The absorption coefficient per unit of distance, per channel.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::RoughDielectric`.
+    pub fn from_roughness_texture(refraction_index: f32, roughness: TextureType, absorption: Color) -> MaterialType {
+        MaterialType::RoughDielectric(RoughDielectric {
+            refraction_index,
+            roughness,
+            absorption,
+            polished: false,
+        })
+    }
+
+    fn distribution(&self, hit_record: &HitRecord) -> TrowbridgeReitz {
+        let roughness = self.roughness.value(hit_record.u, hit_record.v, hit_record.point).r;
+        let alpha = TrowbridgeReitz::roughness_to_alpha(roughness);
+        TrowbridgeReitz::new(alpha, alpha)
+    }
+
+    /// The index of refraction across the surface over the one on the incoming ray's side.
+    fn relative_ior(&self, hit_record: &HitRecord) -> f32 {
+        if hit_record.front_face { self.refraction_index } else { 1.0 / self.refraction_index }
+    }
+
+    /// The light left after travelling from the previous interface, non-zero only for rays coming from inside.
+    fn transmittance(&self, ray_in: &Ray, hit_record: &HitRecord) -> Color {
+        if hit_record.front_face {
+            return Color::new(1.0, 1.0, 1.0);
+        }
+        let distance = hit_record.t * ray_in.direction.len();
+        Color::new(
+            (-self.absorption.r * distance).exp(),
+            (-self.absorption.g * distance).exp(),
+            (-self.absorption.b * distance).exp(),
+        )
+    }
+}
+
+impl DiffuseMaterial for RoughDielectric {
+    fn scatter(&self, ray_in: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
+        let transmittance = self.transmittance(ray_in, hit_record);
+
+        if self.polished {
+            *scattered_ray = Ray::new(hit_record.point, smooth_scatter(self.refraction_index, ray_in, hit_record));
+            *attenuation = transmittance;
+            return true;
+        }
+
+        let frame = Onb::new(hit_record.normal);
+        let wo = frame.to_local(-ray_in.direction.unit_vector());
+        if wo.z <= 0.0 {
+            return false;
+        }
+
+        let distribution = self.distribution(hit_record);
+        let eta = self.relative_ior(hit_record);
+        let wm = distribution.sample_wm(&wo, random_float(), random_float());
+
+        // Choosing reflection with the Fresnel probability cancels it out of the weight
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+        let wi = if random_float() < reflectance {
+            let wi = reflect(&wo, &wm);
+            if wi.z <= 0.0 {
+                return false;
+            }
+            wi
+        } else {
+            match refract(&wo, &wm, eta) {
+                Some(wi) if wi.z < 0.0 => wi,
+                _ => return false,
+            }
+        };
+
+        *scattered_ray = Ray::new(hit_record.point, frame.transform(wi));
+        // Like `Dielectric`, radiance isn't scaled by 1 / eta^2, the factors cancel for rays that leave the object again
+        *attenuation = (distribution.g(&wo, &wi) / distribution.g1(&wo)) * transmittance;
+        true
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> Color {
+        if self.polished {
+            return Color::default();
+        }
+
+        let frame = Onb::new(hit_record.normal);
+        let wo = frame.to_local(-ray_in.direction.unit_vector());
+        let wi = frame.to_local(direction.unit_vector());
+        let eta = self.relative_ior(hit_record);
+        let Some(wm) = half_vector(&wo, &wi, eta) else {
+            return Color::default();
+        };
+
+        let distribution = self.distribution(hit_record);
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+        let scale = if wi.z > 0.0 {
+            distribution.d(&wm) * distribution.g(&wo, &wi) * reflectance / (4.0 * wo.z)
+        } else {
+            let denominator = (wi.dot(&wm) + wo.dot(&wm) / eta).powi(2);
+            distribution.d(&wm) * distribution.g(&wo, &wi) * (1.0 - reflectance)
+                * (wi.dot(&wm) * wo.dot(&wm)).abs() / (wo.z * denominator)
+        };
+        scale * self.transmittance(ray_in, hit_record)
+    }
+
+    fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vector3) -> f32 {
+        if self.polished {
+            return 0.0;
+        }
+
+        let frame = Onb::new(hit_record.normal);
+        let wo = frame.to_local(-ray_in.direction.unit_vector());
+        let wi = frame.to_local(direction.unit_vector());
+        let eta = self.relative_ior(hit_record);
+        let Some(wm) = half_vector(&wo, &wi, eta) else {
+            return 0.0;
+        };
+
+        let visible = self.distribution(hit_record).visible_d(&wo, &wm);
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+        if wi.z > 0.0 {
+            reflectance * visible / (4.0 * wo.dot(&wm).abs())
+        } else {
+            let denominator = (wi.dot(&wm) + wo.dot(&wm) / eta).powi(2);
+            (1.0 - reflectance) * visible * wi.dot(&wm).abs() / denominator
+        }
+    }
+
+    fn is_specular(&self) -> bool {
+        self.polished
+    }
+
+    fn clone_box(&self) -> MaterialType {
+        MaterialType::RoughDielectric(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test_rough_dielectric {
+    use crate::engine::base::constants::constants::PI;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::dielectric::Dielectric;
+    use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+    use crate::engine::lighting::diffuse_lighting_model::microfacet::fresnel_dielectric;
+    use crate::engine::lighting::diffuse_lighting_model::rough_dielectric::RoughDielectric;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::util::color::Color;
+
+    /// The fraction of scattered rays that stay on the incoming side, and the mean scattered direction.
+    fn reflection_statistics(material: &MaterialType, ray: &Ray, rec: &HitRecord) -> (f32, Vector3) {
+        let samples = 40_000;
+        let (mut scattered_count, mut reflected, mut sum) = (0, 0, Vector3::new(0.0, 0.0, 0.0));
+        for _ in 0..samples {
+            let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+            if !material.scatter(ray, &mut scattered, rec, &mut attenuation) {
+                continue;
+            }
+            let direction = scattered.direction.unit_vector();
+            if direction.dot(&rec.normal) > 0.0 {
+                reflected += 1;
+            }
+            scattered_count += 1;
+            sum = sum + direction;
+        }
+        (reflected as f32 / scattered_count as f32, sum / scattered_count as f32)
+    }
+
+    #[test]
+    fn zero_roughness_reduces_to_dielectric(){
+        let smooth = Dielectric::new(1.5);
+        let rough = RoughDielectric::new(1.5, 0.0);
+        let nearly_smooth = RoughDielectric::new(1.5, 0.04);
+
+        for (direction, front_face) in [
+            (Vector3::new(0.2, -1.0, 0.0), true),
+            (Vector3::new(1.0, -0.4, 0.3), true),
+            (Vector3::new(0.3, -1.0, 0.1), false),
+        ] {
+            let ray = Ray::new(Point3::default(), direction);
+            let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face, t: 1.0, ..Default::default() };
+
+            let (expected, expected_mean) = reflection_statistics(&smooth, &ray, &rec);
+            let (fraction, mean) = reflection_statistics(&rough, &ray, &rec);
+            assert!((fraction - expected).abs() < 0.015, "reflects {fraction} of rays instead of {expected}");
+            assert!((mean - expected_mean).len() < 0.02);
+
+            // Barely rough glass scatters close to the two smooth directions, with the exact Fresnel term
+            let unit_direction = direction.unit_vector();
+            let eta = if front_face { 1.5 } else { 1.0 / 1.5 };
+            let reflected = unit_direction.reflect(&rec.normal);
+            let refracted = unit_direction.refract(&rec.normal, 1.0 / eta).unit_vector();
+            let expected = fresnel_dielectric(-unit_direction.dot(&rec.normal), eta);
+
+            let (fraction, _) = reflection_statistics(&nearly_smooth, &ray, &rec);
+            assert!((fraction - expected).abs() < 0.015, "reflects {fraction} of rays instead of {expected}");
+
+            // GGX has long tails, so a few directions stray further
+            let close = (0..1000).filter(|_| {
+                let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+                nearly_smooth.scatter(&ray, &mut scattered, &rec, &mut attenuation) && {
+                    let direction = scattered.direction.unit_vector();
+                    direction.dot(&reflected).max(direction.dot(&refracted)) > 0.99
+                }
+            }).count();
+            assert!(close > 980, "only {close} directions are close to the smooth ones");
+        }
+    }
+
+    #[test]
+    fn sampling_agrees_with_eval(){
+        let glass = RoughDielectric::new(1.5, 0.5);
+        let ray = Ray::new(Point3::default(), Vector3::new(0.6, -0.8, 0.1));
+
+        for front_face in [true, false] {
+            let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face, t: 1.0, ..Default::default() };
+
+            let samples = 100_000;
+            let mut sampled = 0.0;
+            for _ in 0..samples {
+                let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+                if glass.scatter(&ray, &mut scattered, &rec, &mut attenuation) {
+                    let pdf = glass.pdf(&ray, &rec, &scattered.direction);
+                    let expected = glass.eval(&ray, &rec, &scattered.direction).r / pdf;
+                    assert!((attenuation.r - expected).abs() < 1e-3 * expected.max(1.0), "{} vs {expected}", attenuation.r);
+                    sampled += attenuation.r / samples as f32;
+                }
+            }
+
+            // Both the reflected and the transmitted light, integrated with a midpoint rule over the sphere
+            let steps = 1000;
+            let mut integrated = 0.0;
+            for i in 0..steps {
+                let y = 1.0 - 2.0 * (i as f32 + 0.5) / steps as f32;
+                let r = (1.0 - y * y).sqrt();
+                for j in 0..2 * steps {
+                    let phi = PI * (j as f32 + 0.5) / steps as f32;
+                    let direction = Vector3::new(r * phi.cos(), y, r * phi.sin());
+                    integrated += glass.eval(&ray, &rec, &direction).r * 4.0 * PI / (2 * steps * steps) as f32;
+                }
+            }
+
+            assert!(sampled <= 1.0);
+            assert!((sampled - integrated).abs() < 0.02, "sampling gives {sampled}, integration {integrated}");
+        }
+    }
+
+    #[test]
+    fn thick_glass_absorbs_more(){
+        let glass = RoughDielectric::with_absorption(1.5, 0.0, Color::new(0.5, 0.0, 0.0));
+        let ray = Ray::new(Point3::default(), Vector3::new(0.0, -2.0, 0.0));
+
+        for (front_face, t, expected) in [(true, 3.0, 1.0), (false, 1.0, (-1.0f32).exp()), (false, 2.0, (-2.0f32).exp())] {
+            let rec = HitRecord { normal: Vector3::new(0.0, 1.0, 0.0), front_face, t, ..Default::default() };
+            let (mut scattered, mut attenuation) = (Ray::default(), Color::default());
+            assert!(glass.scatter(&ray, &mut scattered, &rec, &mut attenuation));
+            assert!((attenuation.r - expected).abs() < 1e-5);
+            assert_eq!(attenuation.g, 1.0);
+        }
+    }
+}
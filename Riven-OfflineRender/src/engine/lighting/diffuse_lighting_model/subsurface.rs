@@ -0,0 +1,247 @@
+use crate::engine::base::constants::constants::{random_float, PI};
+use crate::engine::base::interval::Interval;
+use crate::engine::base::onb::Onb;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::henyey_greenstein::HenyeyGreenstein;
+use crate::engine::lighting::diffuse_lighting_model::material::DiffuseMaterial;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::objects::object::SHADOW_EPSILON;
+use crate::engine::scene::Scene;
+use crate::engine::textures::solid_color::SolidColor;
+use crate::engine::textures::{Texture, TextureType};
+use crate::util::color::Color;
+
+/// Translucent matter like skin, wax, marble or milk, lit by a random walk under its surface.
+///
+/// Light enters diffusely, scatters through the object as in a homogeneous medium, and leaves
+/// diffusely from wherever the walk crosses the object's boundary. The walk is found by the path
+/// tracer, which traces it through the scene and so works with any closed mesh or `Sphere`.
+/// Tracers that can't walk see a `Lambertian` surface of the scatter color.
+#[derive(Clone, Default)]
+pub struct Subsurface {
+    /// The color of the object seen from afar, once light has scattered through it, per channel.
+    scatter_color: TextureType,
+    /// The average distance light travels between two scattering events inside, per channel.
+    mean_free_path: Color,
+    /// The phase function of the medium inside, a white `HenyeyGreenstein`.
+    phase_function: Box<MaterialType>,
+}
+
+impl Subsurface {
+    /// Walks longer than this are ended, they carry next to no light.
+    const MAX_STEPS: u32 = 256;
+
+    /// Creates a new `Subsurface` of a uniform color.
+    ///
+    /// # Arguments
+    ///
+    /// * `r`, `g`, `b` - The scatter color, the color of the object seen from afar.
+    /// * `mean_free_path` - The average distance light travels between scattering events, per channel.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Subsurface`.
+    pub fn new(r: f32, g: f32, b: f32, mean_free_path: Color) -> MaterialType {
+        Self::from_texture(SolidColor::from_rgb(r, g, b), mean_free_path, 0.0)
+    }
+
+    /// Creates a new `Subsurface` whose color varies over the surface, like veined marble.
+    ///
+    /// # Arguments
+    ///
+    /// * `scatter_color` - The color of the object seen from afar, read where the light enters.
+    /// * `mean_free_path` - The average distance light travels between scattering events, per channel.
+    /// * `anisotropy` - The mean cosine of the scattering angle inside, positive scatters forward.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `MaterialType::Subsurface`.
+    pub fn from_texture(scatter_color: TextureType, mean_free_path: Color, anisotropy: f32) -> MaterialType {
+        MaterialType::Subsurface(Subsurface {
+            scatter_color,
+            mean_free_path,
+            phase_function: Box::new(HenyeyGreenstein::new(1.0, 1.0, 1.0, anisotropy)),
+        })
+    }
+
+    /// Follows light from where it enters the surface to where it leaves.
+    ///
+    /// # Arguments
+    ///
+    /// * `hit_record` - Where it entered, seen from outside.
+    /// * `scene` - The scene holding the object, the first surface found from inside is its boundary.
+    ///
+    /// # Returns
+    ///
+    /// Where the light leaves, seen from outside, and the fraction of it that makes it there.
+    /// Nothing when the walk ends inside.
+    pub(crate) fn walk(&self, hit_record: &HitRecord, scene: &Scene) -> Option<(HitRecord, Color)> {
+        if !hit_record.front_face {
+            return None;
+        }
+
+        let color = self.scatter_color.value(hit_record.u, hit_record.v, hit_record.point);
+        let extinction = [self.mean_free_path.r, self.mean_free_path.g, self.mean_free_path.b].map(|mfp| 1.0 / mfp.max(1e-6));
+        let albedo = [color.r, color.g, color.b].map(single_scattering_albedo);
+
+        // Light enters diffusely, cosine distributed about the inward normal
+        let inward = Onb::new(-hit_record.normal).transform(cosine_direction());
+        let mut ray = Ray::new(hit_record.point, inward);
+        let mut weight = [1.0f32; 3];
+
+        for step in 0..Self::MAX_STEPS {
+            // Pick the channel setting the flight distance in proportion to the light it still carries
+            let total: f32 = weight.iter().sum();
+            if total <= 0.0 {
+                return None;
+            }
+            let xi = random_float() * total;
+            let channel = if xi < weight[0] { 0 } else if xi < weight[0] + weight[1] { 1 } else { 2 };
+            let distance = -random_float().max(f32::MIN_POSITIVE).ln() / extinction[channel];
+
+            // Only the first flight starts on the surface, the others start at a collision
+            let t_min = if step == 0 { SHADOW_EPSILON } else { 0.0 };
+            let mut exit = HitRecord::default();
+            let length = ray.direction.len();
+            if scene.hit(&ray, &mut Interval::new(t_min, distance / length), &mut exit) {
+                // A walk that found the boundary from outside slipped through it numerically
+                if exit.front_face {
+                    return None;
+                }
+
+                // Leaving: each channel's chance of flying this far, over the one the distance was picked with
+                let flown = exit.t * length;
+                let survival = extinction.map(|sigma| (-sigma * flown).exp());
+                let pdf: f32 = (0..3).map(|c| weight[c] * survival[c]).sum::<f32>() / total;
+                if pdf <= 0.0 {
+                    return None;
+                }
+
+                exit.normal = -exit.normal;
+                exit.front_face = true;
+                exit.mat = hit_record.mat;
+                return Some((exit, Color::new(
+                    weight[0] * survival[0] / pdf,
+                    weight[1] * survival[1] / pdf,
+                    weight[2] * survival[2] / pdf,
+                )));
+            }
+
+            // Scattering: each channel's collision density, over the one the distance was picked with
+            let collision = extinction.map(|sigma| sigma * (-sigma * distance).exp());
+            let pdf: f32 = (0..3).map(|c| weight[c] * collision[c]).sum::<f32>() / total;
+            if pdf <= 0.0 {
+                return None;
+            }
+            for c in 0..3 {
+                weight[c] *= albedo[c] * collision[c] / pdf;
+            }
+
+            let point = ray.at(distance / length);
+            let mut scattered = Ray::default();
+            let mut phase_weight = Color::default();
+            let collision_record = HitRecord { point, ..Default::default() };
+            if !self.phase_function.scatter(&ray, &mut scattered, &collision_record, &mut phase_weight) {
+                return None;
+            }
+            ray = scattered;
+
+            // Russian roulette, once the walk is deep and dim
+            let brightest = weight[0].max(weight[1]).max(weight[2]);
+            if step > 8 && brightest < 0.25 {
+                let survival = brightest / 0.25;
+                if random_float() >= survival {
+                    return None;
+                }
+                weight = weight.map(|w| w / survival);
+            }
+        }
+
+        None
+    }
+}
+
+impl DiffuseMaterial for Subsurface {
+    fn scatter(&self, _: &Ray, scattered_ray: &mut Ray, hit_record: &HitRecord, attenuation: &mut Color) -> bool {
+        let direction = Onb::new(hit_record.normal).transform(cosine_direction());
+        *scattered_ray = Ray::new(hit_record.point, direction);
+        *attenuation = self.scatter_color.value(hit_record.u, hit_record.v, hit_record.point);
+        true
+    }
+
+    fn eval(&self, _: &Ray, hit_record: &HitRecord, direction: &Vector3) -> Color {
+        let cosine = hit_record.normal.dot(&direction.unit_vector()).max(0.0);
+        (cosine / PI) * self.scatter_color.value(hit_record.u, hit_record.v, hit_record.point)
+    }
+
+    fn pdf(&self, _: &Ray, hit_record: &HitRecord, direction: &Vector3) -> f32 {
+        hit_record.normal.dot(&direction.unit_vector()).max(0.0) / PI
+    }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    fn clone_box(&self) -> MaterialType {
+        MaterialType::Subsurface(self.clone())
+    }
+}
+
+/// A cosine distributed direction about `+z`.
+fn cosine_direction() -> Vector3 {
+    let (r1, r2) = (random_float(), random_float());
+    let phi = 2.0 * PI * r1;
+    Vector3::new(phi.cos() * r2.sqrt(), phi.sin() * r2.sqrt(), (1.0 - r2).sqrt())
+}
+
+/// Inverts van de Hulst's fit of the multiple scattering albedo of a semi-infinite medium,
+/// so the object ends up the scatter color once light has bounced around inside.
+fn single_scattering_albedo(scatter_color: f32) -> f32 {
+    let a = scatter_color.clamp(0.0, 0.999);
+    let s = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+    1.0 - s * s
+}
+
+#[cfg(test)]
+mod test_subsurface {
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
+    use crate::engine::lighting::diffuse_lighting_model::subsurface::Subsurface;
+    use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::objects::sphere::Sphere;
+    use crate::engine::scene::Scene;
+    use crate::util::color::Color;
+
+    #[test]
+    fn walks_leave_through_the_boundary(){
+        let mut materials = MaterialArena::new();
+        let wax = materials.add(Subsurface::new(1.0, 0.6, 0.2, Color::new(0.2, 0.1, 0.05)));
+        let scene = Scene::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, wax), materials);
+        let MaterialType::Subsurface(material) = scene.material(wax) else {
+            panic!("not a subsurface material");
+        };
+
+        let entry = HitRecord { point: Point3::new(0.0, 1.0, 0.0), normal: Vector3::new(0.0, 1.0, 0.0), front_face: true, mat: wax, ..Default::default() };
+
+        let samples = 5000;
+        let mut reflected = Color::default();
+        for _ in 0..samples {
+            if let Some((exit, weight)) = material.walk(&entry, &scene) {
+                // Exits lie on the sphere, facing out
+                let radial = Vector3::new(exit.point[0], exit.point[1], exit.point[2]);
+                assert!((radial.len() - 1.0).abs() < 1e-3);
+                assert!(exit.normal.dot(&radial) > 0.99 && exit.front_face);
+                reflected = reflected + (1.0 / samples as f32) * weight;
+            }
+        }
+
+        // With short free paths the object looks like its scatter color, and white light isn't lost
+        assert!((reflected.r - 1.0).abs() < 0.05, "red reflectance {}", reflected.r);
+        assert!((reflected.g - 0.6).abs() < 0.1, "green reflectance {}", reflected.g);
+        assert!((reflected.b - 0.2).abs() < 0.1, "blue reflectance {}", reflected.b);
+    }
+}
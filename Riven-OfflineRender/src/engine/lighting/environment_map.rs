@@ -0,0 +1,191 @@
+use std::path::Path;
+use std::sync::Arc;
+use image::{ImageError, ImageReader, Rgb32FImage};
+use crate::engine::base::constants::constants::{random_float, PI};
+use crate::engine::base::distribution::Distribution2D;
+use crate::engine::base::transform::Transform;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::background::Background;
+use crate::util::color::Color;
+
+/// Distant lighting from an equirectangular HDR image wrapped around the scene.
+///
+/// The top row of the image is straight up, and the image's center looks down `+x` before any rotation.
+/// Directions are importance sampled by the pixels' luminance, so small bright suns converge quickly.
+#[derive(Clone)]
+pub struct EnvironmentMap {
+    image: Arc<Rgb32FImage>,
+    distribution: Arc<Distribution2D>,
+    /// Rotation about the vertical axis, from the map's frame to the world.
+    to_world: Transform,
+    intensity: f32,
+}
+
+impl EnvironmentMap {
+    pub fn new(filename: &str, rotation: f32, intensity: f32) -> Background {
+        Self::try_new(filename, rotation, intensity).expect("Failed to load the environment map")
+    }
+
+    /// Loads an environment map from a Radiance `.hdr`, OpenEXR or any other image file.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The path of the equirectangular image to load.
+    /// * `rotation` - Rotation of the map about the vertical axis, in degrees.
+    /// * `intensity` - Scale applied to the map's radiance.
+    ///
+    /// # Returns
+    ///
+    /// The background, or the `ImageError` raised while opening or decoding the file.
+    pub fn try_new<P: AsRef<Path>>(filename: P, rotation: f32, intensity: f32) -> Result<Background, ImageError> {
+        // Any pixel format is converted to linear 32-bit float RGB
+        let image = ImageReader::open(filename.as_ref())?
+            .with_guessed_format()?
+            .decode()?
+            .into_rgb32f();
+
+        Ok(Self::from_image(image, rotation, intensity))
+    }
+
+    /// Creates an environment map from an image already in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The equirectangular image holding linear radiance.
+    /// * `rotation` - Rotation of the map about the vertical axis, in degrees.
+    /// * `intensity` - Scale applied to the map's radiance.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Background::Environment`.
+    pub fn from_image(image: Rgb32FImage, rotation: f32, intensity: f32) -> Background {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        assert!(width > 0 && height > 0, "The environment map image is empty");
+
+        // Rows near the poles cover less solid angle, sin(theta) keeps them from being oversampled
+        let mut func = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sin_theta = (PI * (y as f32 + 0.5) / height as f32).sin();
+            for x in 0..width {
+                let pixel = image.get_pixel(x as u32, y as u32);
+                func.push(Color::new(pixel[0], pixel[1], pixel[2]).luminance() * sin_theta);
+            }
+        }
+
+        Background::Environment(Self {
+            distribution: Arc::new(Distribution2D::new(&func, width, height)),
+            image: Arc::new(image),
+            to_world: Transform::rotate_y(rotation),
+            intensity,
+        })
+    }
+
+    /// Maps a world direction to the map's `(u, v)` coordinates, both in `[0, 1]`.
+    fn direction_to_uv(&self, direction: Vector3) -> (f32, f32) {
+        let d = self.to_world.inverse().vector(direction).unit_vector();
+        let phi = d.z.atan2(d.x);
+        let theta = d.y.clamp(-1.0, 1.0).acos();
+        ((phi + PI) / (2.0 * PI), theta / PI)
+    }
+
+    /// Maps the map's `(u, v)` coordinates to a unit world direction.
+    fn uv_to_direction(&self, u: f32, v: f32) -> Vector3 {
+        self.to_world.vector(equirectangular_direction(u, v))
+    }
+
+    /// Computes the radiance arriving from a direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - The direction the light comes from, it doesn't need to be normalized.
+    ///
+    /// # Returns
+    ///
+    /// A `Color` holding the scaled radiance of the pixel seen in that direction.
+    pub fn value(&self, direction: Vector3) -> Color {
+        let (u, v) = self.direction_to_uv(direction);
+        let x = ((u * self.image.width() as f32) as u32).min(self.image.width() - 1);
+        let y = ((v * self.image.height() as f32) as u32).min(self.image.height() - 1);
+
+        let pixel = self.image.get_pixel(x, y);
+        self.intensity * Color::new(pixel[0], pixel[1], pixel[2])
+    }
+
+    /// Picks a direction with a probability proportional to the radiance arriving from it.
+    pub fn random(&self) -> Vector3 {
+        let ((u, v), _) = self.distribution.sample_continuous(random_float(), random_float());
+        self.uv_to_direction(u, v)
+    }
+
+    /// The density, per unit solid angle, with which `random` picks `direction`.
+    pub fn pdf_value(&self, direction: Vector3) -> f32 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        // The map spans 2 pi by pi radians, and each of its rows shrinks by sin(theta) on the sphere
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+/// The unit direction seen through `(u, v)` of an unrotated equirectangular image, `v = 0` is straight up.
+pub(crate) fn equirectangular_direction(u: f32, v: f32) -> Vector3 {
+    let phi = u * 2.0 * PI - PI;
+    let theta = v * PI;
+    let sin_theta = theta.sin();
+    Vector3::new(sin_theta * phi.cos(), theta.cos(), sin_theta * phi.sin())
+}
+
+#[cfg(test)]
+mod test_environment_map {
+    use image::{Rgb, Rgb32FImage};
+    use crate::engine::base::constants::constants::PI;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::background::Background;
+    use crate::engine::lighting::environment_map::EnvironmentMap;
+
+    /// A dim map with one bright pixel, a small sun.
+    fn sun_map(rotation: f32) -> EnvironmentMap {
+        let mut image = Rgb32FImage::from_pixel(32, 16, Rgb([0.1, 0.1, 0.1]));
+        image.put_pixel(5, 4, Rgb([500.0, 450.0, 400.0]));
+        match EnvironmentMap::from_image(image, rotation, 2.0) {
+            Background::Environment(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn pdf_integrates_to_one_over_the_sphere(){
+        let map = sun_map(30.0);
+        let (steps_theta, steps_phi) = (400, 800);
+        let mut integral = 0.0;
+        for i in 0..steps_theta {
+            let theta = PI * (i as f32 + 0.5) / steps_theta as f32;
+            for j in 0..steps_phi {
+                let phi = 2.0 * PI * (j as f32 + 0.5) / steps_phi as f32;
+                let direction = Vector3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                integral += map.pdf_value(direction) * theta.sin() * (PI / steps_theta as f32) * (2.0 * PI / steps_phi as f32);
+            }
+        }
+        assert!((integral - 1.0).abs() < 0.02, "pdf integrates to {integral}");
+    }
+
+    #[test]
+    fn samples_head_for_the_sun(){
+        let map = sun_map(75.0);
+        let sun = map.uv_to_direction(5.5 / 32.0, 4.5 / 16.0);
+        assert!(map.value(sun).r > 999.0);
+
+        let toward_sun = (0..1000).filter(|_| map.value(map.random()).r > 999.0).count();
+        assert!(toward_sun > 900, "only {toward_sun} samples found the sun");
+
+        // The background seen by escaping rays is the same map
+        let background = Background::Environment(map.clone());
+        let seen = background.value(&Ray::new(Point3::new(1.0, 2.0, 3.0), 3.0 * sun));
+        assert_eq!(seen.g, map.value(sun).g);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::engine::base::constants::constants::{degrees_to_radians, random_float, INFINITY, PI};
+use crate::engine::base::onb::Onb;
+use crate::engine::base::point::Point3;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::lights::{Light, LightSample, LightType};
+use crate::util::color::Color;
+
+/// A light infinitely far away, like the sun.
+///
+/// With a zero angular diameter every point sees it from exactly the same direction and shadows are sharp,
+/// a wider disk is sampled over its cone of directions and softens them.
+#[derive(Clone)]
+pub struct DirectionalLight {
+    /// Unit direction from the scene towards the light.
+    pub(crate) to_light: Vector3,
+    /// Irradiance on a surface facing the light.
+    pub(crate) irradiance: Color,
+    /// `1 - cos` of the light disk's angular radius, zero for a perfectly parallel light.
+    pub(crate) cone_gap: f32,
+}
+
+impl DirectionalLight {
+    /// Creates a new `DirectionalLight`.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - The direction the light travels in.
+    /// * `irradiance` - The light arriving on a surface facing the light.
+    /// * `angular_diameter` - Apparent size of the light disk in degrees, about 0.53 for the sun.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `LightType::Directional`.
+    pub fn new(direction: Vector3, irradiance: Color, angular_diameter: f32) -> LightType {
+        let half_angle = degrees_to_radians(angular_diameter.clamp(0.0, 180.0)) / 2.0;
+        LightType::Directional(Self {
+            to_light: -direction.unit_vector(),
+            irradiance,
+            cone_gap: 1.0 - half_angle.cos(),
+        })
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: Point3) -> Option<LightSample> {
+        let direction = if self.cone_gap > 0.0 {
+            let phi = 2.0 * PI * random_float();
+            let z = 1.0 - random_float() * self.cone_gap;
+            let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+            Onb::new(self.to_light).transform(Vector3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z))
+        } else {
+            self.to_light
+        };
+
+        // Radiance spread evenly over the disk, divided by the uniform pdf, gives back the irradiance
+        Some(LightSample { direction, distance: INFINITY, radiance: self.irradiance })
+    }
+}
+
+#[cfg(test)]
+mod test_directional_light {
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::lights::directional_light::DirectionalLight;
+    use crate::engine::lighting::lights::Light;
+    use crate::util::color::Color;
+
+    #[test]
+    fn directional_light_samples_stay_inside_its_disk(){
+        let direction = Vector3::new(1.0, -2.0, 0.5);
+        let to_light = -direction.unit_vector();
+
+        let sharp = DirectionalLight::new(direction, Color::new(3.0, 3.0, 3.0), 0.0);
+        let sample = sharp.sample(Point3::new(0.0, 0.0, 0.0)).unwrap();
+        assert!((sample.direction.dot(&to_light) - 1.0).abs() < 1e-6);
+        assert!(sample.distance.is_infinite());
+
+        let soft = DirectionalLight::new(direction, Color::new(3.0, 3.0, 3.0), 10.0);
+        let cos_radius = 5f32.to_radians().cos();
+        let mut spread = false;
+        for _ in 0..1000 {
+            let cos_theta = soft.sample(Point3::new(0.0, 0.0, 0.0)).unwrap().direction.dot(&to_light);
+            assert!(cos_theta >= cos_radius - 1e-5);
+            spread |= cos_theta < 0.9999;
+        }
+        assert!(spread);
+    }
+}
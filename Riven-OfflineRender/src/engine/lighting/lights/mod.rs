@@ -0,0 +1,101 @@
+use crate::engine::base::point::Point3;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::lights::directional_light::DirectionalLight;
+use crate::engine::lighting::lights::point_light::PointLight;
+use crate::engine::lighting::lights::spot_light::SpotLight;
+use crate::util::color::Color;
+
+pub mod point_light;
+pub mod spot_light;
+pub mod directional_light;
+
+/// The light a punctual light sends towards a point, along with where it comes from.
+#[derive(Clone, Copy, Debug)]
+pub struct LightSample {
+    /// Unit direction from the lit point towards the light.
+    pub direction: Vector3,
+    /// Distance to the light, infinite for lights that are infinitely far away.
+    pub distance: f32,
+    /// Incident radiance already divided by the probability of the sampled direction.
+    pub radiance: Color,
+}
+
+/// An analytic light that is infinitely small or infinitely far away.
+///
+/// These lights can't be hit by rays, tracers only see them through shadow rays.
+pub trait Light {
+    /// Samples the light arriving at a point, ignoring anything that might block it.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The point being lit.
+    ///
+    /// # Returns
+    ///
+    /// A `LightSample`, or `None` if the light sends nothing towards `point`.
+    fn sample(&self, point: Point3) -> Option<LightSample>;
+}
+
+/// How a light's intensity fades with distance.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Falloff {
+    /// The physically correct `1 / d²`.
+    #[default]
+    InverseSquare,
+    /// `1 / d²` smoothly windowed down to nothing at `range`, so far away lights can be skipped.
+    Windowed { range: f32 },
+}
+
+impl Falloff {
+    /// Computes the fraction of a light's intensity left at some distance.
+    ///
+    /// # Arguments
+    ///
+    /// * `distance` - The distance to the light.
+    ///
+    /// # Returns
+    ///
+    /// The attenuation factor.
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        let inverse_square = 1.0 / (distance * distance);
+        match self {
+            Falloff::InverseSquare => inverse_square,
+            Falloff::Windowed { range } => {
+                let window = (1.0 - (distance / range).powi(4)).clamp(0.0, 1.0);
+                window * window * inverse_square
+            }
+        }
+    }
+}
+
+/// The punctual lights a scene can hold.
+#[derive(Clone)]
+pub enum LightType {
+    Point(PointLight),
+    Spot(SpotLight),
+    Directional(DirectionalLight),
+}
+
+impl Light for LightType {
+    fn sample(&self, point: Point3) -> Option<LightSample> {
+        match self {
+            LightType::Point(light) => light.sample(point),
+            LightType::Spot(light) => light.sample(point),
+            LightType::Directional(light) => light.sample(point),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_falloff {
+    use crate::engine::lighting::lights::Falloff;
+
+    #[test]
+    fn windowed_falloff_reaches_zero_at_range(){
+        let falloff = Falloff::Windowed { range: 10.0 };
+        assert!((falloff.attenuation(0.1) - Falloff::InverseSquare.attenuation(0.1)).abs() < 1e-3);
+        assert!(falloff.attenuation(5.0) < Falloff::InverseSquare.attenuation(5.0));
+        assert_eq!(falloff.attenuation(10.0), 0.0);
+        assert_eq!(falloff.attenuation(20.0), 0.0);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::engine::base::point::Point3;
+use crate::engine::lighting::lights::{Falloff, Light, LightSample, LightType};
+use crate::util::color::Color;
+
+/// A light shining equally in every direction from a single point.
+#[derive(Clone)]
+pub struct PointLight {
+    pub(crate) position: Point3,
+    /// Radiant intensity, the power sent into a unit of solid angle.
+    pub(crate) intensity: Color,
+    pub(crate) falloff: Falloff,
+}
+
+impl PointLight {
+    /// Creates a new `PointLight`.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Where the light sits.
+    /// * `intensity` - The light's radiant intensity.
+    /// * `falloff` - How the intensity fades with distance.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `LightType::Point`.
+    pub fn new(position: Point3, intensity: Color, falloff: Falloff) -> LightType {
+        LightType::Point(Self { position, intensity, falloff })
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: Point3) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance = to_light.len();
+        let attenuation = self.falloff.attenuation(distance);
+        if distance <= 0.0 || attenuation <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: attenuation * self.intensity,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_point_light {
+    use crate::engine::base::point::Point3;
+    use crate::engine::lighting::lights::point_light::PointLight;
+    use crate::engine::lighting::lights::{Falloff, Light};
+    use crate::util::color::Color;
+
+    #[test]
+    fn point_light_follows_inverse_square_law(){
+        let light = PointLight::new(Point3::new(0.0, 4.0, 0.0), Color::new(8.0, 8.0, 8.0), Falloff::InverseSquare);
+
+        let near = light.sample(Point3::new(0.0, 2.0, 0.0)).unwrap();
+        let far = light.sample(Point3::new(0.0, 0.0, 0.0)).unwrap();
+
+        assert_eq!(near.distance, 2.0);
+        assert!((near.direction.y - 1.0).abs() < 1e-6);
+        assert!((near.radiance.r - 2.0).abs() < 1e-6);
+        assert!((far.radiance.r - 0.5).abs() < 1e-6);
+    }
+}
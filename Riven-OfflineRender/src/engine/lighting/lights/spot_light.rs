@@ -0,0 +1,101 @@
+use crate::engine::base::constants::constants::degrees_to_radians;
+use crate::engine::base::point::Point3;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::lights::{Falloff, Light, LightSample, LightType};
+use crate::util::color::Color;
+
+/// A point light limited to a cone, fully bright inside the inner angle and fading out to the outer one.
+#[derive(Clone)]
+pub struct SpotLight {
+    pub(crate) position: Point3,
+    /// Unit direction the cone points along.
+    pub(crate) direction: Vector3,
+    /// Radiant intensity along the cone's axis.
+    pub(crate) intensity: Color,
+    pub(crate) cos_inner: f32,
+    pub(crate) cos_outer: f32,
+    pub(crate) falloff: Falloff,
+}
+
+impl SpotLight {
+    /// Creates a new `SpotLight`.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Where the light sits.
+    /// * `look_at` - A point on the cone's axis.
+    /// * `intensity` - The light's radiant intensity inside the inner cone.
+    /// * `inner_angle` - Half angle in degrees where the light starts fading.
+    /// * `outer_angle` - Half angle in degrees past which there's no light at all.
+    /// * `falloff` - How the intensity fades with distance.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `LightType::Spot`.
+    pub fn new(position: Point3, look_at: Point3, intensity: Color, inner_angle: f32, outer_angle: f32, falloff: Falloff) -> LightType {
+        let outer_angle = outer_angle.max(inner_angle);
+        LightType::Spot(Self {
+            position,
+            direction: (look_at - position).unit_vector(),
+            intensity,
+            cos_inner: degrees_to_radians(inner_angle).cos(),
+            cos_outer: degrees_to_radians(outer_angle).cos(),
+            falloff,
+        })
+    }
+
+    /// The fraction of the axis intensity sent out at some angle from the axis.
+    fn cone_falloff(&self, cos_theta: f32) -> f32 {
+        if cos_theta >= self.cos_inner {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_outer {
+            return 0.0;
+        }
+
+        let t = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: Point3) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance = to_light.len();
+        if distance <= 0.0 {
+            return None;
+        }
+
+        let direction = to_light / distance;
+        let attenuation = self.cone_falloff(-direction.dot(&self.direction)) * self.falloff.attenuation(distance);
+        if attenuation <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample { direction, distance, radiance: attenuation * self.intensity })
+    }
+}
+
+#[cfg(test)]
+mod test_spot_light {
+    use crate::engine::base::point::Point3;
+    use crate::engine::lighting::lights::spot_light::SpotLight;
+    use crate::engine::lighting::lights::{Falloff, Light};
+    use crate::util::color::Color;
+
+    #[test]
+    fn spot_light_fades_smoothly_between_its_cones(){
+        let light = SpotLight::new(Point3::new(0.0, 1.0, 0.0), Point3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), 30.0, 45.0, Falloff::InverseSquare);
+        let at_angle = |degrees: f32| {
+            let radians = degrees.to_radians();
+            light.sample(Point3::new(radians.tan(), 0.0, 0.0)).map_or(0.0, |s| s.radiance.r * s.distance * s.distance)
+        };
+
+        assert!((at_angle(0.0) - 1.0).abs() < 1e-5);
+        assert!((at_angle(29.0) - 1.0).abs() < 1e-5);
+        assert_eq!(at_angle(46.0), 0.0);
+
+        let (a, b, c) = (at_angle(32.0), at_angle(37.5), at_angle(43.0));
+        assert!(1.0 > a && a > b && b > c && c > 0.0);
+    }
+}
@@ -1,2 +1,6 @@
 
-pub mod diffuse_lighting_model;
\ No newline at end of file
+pub mod diffuse_lighting_model;
+pub mod background;
+pub mod lights;
+pub mod environment_map;
+pub mod sky;
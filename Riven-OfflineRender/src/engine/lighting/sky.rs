@@ -0,0 +1,242 @@
+use image::{Rgb, Rgb32FImage};
+use crate::engine::base::constants::constants::{degrees_to_radians, PI};
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::background::Background;
+use crate::engine::lighting::environment_map::{equirectangular_direction, EnvironmentMap};
+use crate::engine::lighting::lights::directional_light::DirectionalLight;
+use crate::engine::lighting::lights::LightType;
+use crate::util::color::Color;
+
+/// Width of the image the sky is baked into, the height is half of it.
+const SKY_MAP_WIDTH: u32 = 512;
+
+/// Illuminance of the sun above the atmosphere, in kilolux to match the sky's kcd/m².
+const SOLAR_ILLUMINANCE: f32 = 128.0;
+
+/// Apparent diameter of the sun in degrees.
+const SUN_ANGULAR_DIAMETER: f32 = 0.53;
+
+/// Coefficients `A` to `E` of the Perez sky luminance distribution.
+#[derive(Clone, Copy, Debug)]
+struct Perez([f32; 5]);
+
+impl Perez {
+    /// Relative brightness of a sky point `theta` from the zenith and `gamma` from the sun.
+    fn value(&self, theta: f32, gamma: f32) -> f32 {
+        let [a, b, c, d, e] = self.0;
+        let cos_gamma = gamma.cos();
+        // Points at the horizon would divide by zero
+        (1.0 + a * (b / theta.cos().max(0.01)).exp()) * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+    }
+}
+
+/// The Preetham et al. 1999 analytic model of a clear daylight sky.
+///
+/// The sky is baked into an importance sampled `EnvironmentMap` to light scenes, and the sun
+/// itself is a separate `DirectionalLight` so the two never count its light twice.
+#[derive(Clone, Debug)]
+pub struct PreethamSky {
+    /// Unit direction from the scene towards the sun.
+    pub(crate) sun_direction: Vector3,
+    /// Angle between the zenith and the sun.
+    theta_sun: f32,
+    /// Distributions of the luminance `Y` and the chromaticities `x` and `y`.
+    perez: [Perez; 3],
+    /// Luminance and chromaticities straight up.
+    zenith: [f32; 3],
+    pub(crate) turbidity: f32,
+    /// Radiance of the ground, lit by the sky and the sun.
+    ground: Color,
+}
+
+impl PreethamSky {
+    /// Creates a new `PreethamSky`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sun_elevation` - Angle of the sun above the horizon in degrees, the model needs it above the horizon.
+    /// * `sun_azimuth` - Angle of the sun around the vertical axis in degrees, zero looks down `+x` and 90 down `+z`.
+    /// * `turbidity` - Haziness of the atmosphere, from about 2 for a clear sky to 10 for a hazy one.
+    /// * `ground_albedo` - Reflectance of the ground seen below the horizon.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `PreethamSky`.
+    pub fn new(sun_elevation: f32, sun_azimuth: f32, turbidity: f32, ground_albedo: Color) -> Self {
+        let elevation = degrees_to_radians(sun_elevation.clamp(0.5, 90.0));
+        let azimuth = degrees_to_radians(sun_azimuth);
+        let sun_direction = Vector3::new(elevation.cos() * azimuth.cos(), elevation.sin(), elevation.cos() * azimuth.sin());
+
+        let t = turbidity.clamp(1.7, 10.0);
+        let theta_sun = PI / 2.0 - elevation;
+
+        let perez = [
+            Perez([0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703]),
+            Perez([-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452]),
+            Perez([-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529]),
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let cubic = |c: [f32; 4]| c[0] * theta_sun.powi(3) + c[1] * theta_sun.powi(2) + c[2] * theta_sun + c[3];
+        let zenith_x = t * t * cubic([0.00166, -0.00375, 0.00209, 0.0])
+            + t * cubic([-0.02903, 0.06377, -0.03202, 0.00394])
+            + cubic([0.11693, -0.21196, 0.06052, 0.25886]);
+        let zenith_y = t * t * cubic([0.00275, -0.00610, 0.00317, 0.0])
+            + t * cubic([-0.04214, 0.08970, -0.04153, 0.00516])
+            + cubic([0.15346, -0.26756, 0.06670, 0.26688]);
+
+        let mut sky = Self {
+            sun_direction,
+            theta_sun,
+            perez,
+            zenith: [zenith_luminance.max(0.0), zenith_x, zenith_y],
+            turbidity: t,
+            ground: Color::default(),
+        };
+
+        // The ground is diffuse, so it reflects albedo / pi of the irradiance from the sky and the sun
+        let irradiance = sky.sky_irradiance() + elevation.sin() * sky.sun_irradiance();
+        sky.ground = (1.0 / PI) * (ground_albedo * irradiance);
+        sky
+    }
+
+    /// Computes the radiance of the sky, or of the ground below the horizon.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - The direction to look in, it doesn't need to be normalized.
+    ///
+    /// # Returns
+    ///
+    /// A `Color` holding the radiance in kcd/m², without the sun's disk.
+    pub fn radiance(&self, direction: Vector3) -> Color {
+        let d = direction.unit_vector();
+        if d.y < 0.0 {
+            return self.ground;
+        }
+
+        let theta = d.y.clamp(-1.0, 1.0).acos();
+        let gamma = d.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            self.zenith[i] * self.perez[i].value(theta, gamma) / self.perez[i].value(0.0, self.theta_sun)
+        });
+        if y <= 0.0 {
+            return Color::default();
+        }
+
+        let sky = Color::from_xyz(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        Color::new(sky.r.max(0.0), sky.g.max(0.0), sky.b.max(0.0))
+    }
+
+    /// Irradiance of the sun on a surface facing it, dimmed and reddened by the atmosphere.
+    ///
+    /// Rayleigh and aerosol extinction are evaluated at a representative wavelength per channel.
+    pub fn sun_irradiance(&self) -> Color {
+        let theta_degrees = self.theta_sun.to_degrees();
+        // Relative optical mass, how much more air the sunlight crosses than from the zenith
+        let mass = 1.0 / (self.theta_sun.cos() + 0.15 * (93.885 - theta_degrees).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+
+        let transmittance = |wavelength_um: f32| {
+            let rayleigh = (-0.008735 * wavelength_um.powf(-4.08) * mass).exp();
+            let aerosol = (-beta * wavelength_um.powf(-1.3) * mass).exp();
+            rayleigh * aerosol
+        };
+
+        SOLAR_ILLUMINANCE * Color::new(transmittance(0.680), transmittance(0.550), transmittance(0.440))
+    }
+
+    /// Irradiance the sky alone sends onto an upward facing surface.
+    fn sky_irradiance(&self) -> Color {
+        let (steps_theta, steps_phi) = (32, 64);
+        let (d_theta, d_phi) = (PI / 2.0 / steps_theta as f32, 2.0 * PI / steps_phi as f32);
+
+        let mut irradiance = Color::default();
+        for i in 0..steps_theta {
+            let theta = (i as f32 + 0.5) * d_theta;
+            for j in 0..steps_phi {
+                let phi = (j as f32 + 0.5) * d_phi;
+                let direction = Vector3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                irradiance = irradiance + (theta.cos() * theta.sin() * d_theta * d_phi) * self.radiance(direction);
+            }
+        }
+        irradiance
+    }
+
+    /// Bakes the sky into an importance sampled environment map.
+    ///
+    /// # Arguments
+    ///
+    /// * `intensity` - Scale applied to the sky's radiance, use the same for `sun`.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Background::Environment`.
+    pub fn background(&self, intensity: f32) -> Background {
+        let (width, height) = (SKY_MAP_WIDTH, SKY_MAP_WIDTH / 2);
+        let image = Rgb32FImage::from_fn(width, height, |x, y| {
+            let direction = equirectangular_direction((x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32);
+            let color = self.radiance(direction);
+            Rgb([color.r, color.g, color.b])
+        });
+
+        EnvironmentMap::from_image(image, 0.0, intensity)
+    }
+
+    /// Creates the sun matching this sky.
+    ///
+    /// # Arguments
+    ///
+    /// * `intensity` - Scale applied to the sun's irradiance, use the same as for `background`.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `LightType::Directional` shining from the sun's direction.
+    pub fn sun(&self, intensity: f32) -> LightType {
+        DirectionalLight::new(-self.sun_direction, intensity * self.sun_irradiance(), SUN_ANGULAR_DIAMETER)
+    }
+}
+
+#[cfg(test)]
+mod test_sky {
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::sky::PreethamSky;
+    use crate::util::color::Color;
+
+    #[test]
+    fn sky_is_brightest_around_the_sun(){
+        let sky = PreethamSky::new(30.0, 0.0, 3.0, Color::new(0.3, 0.3, 0.3));
+        let elevation = 30f32.to_radians();
+
+        let near_sun = sky.radiance(Vector3::new(elevation.cos(), elevation.sin(), 0.3));
+        let away_from_sun = sky.radiance(Vector3::new(-elevation.cos(), elevation.sin(), 0.0));
+        let zenith = sky.radiance(Vector3::new(0.0, 1.0, 0.0));
+
+        assert!(near_sun.luminance() > 2.0 * away_from_sun.luminance());
+        assert!((zenith.luminance() - sky.zenith[0]).abs() < 0.02 * sky.zenith[0]);
+        // A clear sky is blue away from the sun
+        assert!(away_from_sun.b > away_from_sun.r);
+    }
+
+    #[test]
+    fn ground_reflects_sky_and_sun(){
+        let dark = PreethamSky::new(45.0, 0.0, 3.0, Color::new(0.1, 0.1, 0.1));
+        let bright = PreethamSky::new(45.0, 0.0, 3.0, Color::new(0.5, 0.5, 0.5));
+        let down = Vector3::new(0.2, -1.0, 0.0);
+
+        let ratio = bright.radiance(down).g / dark.radiance(down).g;
+        assert!((ratio - 5.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn low_sun_is_dimmer_and_redder(){
+        let noon = PreethamSky::new(70.0, 0.0, 3.0, Color::default()).sun_irradiance();
+        let sunset = PreethamSky::new(3.0, 0.0, 3.0, Color::default()).sun_irradiance();
+
+        assert!(sunset.luminance() < noon.luminance());
+        assert!(sunset.r / sunset.b > noon.r / noon.b);
+    }
+}
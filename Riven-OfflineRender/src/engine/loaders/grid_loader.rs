@@ -0,0 +1,94 @@
+use std::path::Path;
+use crate::engine::loaders::LoaderError;
+use crate::engine::volumes::dense_grid::DenseGrid;
+use crate::engine::volumes::GridType;
+
+/// The four bytes every raw grid file starts with.
+pub const RAW_GRID_MAGIC: &[u8; 4] = b"RVOL";
+
+/// Loads a dense voxel grid from Riven's raw binary format.
+///
+/// The file holds the magic `RVOL`, then the resolution as three little-endian `u32`s `nx`, `ny` and `nz`,
+/// then `nx * ny * nz` little-endian `f32` voxel values with x varying fastest, then y, then z.
+///
+/// # Arguments
+///
+/// * `path` - The path of the grid file.
+///
+/// # Returns
+///
+/// The grid, or the first error found.
+pub fn load_raw_grid<P: AsRef<Path>>(path: P) -> Result<GridType, LoaderError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|source| LoaderError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    parse_raw_grid(&bytes, path)
+}
+
+pub(crate) fn parse_raw_grid(bytes: &[u8], path: &Path) -> Result<GridType, LoaderError> {
+    let error = |message: String| LoaderError::Malformed {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    if bytes.len() < 16 || &bytes[..4] != RAW_GRID_MAGIC {
+        return Err(error("not a raw grid, the RVOL header is missing".to_string()));
+    }
+
+    let word = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    let (nx, ny, nz) = (word(4) as usize, word(8) as usize, word(12) as usize);
+    if nx == 0 || ny == 0 || nz == 0 {
+        return Err(error(format!("the resolution {nx} x {ny} x {nz} holds no voxels")));
+    }
+
+    let count = nx.checked_mul(ny).and_then(|n| n.checked_mul(nz));
+    let data = &bytes[16..];
+    if count.and_then(|n| n.checked_mul(4)) != Some(data.len()) {
+        return Err(error(format!("expected {nx} x {ny} x {nz} voxels but found {} bytes of data", data.len())));
+    }
+
+    let values = data
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+
+    Ok(DenseGrid::new(nx, ny, nz, values))
+}
+
+#[cfg(test)]
+mod test_grid_loader {
+    use std::path::Path;
+    use crate::engine::base::point::Point3;
+    use crate::engine::loaders::grid_loader::{parse_raw_grid, RAW_GRID_MAGIC};
+    use crate::engine::loaders::LoaderError;
+    use crate::engine::volumes::VoxelGrid;
+
+    fn raw_grid(resolution: [u32; 3], values: &[f32]) -> Vec<u8> {
+        let mut bytes = RAW_GRID_MAGIC.to_vec();
+        resolution.iter().for_each(|n| bytes.extend_from_slice(&n.to_le_bytes()));
+        values.iter().for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()));
+        bytes
+    }
+
+    #[test]
+    fn raw_grid_is_read_x_fastest(){
+        let bytes = raw_grid([2, 1, 2], &[0.0, 1.0, 2.0, 3.0]);
+        let grid = parse_raw_grid(&bytes, Path::new("test.rvol")).unwrap();
+
+        assert_eq!(grid.max_value(), 3.0);
+        assert!((grid.value(Point3::new(0.75, 0.5, 0.25)) - 1.0).abs() < 1e-6);
+        assert!((grid.value(Point3::new(0.25, 0.5, 0.75)) - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn truncated_grid_is_an_error(){
+        let bytes = raw_grid([2, 2, 2], &[1.0; 7]);
+        let error = parse_raw_grid(&bytes, Path::new("test.rvol")).err().unwrap();
+        assert!(matches!(error, LoaderError::Malformed { .. }));
+
+        assert!(parse_raw_grid(b"not a grid at all", Path::new("test.rvol")).is_err());
+    }
+}
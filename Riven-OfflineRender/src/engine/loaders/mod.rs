@@ -0,0 +1,44 @@
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use image::ImageError;
+
+pub mod obj_loader;
+pub mod mtl_loader;
+pub mod grid_loader;
+
+/// Errors raised while importing a scene file.
+#[derive(Debug)]
+pub enum LoaderError {
+    /// The file couldn't be read.
+    Io { path: PathBuf, source: std::io::Error },
+    /// The file was read but a statement in it is malformed.
+    Parse { path: PathBuf, line: usize, message: String },
+    /// A texture referenced by the file couldn't be loaded.
+    Texture { path: PathBuf, source: ImageError },
+    /// The file parsed fine but holds no geometry.
+    Empty { path: PathBuf },
+    /// A binary file whose header or size doesn't match its format.
+    Malformed { path: PathBuf, message: String },
+}
+
+impl Display for LoaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoaderError::Io { path, source } => write!(f, "couldn't read {}: {}", path.display(), source),
+            LoaderError::Parse { path, line, message } => write!(f, "{}:{}: {}", path.display(), line, message),
+            LoaderError::Texture { path, source } => write!(f, "couldn't load texture {}: {}", path.display(), source),
+            LoaderError::Empty { path } => write!(f, "{} contains no faces", path.display()),
+            LoaderError::Malformed { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for LoaderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoaderError::Io { source, .. } => Some(source),
+            LoaderError::Texture { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+use std::path::Path;
+use crate::engine::lighting::diffuse_lighting_model::dielectric::Dielectric;
+use crate::engine::lighting::diffuse_lighting_model::diffuse_light::DiffuseLight;
+use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+use crate::engine::lighting::diffuse_lighting_model::metal::Metal;
+use crate::engine::lighting::diffuse_lighting_model::principled::{Principled, PrincipledParameters};
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::loaders::LoaderError;
+use crate::engine::textures::image_texture::ImageTexture;
+use crate::engine::textures::solid_color::SolidColor;
+use crate::engine::textures::TextureType;
+use crate::util::color::Color;
+
+/// The subset of a Wavefront material Riven knows how to render.
+#[derive(Clone)]
+pub struct MtlMaterial {
+    pub name: String,
+    /// `Kd`, the diffuse color.
+    pub diffuse: Color,
+    /// `Ks`, the specular color.
+    pub specular: Color,
+    /// `Ke`, the emitted radiance.
+    pub emission: Color,
+    /// `Ns`, the specular exponent.
+    pub shininess: Option<f32>,
+    /// `Ni`, the index of refraction.
+    pub ior: Option<f32>,
+    /// `d` (or `1 - Tr`), the opacity, a cutout or the transparency of refracting materials.
+    pub dissolve: f32,
+    /// `illum`, the illumination model.
+    pub illum: Option<u32>,
+    /// `map_Kd`, already loaded.
+    pub diffuse_texture: Option<TextureType>,
+    /// `map_d`, already loaded from the image's alpha, or its luminance when it has none.
+    /// Use `opacity` for the cutout of the material, which also accounts for `d`.
+    pub alpha_texture: Option<TextureType>,
+    /// `Pr`, the physically based roughness extension.
+    pub roughness: Option<f32>,
+    /// `Pm`, the metallic extension.
+    pub metallic: Option<f32>,
+    /// `Ps`, the sheen extension.
+    pub sheen: Option<f32>,
+    /// `Pc`, the clearcoat extension.
+    pub clearcoat: Option<f32>,
+    /// `aniso`, the anisotropy extension.
+    pub anisotropy: Option<f32>,
+}
+
+impl MtlMaterial {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::new(0.0, 0.0, 0.0),
+            emission: Color::new(0.0, 0.0, 0.0),
+            shininess: None,
+            ior: None,
+            dissolve: 1.0,
+            illum: None,
+            diffuse_texture: None,
+            alpha_texture: None,
+            roughness: None,
+            metallic: None,
+            sheen: None,
+            clearcoat: None,
+            anisotropy: None,
+        }
+    }
+
+    /// Whether light goes through the material rather than around it: the refracting illumination models,
+    /// or a partial `d` along with an `Ni` and no `map_d` cutout.
+    fn is_refractive(&self) -> bool {
+        matches!(self.illum, Some(4 | 6 | 7 | 9))
+            || (self.dissolve < 1.0 && self.alpha_texture.is_none() && self.ior.is_some_and(|ior| ior > 1.0))
+    }
+
+    /// The cutout of the material, `map_d` multiplied by `d`, for `MaterialArena::add_masked`.
+    ///
+    /// # Returns
+    ///
+    /// The opacity texture, or `None` when the material is fully opaque. The `d` of refracting materials
+    /// is their transparency and isn't part of it.
+    pub fn opacity(&self) -> Option<TextureType> {
+        let dissolve = if self.is_refractive() { 1.0 } else { self.dissolve.clamp(0.0, 1.0) };
+        match (&self.alpha_texture, dissolve < 1.0) {
+            (Some(TextureType::Image(image)), true) => Some(image.scaled(dissolve)),
+            (Some(texture), _) => Some(texture.clone()),
+            (None, true) => Some(SolidColor::from_rgb(dissolve, dissolve, dissolve)),
+            (None, false) => None,
+        }
+    }
+
+    /// Whether the material uses any of the physically based extensions, `Pr`, `Pm`, `Ps`, `Pc` or `aniso`.
+    fn is_physically_based(&self) -> bool {
+        [self.roughness, self.metallic, self.sheen, self.clearcoat, self.anisotropy].iter().any(Option::is_some)
+    }
+
+    fn to_principled(&self) -> MaterialType {
+        let scalar = |value: f32| SolidColor::from_rgb(value, value, value);
+        let defaults = PrincipledParameters::default();
+
+        // The index of refraction becomes the specular level, where 0.5 is 4% reflectance
+        let specular = self.ior.map_or(defaults.specular, |ior| {
+            let f0 = ((ior - 1.0) / (ior + 1.0)).powi(2);
+            scalar(f0 / 0.08)
+        });
+
+        Principled::from_parameters(PrincipledParameters {
+            base_color: self.diffuse_texture.clone()
+                .unwrap_or_else(|| SolidColor::from_rgb(self.diffuse.r, self.diffuse.g, self.diffuse.b)),
+            metallic: self.metallic.map_or(defaults.metallic, scalar),
+            roughness: self.roughness.map_or(defaults.roughness, scalar),
+            specular,
+            sheen: self.sheen.map_or(defaults.sheen, scalar),
+            clearcoat: self.clearcoat.map_or(defaults.clearcoat, scalar),
+            transmission: if self.is_refractive() { scalar(1.0 - self.dissolve) } else { defaults.transmission },
+            anisotropy: self.anisotropy.map_or(defaults.anisotropy, scalar),
+        })
+    }
+
+    /// Maps the material onto the closest Riven material.
+    ///
+    /// Emissive materials become a `DiffuseLight`, and materials using the physically based extensions
+    /// become `Principled`. Otherwise refracting materials become `Dielectric` using `Ni`, materials
+    /// using the reflective illumination model or without a diffuse color become `Metal` tinted by `Ks`,
+    /// and everything else is `Lambertian`, textured by `map_Kd` when present.
+    pub fn to_material(&self) -> MaterialType {
+        if self.emission.r.max(self.emission.g).max(self.emission.b) > 0.0 {
+            return DiffuseLight::new(self.emission.r, self.emission.g, self.emission.b);
+        }
+
+        if self.is_physically_based() {
+            return self.to_principled();
+        }
+
+        if self.is_refractive() {
+            return Dielectric::new(self.ior.unwrap_or(1.5));
+        }
+
+        let max_diffuse = self.diffuse.r.max(self.diffuse.g).max(self.diffuse.b);
+        let max_specular = self.specular.r.max(self.specular.g).max(self.specular.b);
+        let reflective = self.illum == Some(3) || (max_diffuse <= 0.0 && self.diffuse_texture.is_none());
+        if reflective && max_specular > 0.0 {
+            // Phong exponent to an approximate roughness
+            let roughness = self.shininess.map_or(0.0, |ns| (2.0 / (ns.max(0.0) + 2.0)).sqrt());
+            return Metal::new(self.specular.r, self.specular.g, self.specular.b, roughness);
+        }
+
+        match &self.diffuse_texture {
+            Some(texture) => Lambertian::from_texture(texture.clone()),
+            None => Lambertian::new(self.diffuse.r, self.diffuse.g, self.diffuse.b),
+        }
+    }
+}
+
+/// Loads every material of an `.mtl` library, textures are resolved relative to the library.
+///
+/// # Arguments
+///
+/// * `path` - The path of the material library.
+///
+/// # Returns
+///
+/// The materials keyed by name, or the first error found in the file.
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<HashMap<String, MtlMaterial>, LoaderError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|source| LoaderError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    parse_mtl(&source, path)
+}
+
+pub(crate) fn parse_mtl(source: &str, path: &Path) -> Result<HashMap<String, MtlMaterial>, LoaderError> {
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let mut materials = HashMap::new();
+    let mut current: Option<MtlMaterial> = None;
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let error = |message: String| LoaderError::Parse {
+            path: path.to_path_buf(),
+            line: line_number,
+            message,
+        };
+
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next().unwrap_or("");
+        let arguments: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if arguments.is_empty() {
+                return Err(error("`newmtl` needs a material name".to_string()));
+            }
+            if let Some(material) = current.take() {
+                materials.insert(material.name.clone(), material);
+            }
+            current = Some(MtlMaterial::new(&arguments.join(" ")));
+            continue;
+        }
+
+        let Some(material) = current.as_mut() else {
+            return Err(error(format!("`{keyword}` appears before any `newmtl`")));
+        };
+
+        match keyword {
+            "Kd" => material.diffuse = parse_color(&arguments).map_err(error)?,
+            "Ks" => material.specular = parse_color(&arguments).map_err(error)?,
+            "Ke" => material.emission = parse_color(&arguments).map_err(error)?,
+            "Ns" => material.shininess = Some(parse_scalar(keyword, &arguments).map_err(error)?),
+            "Ni" => material.ior = Some(parse_scalar(keyword, &arguments).map_err(error)?),
+            "d" => material.dissolve = parse_scalar(keyword, &arguments).map_err(error)?,
+            "Tr" => material.dissolve = 1.0 - parse_scalar(keyword, &arguments).map_err(error)?,
+            "Pr" => material.roughness = Some(parse_scalar(keyword, &arguments).map_err(error)?),
+            "Pm" => material.metallic = Some(parse_scalar(keyword, &arguments).map_err(error)?),
+            "Ps" => material.sheen = Some(parse_scalar(keyword, &arguments).map_err(error)?),
+            "Pc" => material.clearcoat = Some(parse_scalar(keyword, &arguments).map_err(error)?),
+            "aniso" => material.anisotropy = Some(parse_scalar(keyword, &arguments).map_err(error)?),
+            "illum" => {
+                let illum = arguments.first()
+                    .and_then(|value| value.parse::<u32>().ok())
+                    .ok_or_else(|| error("`illum` needs an integer model".to_string()))?;
+                material.illum = Some(illum);
+            }
+            "map_Kd" | "map_d" => {
+                // Texture options come first, the file name is always last
+                let file = arguments.last().ok_or_else(|| error(format!("`{keyword}` needs a file name")))?;
+                let texture_path = base_dir.join(file);
+                let (texture, slot) = if keyword == "map_Kd" {
+                    (ImageTexture::try_new(&texture_path), &mut material.diffuse_texture)
+                } else {
+                    // Opacity is the image's alpha, or its luminance, never its red channel
+                    (ImageTexture::try_opacity(&texture_path), &mut material.alpha_texture)
+                };
+                *slot = Some(texture.map_err(|source| LoaderError::Texture {
+                    path: texture_path,
+                    source,
+                })?);
+            }
+            // Statements Riven has no use for yet are skipped
+            _ => {}
+        }
+    }
+
+    if let Some(material) = current.take() {
+        materials.insert(material.name.clone(), material);
+    }
+
+    Ok(materials)
+}
+
+fn parse_scalar(keyword: &str, arguments: &[&str]) -> Result<f32, String> {
+    arguments.first()
+        .and_then(|value| value.parse::<f32>().ok())
+        .ok_or_else(|| format!("`{keyword}` needs a number"))
+}
+
+fn parse_color(arguments: &[&str]) -> Result<Color, String> {
+    let values = arguments.iter()
+        .map(|value| value.parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| format!("expected an RGB color, found `{}`", arguments.join(" ")))?;
+
+    match values.as_slice() {
+        [v] => Ok(Color::new(*v, *v, *v)),
+        [r, g, b] => Ok(Color::new(*r, *g, *b)),
+        _ => Err(format!("expected an RGB color, found `{}`", arguments.join(" "))),
+    }
+}
+
+#[cfg(test)]
+mod test_mtl_loader {
+    use std::path::Path;
+    use crate::engine::base::point::Point3;
+    use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+    use crate::engine::loaders::mtl_loader::parse_mtl;
+    use crate::engine::textures::Texture;
+
+    #[test]
+    fn statements_map_to_materials(){
+        let source = "newmtl glass\nNi 1.45\nd 0.2\n\
+                      newmtl chrome\nKd 0 0 0\nKs 0.9 0.9 0.9\nNs 800\n\
+                      newmtl paint\nKd 0.8 0.1 0.1\nKs 0.5 0.5 0.5\n\
+                      newmtl lamp\nKd 0 0 0\nKe 4 4 4\n\
+                      newmtl car_paint\nKd 0.6 0.0 0.1\nPr 0.4\nPm 0.2\nPc 1.0\n";
+        let materials = parse_mtl(source, Path::new("test.mtl")).unwrap();
+
+        assert!(matches!(materials["glass"].to_material(), MaterialType::Dielectric(_)));
+        assert!(matches!(materials["chrome"].to_material(), MaterialType::Metal(_)));
+        assert!(matches!(materials["paint"].to_material(), MaterialType::Lambertian(_)));
+        assert!(matches!(materials["lamp"].to_material(), MaterialType::DiffuseLight(_)));
+        assert!(matches!(materials["car_paint"].to_material(), MaterialType::Principled(_)));
+    }
+
+    #[test]
+    fn cutouts_read_alpha_or_luminance(){
+        let dir = std::env::temp_dir().join(format!("riven_map_d_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // A green leaf on a transparent background, its red channel is zero everywhere
+        let leaf = image::RgbaImage::from_fn(2, 1, |x, _| image::Rgba([0, 200, 0, if x == 0 { 255 } else { 0 }]));
+        leaf.save(dir.join("leaf.png")).unwrap();
+        // A white on black mask without alpha
+        let mask = image::RgbImage::from_fn(2, 1, |x, _| if x == 0 { image::Rgb([255, 255, 255]) } else { image::Rgb([0, 0, 0]) });
+        mask.save(dir.join("mask.png")).unwrap();
+
+        let source = "newmtl leaf\nmap_Kd leaf.png\nmap_d leaf.png\nnewmtl fence\nmap_d mask.png\n";
+        let materials = parse_mtl(source, &dir.join("test.mtl")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        for name in ["leaf", "fence"] {
+            let alpha = materials[name].alpha_texture.as_ref().unwrap();
+            assert_eq!(alpha.value(0.0, 0.0, Point3::default()).r, 1.0, "{name} is solid on the left");
+            assert_eq!(alpha.value(1.0, 0.0, Point3::default()).r, 0.0, "{name} is cut out on the right");
+        }
+        let color = materials["leaf"].diffuse_texture.as_ref().unwrap().value(0.0, 0.0, Point3::default());
+        assert!(color.r == 0.0 && color.g > 0.7);
+    }
+
+    #[test]
+    fn dissolve_cuts_out_rather_than_refracts(){
+        let dir = std::env::temp_dir().join(format!("riven_dissolve_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let leaf = image::RgbaImage::from_fn(2, 1, |x, _| image::Rgba([40, 160, 20, if x == 0 { 255 } else { 0 }]));
+        leaf.save(dir.join("leaf.png")).unwrap();
+
+        // The way exporters write foliage, with the usual Ni on every material
+        let source = "newmtl leaf\nKd 0.2 0.6 0.1\nNi 1.45\nd 0.9\nillum 2\nmap_Kd leaf.png\nmap_d leaf.png\n\
+                      newmtl veil\nKd 0.5 0.5 0.5\nd 0.3\n\
+                      newmtl pbr_veil\nKd 0.5 0.5 0.5\nPr 0.5\nd 0.3\n\
+                      newmtl window\nNi 1.5\nd 0.1\nillum 4\n\
+                      newmtl opaque\nKd 0.5 0.5 0.5\n";
+        let materials = parse_mtl(source, &dir.join("test.mtl")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let at = |name: &str, u: f32| materials[name].opacity().unwrap().value(u, 0.0, Point3::default()).r;
+        assert!(matches!(materials["leaf"].to_material(), MaterialType::Lambertian(_)));
+        assert!((at("leaf", 0.0) - 0.9).abs() < 0.01 && at("leaf", 1.0) == 0.0);
+
+        assert!(matches!(materials["veil"].to_material(), MaterialType::Lambertian(_)));
+        assert!((at("veil", 0.5) - 0.3).abs() < 1e-6);
+        assert!(matches!(materials["pbr_veil"].to_material(), MaterialType::Principled(_)));
+        assert!((at("pbr_veil", 0.5) - 0.3).abs() < 1e-6);
+
+        // Glass lets light through by refracting it, not through holes
+        assert!(matches!(materials["window"].to_material(), MaterialType::Dielectric(_)));
+        assert!(materials["window"].opacity().is_none());
+        assert!(materials["opaque"].opacity().is_none());
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use crate::engine::base::point::Point3;
+use crate::engine::base::vector::Vector3;
+use crate::engine::bounding_model::bvh::Bvh;
+use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
+use crate::engine::loaders::mtl_loader::{load_mtl, MtlMaterial};
+use crate::engine::loaders::LoaderError;
+use crate::engine::objects::object::HitList;
+use crate::engine::objects::triangle::TriangleMesh;
+use crate::engine::objects::Objects;
+
+/// One corner of a face, as indices into the file's `v`, `vt` and `vn` lists.
+#[derive(Clone, Copy)]
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+/// A triangle of the file, polygons are already fanned out.
+struct Face {
+    corners: [FaceVertex; 3],
+    /// The smoothing group, `0` when smoothing is off.
+    smoothing: u32,
+}
+
+/// Decides which normal a mesh vertex gets.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum NormalKey {
+    /// A `vn` from the file.
+    Explicit(usize),
+    /// The averaged normal of a position within a smoothing group.
+    Smooth(usize, u32),
+    /// The face normal of a triangle outside any smoothing group.
+    Flat(usize),
+}
+
+/// The faces sharing a material, they end up in the same `TriangleMesh`.
+struct FaceGroup {
+    material: Option<String>,
+    faces: Vec<usize>,
+}
+
+/// Loads a Wavefront `.obj` file and its material libraries into a `HitList` of triangles.
+///
+/// # Arguments
+///
+/// * `path` - The path of the `.obj` file.
+/// * `materials` - The arena the file's materials are added to.
+///
+/// # Returns
+///
+/// A `HitList` holding every triangle of the file, or the first error found.
+pub fn load_obj<P: AsRef<Path>>(path: P, materials: &mut MaterialArena) -> Result<HitList, LoaderError> {
+    let meshes = load_obj_meshes(path, materials)?;
+
+    let mut list = HitList::new();
+    for mesh in meshes.iter() {
+        for triangle in TriangleMesh::triangles(mesh) {
+            list.add(triangle);
+        }
+    }
+
+    Ok(list)
+}
+
+/// Loads a Wavefront `.obj` file straight into a `Bvh`.
+pub fn load_obj_bvh<P: AsRef<Path>>(path: P, materials: &mut MaterialArena) -> Result<Objects, LoaderError> {
+    let list = load_obj(path.as_ref(), materials)?;
+    if list.objects.is_empty() {
+        return Err(LoaderError::Empty { path: path.as_ref().to_path_buf() });
+    }
+
+    Ok(Bvh::from_world(list))
+}
+
+/// Loads a Wavefront `.obj` file as one `TriangleMesh` per material.
+pub fn load_obj_meshes<P: AsRef<Path>>(path: P, materials: &mut MaterialArena) -> Result<Vec<Arc<TriangleMesh>>, LoaderError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|source| LoaderError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    parse_obj(&source, path, materials)
+}
+
+pub(crate) fn parse_obj(source: &str, path: &Path, arena: &mut MaterialArena) -> Result<Vec<Arc<TriangleMesh>>, LoaderError> {
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut positions: Vec<Point3> = Vec::new();
+    let mut uvs: Vec<(f32, f32)> = Vec::new();
+    let mut normals: Vec<Vector3> = Vec::new();
+    let mut faces: Vec<Face> = Vec::new();
+
+    let mut materials: HashMap<String, MtlMaterial> = HashMap::new();
+    let mut groups: Vec<FaceGroup> = vec![FaceGroup { material: None, faces: Vec::new() }];
+    let mut current_group = 0;
+    let mut smoothing = 0;
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let error = |message: String| LoaderError::Parse {
+            path: path.to_path_buf(),
+            line: line_number,
+            message,
+        };
+
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next().unwrap_or("");
+        let arguments: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats::<3>(keyword, &arguments, 3).map_err(error)?;
+                positions.push(Point3::new(x, y, z));
+            }
+            "vt" => {
+                let [u, v] = parse_floats::<2>(keyword, &arguments, 1).map_err(error)?;
+                uvs.push((u, v));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats::<3>(keyword, &arguments, 3).map_err(error)?;
+                normals.push(Vector3::new(x, y, z));
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(error(format!("a face needs at least 3 vertices, found {}", arguments.len())));
+                }
+
+                let corners = arguments.iter()
+                    .map(|token| parse_face_vertex(token, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<Vec<FaceVertex>, String>>()
+                    .map_err(error)?;
+
+                // Fan triangulation of polygons with more than three vertices
+                for i in 1..corners.len() - 1 {
+                    groups[current_group].faces.push(faces.len());
+                    faces.push(Face {
+                        corners: [corners[0], corners[i], corners[i + 1]],
+                        smoothing,
+                    });
+                }
+            }
+            "s" => {
+                smoothing = match arguments.first() {
+                    Some(&"off") => 0,
+                    Some(value) => value.parse::<u32>()
+                        .map_err(|_| error(format!("invalid smoothing group `{value}`")))?,
+                    None => return Err(error("`s` needs a smoothing group".to_string())),
+                };
+            }
+            "mtllib" => {
+                if arguments.is_empty() {
+                    return Err(error("`mtllib` needs a file name".to_string()));
+                }
+                for file in arguments.iter() {
+                    materials.extend(load_mtl(base_dir.join(file))?);
+                }
+            }
+            "usemtl" => {
+                let name = arguments.join(" ");
+                if !materials.contains_key(&name) {
+                    return Err(error(format!("material `{name}` isn't defined in any mtllib")));
+                }
+
+                current_group = match groups.iter().position(|group| group.material.as_ref() == Some(&name)) {
+                    Some(group) => group,
+                    None => {
+                        groups.push(FaceGroup { material: Some(name), faces: Vec::new() });
+                        groups.len() - 1
+                    }
+                };
+            }
+            // Objects, groups, lines, points and free-form geometry don't change the triangles
+            _ => {}
+        }
+    }
+
+    let face_normals: Vec<Vector3> = faces.iter()
+        .map(|face| {
+            let [p0, p1, p2] = face.corners.map(|corner| positions[corner.position]);
+            (p1 - p0).cross(&(p2 - p0))
+        })
+        .collect();
+
+    // Area weighted normals for every position of every smoothing group
+    let mut smooth_normals: HashMap<(usize, u32), Vector3> = HashMap::new();
+    for (face, face_normal) in faces.iter().zip(face_normals.iter()) {
+        if face.smoothing == 0 {
+            continue;
+        }
+        for corner in face.corners.iter().filter(|corner| corner.normal.is_none()) {
+            let normal = smooth_normals.entry((corner.position, face.smoothing)).or_default();
+            *normal = *normal + *face_normal;
+        }
+    }
+
+    let mut meshes = Vec::new();
+    for group in groups.iter().filter(|group| !group.faces.is_empty()) {
+        let has_uvs = group.faces.iter().any(|&face| faces[face].corners.iter().any(|corner| corner.uv.is_some()));
+
+        let mut mesh_positions = Vec::new();
+        let mut mesh_normals = Vec::new();
+        let mut mesh_uvs = Vec::new();
+        let mut mesh_indices = Vec::new();
+        let mut vertex_ids: HashMap<(usize, Option<usize>, NormalKey), u32> = HashMap::new();
+
+        for &face_index in group.faces.iter() {
+            let face = &faces[face_index];
+            let face_normal = face_normals[face_index];
+
+            // Zero area triangles can never be hit and have no normal
+            if face_normal.near_zero() {
+                continue;
+            }
+
+            let mut triangle = [0u32; 3];
+            for (slot, corner) in face.corners.iter().enumerate() {
+                let normal_key = match corner.normal {
+                    Some(normal) => NormalKey::Explicit(normal),
+                    None if face.smoothing != 0 => NormalKey::Smooth(corner.position, face.smoothing),
+                    None => NormalKey::Flat(face_index),
+                };
+
+                let key = (corner.position, corner.uv, normal_key);
+                triangle[slot] = *vertex_ids.entry(key).or_insert_with(|| {
+                    let normal = match normal_key {
+                        NormalKey::Explicit(normal) => normals[normal],
+                        NormalKey::Smooth(position, group) => smooth_normals[&(position, group)],
+                        NormalKey::Flat(_) => face_normal,
+                    };
+
+                    mesh_positions.push(positions[corner.position]);
+                    mesh_normals.push(if normal.near_zero() { face_normal.unit_vector() } else { normal.unit_vector() });
+                    if has_uvs {
+                        mesh_uvs.push(corner.uv.map_or((0.0, 0.0), |uv| uvs[uv]));
+                    }
+
+                    (mesh_positions.len() - 1) as u32
+                });
+            }
+            mesh_indices.push(triangle);
+        }
+
+        if mesh_indices.is_empty() {
+            continue;
+        }
+
+        // Each group holds a different material, so every material is added to the arena once
+        let mat = match group.material.as_ref().map(|name| &materials[name]) {
+            Some(material) => match material.opacity() {
+                Some(alpha) => arena.add_masked(material.to_material(), alpha),
+                None => arena.add(material.to_material()),
+            },
+            None => arena.add(Lambertian::new(0.8, 0.8, 0.8)),
+        };
+
+        meshes.push(TriangleMesh::new(mesh_positions, mesh_normals, mesh_uvs, mesh_indices, mat));
+    }
+
+    Ok(meshes)
+}
+
+/// Parses between `required` and `N` floats, missing optional values are zero.
+fn parse_floats<const N: usize>(keyword: &str, arguments: &[&str], required: usize) -> Result<[f32; N], String> {
+    if arguments.len() < required {
+        return Err(format!("`{keyword}` needs at least {required} values, found {}", arguments.len()));
+    }
+
+    let mut values = [0f32; N];
+    for (value, argument) in values.iter_mut().zip(arguments.iter()) {
+        *value = argument.parse::<f32>()
+            .map_err(|_| format!("`{keyword}` has an invalid number `{argument}`"))?;
+    }
+
+    Ok(values)
+}
+
+/// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner.
+fn parse_face_vertex(token: &str, position_count: usize, uv_count: usize, normal_count: usize) -> Result<FaceVertex, String> {
+    let mut parts = token.split('/');
+
+    let position = resolve_index(parts.next().unwrap_or(""), position_count, "vertex")?;
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(uv) => Some(resolve_index(uv, uv_count, "texture coordinate")?),
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(normal) => Some(resolve_index(normal, normal_count, "normal")?),
+    };
+
+    if parts.next().is_some() {
+        return Err(format!("invalid face vertex `{token}`"));
+    }
+
+    Ok(FaceVertex { position, uv, normal })
+}
+
+/// Turns a one based, possibly negative (relative to the end), OBJ index into a zero based one.
+fn resolve_index(raw: &str, count: usize, kind: &str) -> Result<usize, String> {
+    let index = raw.parse::<i64>().map_err(|_| format!("invalid {kind} index `{raw}`"))?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else if index < 0 {
+        count as i64 + index
+    } else {
+        return Err(format!("{kind} index 0 is invalid, OBJ indices start at 1"));
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{kind} index {index} is out of range, {count} defined so far"));
+    }
+
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod test_obj_loader {
+    use std::path::Path;
+    use crate::engine::base::constants::constants;
+    use crate::engine::base::interval::Interval;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
+    use crate::engine::loaders::obj_loader::{load_obj, parse_obj};
+    use crate::engine::loaders::LoaderError;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::objects::object::{GeometricObject, HitList};
+
+    fn to_list(source: &str) -> HitList {
+        let mut list = HitList::new();
+        for mesh in parse_obj(source, Path::new("test.obj"), &mut MaterialArena::new()).unwrap().iter() {
+            for triangle in crate::engine::objects::triangle::TriangleMesh::triangles(mesh) {
+                list.add(triangle);
+            }
+        }
+        list
+    }
+
+    #[test]
+    fn polygons_are_triangulated(){
+        let list = to_list("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv -1 1 0\nf 1 2 3 4 5\n");
+        assert_eq!(list.objects.len(), 3);
+    }
+
+    #[test]
+    fn negative_indices_are_relative(){
+        let list = to_list("v 0 0 0\nv 1 0 0\nv 1 1 0\nf -3 -2 -1\n");
+        assert_eq!(list.objects.len(), 1);
+    }
+
+    #[test]
+    fn smoothing_groups_average_missing_normals(){
+        // Two faces folded along the y axis, the shared edge gets the averaged normal
+        let source = "v 0 0 0\nv 0 1 0\nv 1 0 1\nv -1 0 1\ns 1\nf 1 3 2\nf 1 2 4\n";
+        let list = to_list(source);
+
+        let ray = Ray::new(Point3::new(0.0, 0.5, 2.0), Vector3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+        assert!(list.hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut rec));
+        assert!(rec.normal.x.abs() < 1e-4);
+        assert!((rec.normal.z - 1.0).abs() < 1e-4);
+
+        let flat = to_list(&source.replace("s 1", "s off"));
+        let mut rec = HitRecord::default();
+        assert!(flat.hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut rec));
+        assert!(rec.normal.x.abs() > 0.5);
+    }
+
+    #[test]
+    fn invalid_index_reports_line(){
+        let error = parse_obj("v 0 0 0\nv 1 0 0\n\nf 1 2 3\n", Path::new("broken.obj"), &mut MaterialArena::new()).err().unwrap();
+        match error {
+            LoaderError::Parse { line, message, .. } => {
+                assert_eq!(line, 4);
+                assert!(message.contains("out of range"));
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn missing_file_is_an_error(){
+        assert!(matches!(load_obj("does/not/exist.obj", &mut MaterialArena::new()), Err(LoaderError::Io { .. })));
+    }
+}
@@ -6,4 +6,7 @@ pub mod bounding_model;
 pub mod tracers;
 mod sampler;
 pub mod textures;
+pub mod loaders;
+pub mod scene;
+pub mod volumes;
 // pub mod textures;
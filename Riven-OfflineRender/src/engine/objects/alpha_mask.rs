@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use crate::engine::base::constants::constants::random_float;
+use crate::engine::base::point::Point3;
+use crate::engine::textures::{Texture, TextureType};
+
+/// An opacity mask cutting holes in a surface, like the outline of a leaf card or the gaps of a fence.
+///
+/// Alpha is read from the texture's red channel. Rays pass through points at zero and stop at points
+/// at one, in between they stop with a probability equal to the alpha, so on average the right
+/// fraction of light gets through. Clones share the texture.
+#[derive(Clone)]
+pub struct AlphaMask {
+    alpha: Arc<TextureType>,
+}
+
+impl AlphaMask {
+    pub fn new(alpha: TextureType) -> Self {
+        Self { alpha: Arc::new(alpha) }
+    }
+
+    /// Decides whether a ray stops at a surface point or goes through it.
+    ///
+    /// # Arguments
+    ///
+    /// * `u`, `v` - The texture coordinates of the point.
+    /// * `point` - The point, for solid textures.
+    ///
+    /// # Returns
+    ///
+    /// Whether the point is solid, drawn at random where the surface is partly transparent.
+    pub(crate) fn blocks(&self, u: f32, v: f32, point: Point3) -> bool {
+        let alpha = self.alpha.value(u, v, point).r;
+        alpha >= 1.0 || (alpha > 0.0 && random_float() < alpha)
+    }
+}
+
+#[cfg(test)]
+mod test_alpha_mask {
+    use crate::engine::base::constants::constants::INFINITY;
+    use crate::engine::base::interval::Interval;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::objects::object::HitList;
+    use crate::engine::objects::quad::Quad;
+    use crate::engine::objects::Objects;
+    use crate::engine::objects::sphere::Sphere;
+    use crate::engine::scene::Scene;
+    use crate::engine::textures::solid_color::SolidColor;
+
+    /// A masked quad at `z = 1` in front of an opaque one at `z = 0`, and a masked sphere off to the side.
+    fn scene(alpha: f32) -> Scene {
+        let mut materials = MaterialArena::new();
+        let opaque = materials.add(Lambertian::new(0.5, 0.5, 0.5));
+        let masked = materials.add_masked(Lambertian::new(0.5, 0.5, 0.5), SolidColor::from_rgb(alpha, alpha, alpha));
+
+        let mut world = HitList::new();
+        world.add(Quad::new(Point3::new(-1.0, -1.0, 1.0), Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 2.0, 0.0), masked));
+        world.add(Quad::new(Point3::new(-1.0, -1.0, 0.0), Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 2.0, 0.0), opaque));
+        world.add(Sphere::new(Point3::new(5.0, 0.0, 0.0), 1.0, masked));
+        Scene::new(Objects::List(world), materials)
+    }
+
+    fn shoot(scene: &Scene, origin: Point3) -> Option<HitRecord> {
+        let mut rec = HitRecord::default();
+        scene.hit(&Ray::new(origin, Vector3::new(0.0, 0.0, -1.0)), &mut Interval::new(0.0001, INFINITY), &mut rec).then_some(rec)
+    }
+
+    #[test]
+    fn holes_let_rays_and_shadows_through(){
+        let cut_out = scene(0.0);
+        let rec = shoot(&cut_out, Point3::new(0.0, 0.0, 2.0)).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-4, "hit the cut out quad at t = {}", rec.t);
+        assert!(shoot(&cut_out, Point3::new(5.0, 0.0, 2.0)).is_none());
+
+        let shadow_ray = Ray::new(Point3::new(0.0, 0.0, 2.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(!cut_out.occluded(&shadow_ray, 1.5));
+        assert!(cut_out.occluded(&shadow_ray, 2.5));
+
+        let solid = scene(1.0);
+        assert!((shoot(&solid, Point3::new(0.0, 0.0, 2.0)).unwrap().t - 1.0).abs() < 1e-4);
+        assert!((shoot(&solid, Point3::new(5.0, 0.0, 2.0)).unwrap().t - 1.0).abs() < 1e-4);
+        assert!(solid.occluded(&shadow_ray, 1.5));
+    }
+
+    #[test]
+    fn partial_alpha_stops_that_fraction_of_rays(){
+        let veil = scene(0.25);
+        let samples = 20000;
+        let stopped = (0..samples)
+            .filter(|_| shoot(&veil, Point3::new(0.0, 0.0, 2.0)).unwrap().t < 1.5)
+            .count();
+        let fraction = stopped as f32 / samples as f32;
+        assert!((fraction - 0.25).abs() < 0.02, "stopped {fraction} of the rays");
+
+        // Rays cross the sphere twice, and get through both sides 0.75² of the time
+        let through_sphere = (0..samples)
+            .filter(|_| shoot(&veil, Point3::new(5.0, 0.0, 2.0)).is_none())
+            .count();
+        let fraction = through_sphere as f32 / samples as f32;
+        assert!((fraction - 0.5625).abs() < 0.02, "{fraction} of the rays went through the sphere");
+    }
+}
@@ -0,0 +1,144 @@
+use crate::engine::base::constants::constants::{random_float, INFINITY};
+use crate::engine::base::interval::Interval;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::bounding_model::aabb::AABB;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::objects::object::{GeometricObject, SHADOW_EPSILON};
+use crate::engine::objects::Objects;
+use crate::engine::objects::Objects::ConstantMedia;
+
+/// A participating medium of uniform density filling a closed boundary, like fog, smoke or jade.
+///
+/// A ray crossing the medium scatters after an exponentially distributed distance, or passes through
+/// when that distance is past the far side of the boundary. Shadow rays are blocked the same way,
+/// so lights are seen through the medium with the probability of its transmittance.
+#[derive(Clone)]
+pub struct ConstantMedium {
+    /// The closed object holding the medium, it must be convex.
+    boundary: Box<Objects>,
+    /// How many scattering events happen per unit of distance, on average.
+    density: f32,
+    /// The phase function, usually an `Isotropic` material.
+    phase_function: MaterialId,
+}
+
+impl ConstantMedium {
+    /// Creates a new `ConstantMedium`.
+    ///
+    /// # Arguments
+    ///
+    /// * `boundary` - The convex object the medium fills.
+    /// * `density` - How many scattering events happen per unit of distance, on average.
+    /// * `phase_function` - The medium's material, usually an `Isotropic`.
+    ///
+    /// # Returns
+    ///
+    /// The medium wrapped in `Objects`.
+    pub fn new(boundary: Objects, density: f32, phase_function: MaterialId) -> Objects {
+        ConstantMedia(Self {
+            boundary: Box::new(boundary),
+            density,
+            phase_function,
+        })
+    }
+}
+
+impl GeometricObject for ConstantMedium {
+    fn hit(&self, ray: &Ray, ray_t: &mut Interval, rec: &mut HitRecord) -> bool {
+        let Some((t_enter, t_exit)) = boundary_span(&self.boundary, ray, ray_t) else {
+            return false;
+        };
+
+        let ray_length = ray.direction.len();
+        let distance_inside_boundary = (t_exit - t_enter) * ray_length;
+        let hit_distance = -random_float().max(f32::MIN_POSITIVE).ln() / self.density;
+        if hit_distance > distance_inside_boundary {
+            return false;
+        }
+
+        rec.t = t_enter + hit_distance / ray_length;
+        rec.point = ray.at(rec.t);
+        // There's no surface inside a volume, the normal and face are arbitrary
+        rec.normal = Vector3::new(1.0, 0.0, 0.0);
+        rec.front_face = true;
+        rec.mat = self.phase_function;
+        rec.u = 0.0;
+        rec.v = 0.0;
+
+        true
+    }
+
+    /// Beer-Lambert's law, exact for a uniform density.
+    fn transmittance(&self, ray: &Ray, t_max: f32) -> f32 {
+        match boundary_span(&self.boundary, ray, &Interval::new(SHADOW_EPSILON, t_max)) {
+            Some((t_enter, t_exit)) => (-self.density * (t_exit - t_enter) * ray.direction.len()).exp(),
+            None => 1.0,
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.boundary.bounding_box()
+    }
+}
+
+/// Finds the part of a ray inside a convex boundary, even when the ray starts inside it.
+///
+/// # Arguments
+///
+/// * `boundary` - The convex object enclosing a medium.
+/// * `ray` - The ray crossing it.
+/// * `ray_t` - The range of the ray to clip the span to.
+///
+/// # Returns
+///
+/// The ray parameters where the ray enters and leaves the boundary within `ray_t`, if it does.
+pub(crate) fn boundary_span(boundary: &Objects, ray: &Ray, ray_t: &Interval) -> Option<(f32, f32)> {
+    let mut entry = HitRecord::default();
+    if !boundary.hit(ray, &mut Interval::new(-INFINITY, INFINITY), &mut entry) {
+        return None;
+    }
+    let mut exit = HitRecord::default();
+    if !boundary.hit(ray, &mut Interval::new(entry.t + 0.0001, INFINITY), &mut exit) {
+        return None;
+    }
+
+    let t_enter = entry.t.max(ray_t.min).max(0.0);
+    let t_exit = exit.t.min(ray_t.max);
+    (t_enter < t_exit).then_some((t_enter, t_exit))
+}
+
+#[cfg(test)]
+mod test_constant_medium {
+    use crate::engine::base::constants::constants::INFINITY;
+    use crate::engine::base::interval::Interval;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
+    use crate::engine::objects::constant_medium::ConstantMedium;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::objects::quad::Quad;
+
+    #[test]
+    fn transmittance_follows_beer_lambert(){
+        let density = 0.5;
+        let slab = Quad::make_box(Point3::new(0.0, -10.0, -10.0), Point3::new(2.0, 10.0, 10.0), MaterialId::default());
+        let medium = ConstantMedium::new(slab, density, MaterialId::default());
+
+        // Start inside the medium, so both the entry and the exit logic are used
+        for origin_x in [-1.0, 1.0] {
+            let ray = Ray::new(Point3::new(origin_x, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0));
+            let samples = 20_000;
+            let passed = (0..samples)
+                .filter(|_| !medium.hit(&ray, &mut Interval::new(0.0001, INFINITY), &mut HitRecord::default()))
+                .count();
+
+            let inside = 2.0 - origin_x.max(0.0);
+            let expected = (-density * inside).exp();
+            let transmittance = passed as f32 / samples as f32;
+            assert!((transmittance - expected).abs() < 0.015, "transmittance {transmittance}, expected {expected}");
+        }
+    }
+}
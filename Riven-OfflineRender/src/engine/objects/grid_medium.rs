@@ -0,0 +1,214 @@
+use crate::engine::base::constants::constants::random_float;
+use crate::engine::base::interval::Interval;
+use crate::engine::base::point::Point3;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::bounding_model::aabb::AABB;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
+use crate::engine::objects::constant_medium::boundary_span;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::objects::object::{GeometricObject, SHADOW_EPSILON};
+use crate::engine::objects::Objects;
+use crate::engine::objects::Objects::GridMedia;
+use crate::engine::volumes::{GridType, VoxelGrid};
+
+/// A participating medium whose density varies through space, read from a voxel grid, like clouds, smoke or fire.
+///
+/// The grid is stretched over the boundary's bounding box. Collisions are found with delta tracking
+/// and shadow rays are attenuated with ratio tracking, both against the grid's largest density.
+#[derive(Clone)]
+pub struct GridMedium {
+    /// The closed object holding the medium, it must be convex.
+    boundary: Box<Objects>,
+    density: GridType,
+    /// Scales the grid's values into scattering events per unit of distance.
+    density_scale: f32,
+    /// Optional grid, usually temperature, handed to the phase function as the `u` coordinate.
+    emission: Option<GridType>,
+    /// The phase function, usually a `HenyeyGreenstein` material.
+    phase_function: MaterialId,
+    /// World position of the grid's `(0, 0, 0)` corner.
+    grid_origin: Point3,
+    /// World size of the grid along each axis.
+    grid_extent: Vector3,
+    /// Upper bound of the density anywhere in the medium.
+    majorant: f32,
+}
+
+impl GridMedium {
+    /// Creates a new `GridMedium`.
+    ///
+    /// # Arguments
+    ///
+    /// * `boundary` - The convex object the medium fills, the grid spans its bounding box.
+    /// * `density` - The density grid.
+    /// * `density_scale` - Multiplies the grid's values into scattering events per unit of distance.
+    /// * `phase_function` - The medium's material, usually a `HenyeyGreenstein`.
+    ///
+    /// # Returns
+    ///
+    /// The medium wrapped in `Objects`.
+    pub fn new(boundary: Objects, density: GridType, density_scale: f32, phase_function: MaterialId) -> Objects {
+        Self::build(boundary, density, density_scale, None, phase_function)
+    }
+
+    /// Creates a new glowing `GridMedium`, for fire and explosions.
+    ///
+    /// # Arguments
+    ///
+    /// * `boundary` - The convex object the medium fills, the grid spans its bounding box.
+    /// * `density` - The density grid.
+    /// * `density_scale` - Multiplies the grid's values into scattering events per unit of distance.
+    /// * `emission` - A grid, usually temperature, the phase function's emission texture receives as `u`.
+    /// * `phase_function` - The medium's material, a `HenyeyGreenstein` with an emission texture.
+    ///
+    /// # Returns
+    ///
+    /// The medium wrapped in `Objects`.
+    pub fn with_emission(boundary: Objects, density: GridType, density_scale: f32, emission: GridType, phase_function: MaterialId) -> Objects {
+        Self::build(boundary, density, density_scale, Some(emission), phase_function)
+    }
+
+    fn build(boundary: Objects, density: GridType, density_scale: f32, emission: Option<GridType>, phase_function: MaterialId) -> Objects {
+        let bbox = boundary.bounding_box();
+        let (x, y, z) = (bbox.get_axis_interval(0), bbox.get_axis_interval(1), bbox.get_axis_interval(2));
+
+        GridMedia(Self {
+            boundary: Box::new(boundary),
+            majorant: density_scale * density.max_value(),
+            density,
+            density_scale,
+            emission,
+            phase_function,
+            grid_origin: Point3::new(x.min, y.min, z.min),
+            grid_extent: Vector3::new(x.size(), y.size(), z.size()),
+        })
+    }
+
+    /// Maps a world point into the grid's `[0, 1]` cube.
+    fn grid_point(&self, point: Point3) -> Point3 {
+        let offset = point - self.grid_origin;
+        Point3::new(
+            offset.x / self.grid_extent.x,
+            offset.y / self.grid_extent.y,
+            offset.z / self.grid_extent.z,
+        )
+    }
+
+    fn density_at(&self, point: Point3) -> f32 {
+        self.density_scale * self.density.value(self.grid_point(point))
+    }
+
+    /// Takes an exponentially distributed step against the majorant.
+    fn free_flight(&self, ray_length: f32) -> f32 {
+        -(1.0 - random_float()).max(f32::MIN_POSITIVE).ln() / (self.majorant * ray_length)
+    }
+}
+
+impl GeometricObject for GridMedium {
+    /// Delta tracking: tentative collisions against the majorant are real with probability `density / majorant`.
+    fn hit(&self, ray: &Ray, ray_t: &mut Interval, rec: &mut HitRecord) -> bool {
+        if self.majorant <= 0.0 {
+            return false;
+        }
+        let Some((t_enter, t_exit)) = boundary_span(&self.boundary, ray, ray_t) else {
+            return false;
+        };
+
+        let ray_length = ray.direction.len();
+        let mut t = t_enter;
+        loop {
+            t += self.free_flight(ray_length);
+            if t >= t_exit {
+                return false;
+            }
+
+            let point = ray.at(t);
+            if random_float() * self.majorant < self.density_at(point) {
+                rec.t = t;
+                rec.point = point;
+                // There's no surface inside a volume, the normal and face are arbitrary
+                rec.normal = Vector3::new(1.0, 0.0, 0.0);
+                rec.front_face = true;
+                rec.mat = self.phase_function;
+                rec.u = self.emission.as_ref().map_or(0.0, |grid| grid.value(self.grid_point(point)));
+                rec.v = 0.0;
+                return true;
+            }
+        }
+    }
+
+    /// Ratio tracking: every tentative collision scales the transmittance by the chance it was fictitious.
+    fn transmittance(&self, ray: &Ray, t_max: f32) -> f32 {
+        if self.majorant <= 0.0 {
+            return 1.0;
+        }
+        let Some((t_enter, t_exit)) = boundary_span(&self.boundary, ray, &Interval::new(SHADOW_EPSILON, t_max)) else {
+            return 1.0;
+        };
+
+        let ray_length = ray.direction.len();
+        let mut transmittance = 1.0;
+        let mut t = t_enter;
+        loop {
+            t += self.free_flight(ray_length);
+            if t >= t_exit {
+                return transmittance;
+            }
+
+            transmittance *= 1.0 - self.density_at(ray.at(t)) / self.majorant;
+
+            // Russian roulette keeps dense media from taking many steps for a tiny contribution
+            if transmittance < 0.1 {
+                if random_float() < 0.5 {
+                    return 0.0;
+                }
+                transmittance *= 2.0;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod test_grid_medium {
+    use crate::engine::base::constants::constants::INFINITY;
+    use crate::engine::base::interval::Interval;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
+    use crate::engine::objects::grid_medium::GridMedium;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::objects::quad::Quad;
+    use crate::engine::volumes::dense_grid::DenseGrid;
+    use crate::engine::volumes::VoxelGrid;
+
+    #[test]
+    fn delta_and_ratio_tracking_match_the_optical_depth(){
+        let (scale, width) = (1.5, 2.0);
+        let grid = DenseGrid::from_fn(16, 1, 1, |p| p.x);
+        let slab = Quad::make_box(Point3::new(0.0, 0.0, 0.0), Point3::new(width, 1.0, 1.0), MaterialId::default());
+        let medium = GridMedium::new(slab, grid.clone(), scale, MaterialId::default());
+
+        // Integrate the interpolated density along the ray for the exact transmittance
+        let steps = 10_000;
+        let optical_depth: f32 = (0..steps)
+            .map(|i| scale * grid.value(Point3::new((i as f32 + 0.5) / steps as f32, 0.5, 0.5)) * width / steps as f32)
+            .sum();
+        let expected = (-optical_depth).exp();
+
+        let ray = Ray::new(Point3::new(-1.0, 0.5, 0.5), Vector3::new(1.0, 0.0, 0.0));
+        let samples = 20_000;
+        let ratio = (0..samples).map(|_| medium.transmittance(&ray, INFINITY)).sum::<f32>() / samples as f32;
+        let delta = (0..samples)
+            .filter(|_| !medium.hit(&ray, &mut Interval::new(0.0001, INFINITY), &mut HitRecord::default()))
+            .count() as f32 / samples as f32;
+
+        assert!((ratio - expected).abs() < 0.015, "ratio tracking gave {ratio}, expected {expected}");
+        assert!((delta - expected).abs() < 0.015, "delta tracking gave {delta}, expected {expected}");
+    }
+}
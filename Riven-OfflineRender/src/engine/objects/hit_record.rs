@@ -1,7 +1,7 @@
 use crate::engine::base::point::Point3;
 use crate::engine::base::ray::Ray;
 use crate::engine::base::vector::Vector3;
-use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
 
 /// A struct representing a record of a hit in ray tracing.
 #[derive(Default)]
@@ -15,10 +15,16 @@ pub struct HitRecord {
     /// A boolean indicating whether the hit was on the front face.
     pub front_face: bool,
 
-    pub mat: MaterialType,
+    /// The material at the hit point, resolved through the scene's `MaterialArena`.
+    pub mat: MaterialId,
 
     pub u : f32, // texture coordinates
     pub v : f32, // texture coordinates
+
+    /// How the point moves as `u` grows, zero on surfaces without texture coordinates.
+    pub dpdu: Vector3,
+    /// How the point moves as `v` grows, zero on surfaces without texture coordinates.
+    pub dpdv: Vector3,
 }
 
 
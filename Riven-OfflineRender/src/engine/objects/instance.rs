@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::engine::base::interval::Interval;
+use crate::engine::base::point::Point3;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::transform::Transform;
+use crate::engine::base::vector::Vector3;
+use crate::engine::bounding_model::aabb::AABB;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::objects::object::GeometricObject;
+use crate::engine::objects::Objects;
+use crate::engine::objects::Objects::Instances;
+
+/// Places shared geometry in the world through a `Transform`, without copying it.
+#[derive(Clone)]
+pub struct Instance {
+    object: Arc<Objects>,
+    /// Object space to world space.
+    transform: Transform,
+    bbox: AABB,
+}
+
+impl Instance {
+    /// Creates an instance of `object`, usually a `Bvh` or a mesh, moved into the world by `transform`.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The geometry, shared with every other instance of it.
+    /// * `transform` - The object to world transform.
+    ///
+    /// # Returns
+    ///
+    /// The instance wrapped in `Objects`.
+    pub fn new(object: Arc<Objects>, transform: Transform) -> Objects {
+        let bbox = transform.bounding_box(&object.bounding_box());
+        Instances(Self {
+            object,
+            transform,
+            bbox,
+        })
+    }
+
+    /// Binds the opacity masks of the instanced geometry's materials, see `Objects::bind_alpha_masks`.
+    ///
+    /// Geometry shared between instances is bound once, and stays shared. It's only copied when it uses
+    /// a masked material and other instances still hold it.
+    pub(crate) fn bind_alpha_masks(&mut self, materials: &MaterialArena, shared: &mut HashMap<usize, Arc<Objects>>) {
+        let key = Arc::as_ptr(&self.object) as usize;
+        if let Some(bound) = shared.get(&key) {
+            self.object = bound.clone();
+            return;
+        }
+
+        if self.object.uses_alpha_masks(materials) {
+            match Arc::get_mut(&mut self.object) {
+                Some(object) => object.bind_alpha_masks(materials, shared),
+                None => {
+                    let mut object = self.object.as_ref().clone();
+                    object.bind_alpha_masks(materials, shared);
+                    self.object = Arc::new(object);
+                }
+            }
+        }
+        shared.insert(key, self.object.clone());
+    }
+
+    /// Whether the instanced geometry uses any material with an opacity mask.
+    pub(crate) fn uses_alpha_masks(&self, materials: &MaterialArena) -> bool {
+        self.object.uses_alpha_masks(materials)
+    }
+}
+
+impl GeometricObject for Instance {
+    fn hit(&self, ray: &Ray, ray_t: &mut Interval, rec: &mut HitRecord) -> bool {
+        // The direction isn't normalized, so t means the same thing in both spaces
+        let world_to_object = self.transform.inverse();
+        let object_ray = Ray::new(world_to_object.point(ray.origin), world_to_object.vector(ray.direction));
+
+        if !self.object.hit(&object_ray, ray_t, rec) {
+            return false;
+        }
+
+        rec.point = self.transform.point(rec.point);
+        rec.normal = self.transform.normal(rec.normal).unit_vector();
+        rec.dpdu = self.transform.vector(rec.dpdu);
+        rec.dpdv = self.transform.vector(rec.dpdv);
+
+        true
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox.to_owned()
+    }
+
+    fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        let world_to_object = self.transform.inverse();
+        let object_ray = Ray::new(world_to_object.point(ray.origin), world_to_object.vector(ray.direction));
+        self.object.occluded(&object_ray, t_max)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_max: f32) -> f32 {
+        let world_to_object = self.transform.inverse();
+        let object_ray = Ray::new(world_to_object.point(ray.origin), world_to_object.vector(ray.direction));
+        self.object.transmittance(&object_ray, t_max)
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vector3) -> f32 {
+        let world_to_object = self.transform.inverse();
+        let object_direction = world_to_object.vector(direction.unit_vector());
+        let stretch = object_direction.len();
+
+        // Mapping unit directions through a linear map L scales solid angle by |det L| / |L w|^3
+        self.object.pdf_value(world_to_object.point(origin), object_direction)
+            * world_to_object.determinant().abs() / (stretch * stretch * stretch)
+    }
+
+    fn random(&self, origin: Point3) -> Vector3 {
+        let object_origin = self.transform.inverse().point(origin);
+        self.transform.vector(self.object.random(object_origin))
+    }
+}
+
+#[cfg(test)]
+mod test_instance {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use crate::engine::base::constants::constants;
+    use crate::engine::base::interval::Interval;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::transform::Transform;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::{MaterialArena, MaterialId};
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::objects::instance::Instance;
+    use crate::engine::objects::object::HitList;
+    use crate::engine::objects::Objects;
+    use crate::engine::objects::quad::Quad;
+    use crate::engine::objects::sphere::Sphere;
+    use crate::engine::textures::solid_color::SolidColor;
+
+    #[test]
+    fn instance_moves_and_stretches_shared_geometry(){
+        let sphere = Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, MaterialId::default()));
+        let transform = Transform::translate(Vector3::new(10.0, 0.0, 0.0)) * Transform::scale(1.0, 1.0, 3.0);
+        let instance = Instance::new(Arc::clone(&sphere), transform);
+
+        let ray = Ray::new(Point3::new(10.0, 0.0, -10.0), Vector3::new(0.0, 0.0, 1.0));
+        let mut rec = HitRecord::default();
+        assert!(instance.hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut rec));
+
+        // The stretched sphere starts at z = -3
+        assert!((rec.t - 7.0).abs() < 1e-4);
+        assert!((rec.point.x - 10.0).abs() < 1e-4);
+        assert!((rec.normal.z + 1.0).abs() < 1e-4);
+
+        let bbox = instance.bounding_box();
+        assert!((bbox.get_axis_interval(2).min + 3.0).abs() < 1e-4);
+        assert!((bbox.get_axis_interval(0).max - 11.0).abs() < 1e-4);
+        assert_eq!(Arc::strong_count(&sphere), 2);
+    }
+
+    #[test]
+    fn light_pdf_of_stretched_instance_integrates_to_one(){
+        let disk = Arc::new(Quad::disk(Point3::new(0.0, 0.0, 0.0), 1.0, Vector3::new(0.0, 1.0, 0.0), MaterialId::default()));
+        let transform = Transform::translate(Vector3::new(0.0, 2.0, 0.0))
+            * Transform::rotate_x(30.0)
+            * Transform::scale(2.0, 1.0, 0.5);
+        let instance = Instance::new(disk, transform);
+        let origin = Point3::new(0.3, 0.0, 0.2);
+
+        // Integrate the density over the sphere of directions with uniform samples
+        let samples = 200_000;
+        let total: f32 = (0..samples)
+            .map(|_| instance.pdf_value(origin, Vector3::random_unit_vector()))
+            .sum();
+        let integral = total / samples as f32 * 4.0 * constants::PI;
+
+        assert!((integral - 1.0).abs() < 0.05, "density integrates to {integral}");
+    }
+
+    #[test]
+    fn binding_masks_keeps_instances_shared(){
+        let mut materials = MaterialArena::new();
+        let opaque = materials.add(Lambertian::new(0.5, 0.5, 0.5));
+        let cut_out = materials.add_masked(Lambertian::new(0.5, 0.5, 0.5), SolidColor::from_rgb(0.0, 0.0, 0.0));
+        let place = |object: &Arc<Objects>, x: f32| Instance::new(Arc::clone(object), Transform::translate(Vector3::new(x, 0.0, 0.0)));
+        let geometry = |instance: &Objects| match instance {
+            Objects::Instances(instance) => Arc::as_ptr(&instance.object),
+            _ => panic!("not an instance"),
+        };
+
+        let rock = Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, opaque));
+        let leaf = Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, cut_out));
+        let mut world = HitList::new();
+        for x in [0.0, 5.0] {
+            world.add(place(&rock, x));
+            world.add(place(&leaf, x + 10.0));
+        }
+        let mut world = Objects::List(world);
+        world.bind_alpha_masks(&materials, &mut HashMap::new());
+
+        let Objects::List(list) = &world else { unreachable!() };
+        // Opaque geometry isn't copied, masked geometry is copied once and shared by all its instances
+        assert_eq!(geometry(&list.objects[0]), Arc::as_ptr(&rock));
+        assert_eq!(geometry(&list.objects[2]), Arc::as_ptr(&rock));
+        assert_ne!(geometry(&list.objects[1]), Arc::as_ptr(&leaf));
+        assert_eq!(geometry(&list.objects[1]), geometry(&list.objects[3]));
+
+        let ray = Ray::new(Point3::new(15.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(!world.hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut HitRecord::default()));
+    }
+}
+
@@ -1,34 +1,120 @@
+use std::collections::HashMap;
+use std::sync::Arc;
 use crate::engine::base::interval::Interval;
+use crate::engine::base::point::Point3;
 use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
 use crate::engine::bounding_model::aabb::AABB;
-use crate::engine::bounding_model::bvh::BvhNode;
+use crate::engine::bounding_model::bvh::Bvh;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
+use crate::engine::objects::constant_medium::ConstantMedium;
+use crate::engine::objects::grid_medium::GridMedium;
 use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::objects::instance::Instance;
 use crate::engine::objects::object::{GeometricObject, HitList};
-use crate::engine::objects::Objects::{BVH, List, Planes, Spheres};
+use crate::engine::objects::Objects::{ConstantMedia, GridMedia, BVH, Instances, List, Planes, Quads, Spheres, Triangles};
 use crate::engine::objects::plane::Plane;
+use crate::engine::objects::quad::Quad;
 use crate::engine::objects::sphere::Sphere;
+use crate::engine::objects::triangle::Triangle;
 
 pub mod sphere;
 pub mod object;
 pub mod hit_record;
 pub mod plane;
-mod quad;
+pub mod triangle;
+pub mod quad;
+pub mod instance;
+pub mod constant_medium;
+pub mod grid_medium;
+pub mod alpha_mask;
 
 #[derive(Clone)]
 pub enum Objects{
     Spheres(Sphere),
     Planes(Plane),
+    Triangles(Triangle),
+    Quads(Quad),
+    Instances(Instance),
+    ConstantMedia(ConstantMedium),
+    GridMedia(GridMedium),
     List(HitList),
-    BVH(Box<BvhNode>),
+    BVH(Box<Bvh>),
 }
 
 impl Objects{
+    /// Hands every sphere, quad and triangle the opacity mask of its material, so traversal can skip the holes.
+    ///
+    /// # Arguments
+    ///
+    /// * `materials` - The arena the primitives' `MaterialId`s point into.
+    /// * `shared` - The instanced geometry bound so far, keyed by its address before binding.
+    pub(crate) fn bind_alpha_masks(&mut self, materials: &MaterialArena, shared: &mut HashMap<usize, Arc<Objects>>) {
+        match self {
+            Spheres(s) => s.bind_alpha_mask(materials),
+            Triangles(triangle) => triangle.bind_alpha_mask(materials),
+            Quads(quad) => quad.bind_alpha_mask(materials),
+            Instances(instance) => instance.bind_alpha_masks(materials, shared),
+            List(list) => list.objects.iter_mut().for_each(|object| object.bind_alpha_masks(materials, shared)),
+            BVH(bvh) => bvh.primitives_mut().for_each(|object| object.bind_alpha_masks(materials, shared)),
+            // Planes are never cut out, and media boundaries are volumes rather than surfaces
+            Planes(_) | ConstantMedia(_) | GridMedia(_) => {}
+        }
+    }
+
+    /// Whether any sphere, quad or triangle of the object uses a material with an opacity mask.
+    pub(crate) fn uses_alpha_masks(&self, materials: &MaterialArena) -> bool {
+        let masked = |mat| materials.alpha_mask(mat).is_some();
+        match self {
+            Spheres(s) => masked(s.material()),
+            Triangles(triangle) => masked(triangle.material()),
+            Quads(quad) => masked(quad.material()),
+            Instances(instance) => instance.uses_alpha_masks(materials),
+            List(list) => list.objects.iter().any(|object| object.uses_alpha_masks(materials)),
+            BVH(bvh) => bvh.primitives().any(|object| object.uses_alpha_masks(materials)),
+            Planes(_) | ConstantMedia(_) | GridMedia(_) => false,
+        }
+    }
+
     pub fn hit(&self, ray: &Ray, ray_t: &mut Interval, rec: &mut HitRecord) -> bool {
         match self{
             Planes(plane) => plane.hit(ray, ray_t, rec),
             Spheres(s) => s.hit(ray, ray_t, rec),
+            Triangles(triangle) => triangle.hit(ray, ray_t, rec),
+            Quads(quad) => quad.hit(ray, ray_t, rec),
+            Instances(instance) => instance.hit(ray, ray_t, rec),
+            ConstantMedia(medium) => medium.hit(ray, ray_t, rec),
+            GridMedia(medium) => medium.hit(ray, ray_t, rec),
             List(list) => list.hit(ray, ray_t, rec),
-            BVH(BvhNode) => BvhNode.hit(ray, ray_t, rec),
+            BVH(bvh) => bvh.hit(ray, ray_t, rec),
+        }
+    }
+
+    pub fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        match self {
+            Planes(plane) => plane.occluded(ray, t_max),
+            Spheres(s) => s.occluded(ray, t_max),
+            Triangles(triangle) => triangle.occluded(ray, t_max),
+            Quads(quad) => quad.occluded(ray, t_max),
+            Instances(instance) => instance.occluded(ray, t_max),
+            ConstantMedia(medium) => medium.occluded(ray, t_max),
+            GridMedia(medium) => medium.occluded(ray, t_max),
+            List(list) => list.occluded(ray, t_max),
+            BVH(bvh) => bvh.occluded(ray, t_max),
+        }
+    }
+
+    pub fn transmittance(&self, ray: &Ray, t_max: f32) -> f32 {
+        match self {
+            Planes(plane) => plane.transmittance(ray, t_max),
+            Spheres(s) => s.transmittance(ray, t_max),
+            Triangles(triangle) => triangle.transmittance(ray, t_max),
+            Quads(quad) => quad.transmittance(ray, t_max),
+            Instances(instance) => instance.transmittance(ray, t_max),
+            ConstantMedia(medium) => medium.transmittance(ray, t_max),
+            GridMedia(medium) => medium.transmittance(ray, t_max),
+            List(list) => list.transmittance(ray, t_max),
+            BVH(bvh) => bvh.transmittance(ray, t_max),
         }
     }
 
@@ -36,8 +122,41 @@ impl Objects{
         match self {
             Planes(plane) => plane.bounding_box(),
             Spheres(s) => s.bounding_box(),
+            Triangles(triangle) => triangle.bounding_box(),
+            Quads(quad) => quad.bounding_box(),
+            Instances(instance) => instance.bounding_box(),
+            ConstantMedia(medium) => medium.bounding_box(),
+            GridMedia(medium) => medium.bounding_box(),
             List(list) => list.bounding_box(),
-            BVH(BvhNode) => BvhNode.bounding_box()
+            BVH(bvh) => bvh.bounding_box()
+        }
+    }
+
+    pub fn pdf_value(&self, origin: Point3, direction: Vector3) -> f32 {
+        match self {
+            Planes(plane) => plane.pdf_value(origin, direction),
+            Spheres(s) => s.pdf_value(origin, direction),
+            Triangles(triangle) => triangle.pdf_value(origin, direction),
+            Quads(quad) => quad.pdf_value(origin, direction),
+            Instances(instance) => instance.pdf_value(origin, direction),
+            ConstantMedia(medium) => medium.pdf_value(origin, direction),
+            GridMedia(medium) => medium.pdf_value(origin, direction),
+            List(list) => list.pdf_value(origin, direction),
+            BVH(bvh) => bvh.pdf_value(origin, direction),
+        }
+    }
+
+    pub fn random(&self, origin: Point3) -> Vector3 {
+        match self {
+            Planes(plane) => plane.random(origin),
+            Spheres(s) => s.random(origin),
+            Triangles(triangle) => triangle.random(origin),
+            Quads(quad) => quad.random(origin),
+            Instances(instance) => instance.random(origin),
+            ConstantMedia(medium) => medium.random(origin),
+            GridMedia(medium) => medium.random(origin),
+            List(list) => list.random(origin),
+            BVH(bvh) => bvh.random(origin),
         }
     }
 }
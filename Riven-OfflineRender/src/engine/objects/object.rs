@@ -1,9 +1,15 @@
+use crate::engine::base::constants::constants::ranged_random_int;
 use crate::engine::base::interval::Interval;
+use crate::engine::base::point::Point3;
 use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
 use crate::engine::bounding_model::aabb::AABB;
 use crate::engine::objects::hit_record::HitRecord;
 use crate::engine::objects::Objects;
 
+/// The smallest distance along a shadow ray at which an intersection counts as blocking it.
+pub const SHADOW_EPSILON: f32 = 0.0001;
+
 /// A trait representing an object that can be hit by a ray.
 pub trait GeometricObject{
     /// Determines if a ray hits the object within a given range.
@@ -20,6 +26,52 @@ pub trait GeometricObject{
     fn hit(&self, ray: &Ray, ray_t : &mut Interval, rec: &mut HitRecord) -> bool;
 
     fn bounding_box(&self) -> AABB;
+
+    /// Determines if anything blocks a ray before it travels `t_max`, used for shadow rays.
+    ///
+    /// Unlike `hit` this stops at the first intersection found, in any order, and fills no `HitRecord`.
+    /// Intersections closer than `SHADOW_EPSILON` are ignored so rays leaving a surface don't hit it again.
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - The shadow ray.
+    /// * `t_max` - The ray parameter of the point being tested for visibility.
+    ///
+    /// # Returns
+    ///
+    /// A boolean indicating whether the ray is blocked.
+    fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        let mut rec = HitRecord::default();
+        self.hit(ray, &mut Interval::new(SHADOW_EPSILON, t_max), &mut rec)
+    }
+
+    /// The fraction of light that makes it along a shadow ray to `t_max`.
+    ///
+    /// Surfaces block all of it, participating media override this to let part of it through.
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - The shadow ray.
+    /// * `t_max` - The ray parameter of the point being tested for visibility.
+    ///
+    /// # Returns
+    ///
+    /// The transmittance, between zero and one, possibly a stochastic estimate of it.
+    fn transmittance(&self, ray: &Ray, t_max: f32) -> f32 {
+        if self.occluded(ray, t_max) { 0.0 } else { 1.0 }
+    }
+
+    /// The density, per unit solid angle, with which `random` picks `direction` from `origin`.
+    ///
+    /// Objects that can't be sampled as lights return zero.
+    fn pdf_value(&self, _origin: Point3, _direction: Vector3) -> f32 {
+        0.0
+    }
+
+    /// Picks a direction from `origin` towards a random point on the object, used to sample lights.
+    fn random(&self, _origin: Point3) -> Vector3 {
+        Vector3::new(1.0, 0.0, 0.0)
+    }
 }
 
 
@@ -83,9 +135,11 @@ impl GeometricObject for HitList {
                 rec.normal = temp_rec.normal;
                 rec.t = temp_rec.t;
                 rec.front_face = temp_rec.front_face;
-                rec.mat = temp_rec.mat.clone();
+                rec.mat = temp_rec.mat;
                 rec.u = temp_rec.u;
                 rec.v = temp_rec.v.abs();
+                rec.dpdu = temp_rec.dpdu;
+                rec.dpdv = temp_rec.dpdv;
             }
         }
 
@@ -95,4 +149,34 @@ impl GeometricObject for HitList {
     fn bounding_box(&self) -> AABB {
         self.bbox.clone()
     }
+
+    fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        self.objects.iter().any(|object| object.occluded(ray, t_max))
+    }
+
+    fn transmittance(&self, ray: &Ray, t_max: f32) -> f32 {
+        let mut transmittance = 1.0;
+        for object in self.objects.iter() {
+            transmittance *= object.transmittance(ray, t_max);
+            if transmittance <= 0.0 {
+                return 0.0;
+            }
+        }
+        transmittance
+    }
+
+    /// Every object is picked with the same probability, so the density is their average.
+    fn pdf_value(&self, origin: Point3, direction: Vector3) -> f32 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f32 = self.objects.iter().map(|object| object.pdf_value(origin, direction)).sum();
+        sum / self.objects.len() as f32
+    }
+
+    fn random(&self, origin: Point3) -> Vector3 {
+        let index = ranged_random_int(0, self.objects.len() as i32) as usize;
+        self.objects[index].random(origin)
+    }
 }
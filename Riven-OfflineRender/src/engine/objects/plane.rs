@@ -3,7 +3,7 @@ use crate::engine::base::point::Point3;
 use crate::engine::base::ray::Ray;
 use crate::engine::base::vector::Vector3;
 use crate::engine::bounding_model::aabb::AABB;
-use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
 use crate::engine::objects::hit_record::HitRecord;
 use crate::engine::objects::object::GeometricObject;
 use crate::engine::objects::Objects;
@@ -14,11 +14,11 @@ pub struct Plane{
     point  : Point3, // Point through which the plane passes
     normal : Vector3, // The plane Normal
     kepsilon: f32,
-    mat : MaterialType
+    mat : MaterialId
 }
 
 impl Plane{
-    pub fn new(point : Point3, normal : Vector3, mat : MaterialType) -> Objects {
+    pub fn new(point : Point3, normal : Vector3, mat : MaterialId) -> Objects {
         Planes(Self{
             point,
             normal,
@@ -41,7 +41,10 @@ impl GeometricObject for Plane{
             rec.t = t;
             rec.normal = self.normal;
             rec.point = ray.origin + (t * ray.direction);
-            rec.mat = self.mat.clone();
+            rec.mat = self.mat;
+            // Planes have no texture coordinates, so nothing to bump or orient along
+            rec.dpdu = Vector3::default();
+            rec.dpdv = Vector3::default();
 
             true
         }else {
@@ -52,6 +55,16 @@ impl GeometricObject for Plane{
     fn bounding_box(&self) -> AABB {
         return AABB::default()
     }
+
+    fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        let denominator = ray.direction.dot(&self.normal);
+        if denominator.abs() < 1e-8 {
+            return false;
+        }
+        let t: f32 = (self.point - ray.origin).dot(&self.normal) / denominator;
+
+        t > self.kepsilon && t < t_max
+    }
 }
 
 #[cfg(test)]
@@ -61,13 +74,13 @@ mod test_plane {
     use crate::engine::base::point::Point3;
     use crate::engine::base::ray::Ray;
     use crate::engine::base::vector::Vector3;
-    use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
     use crate::engine::objects::hit_record::HitRecord;
     use crate::engine::objects::plane::Plane;
 
     #[test]
     fn plane_intersect_a_ray(){
-        let mat = Lambertian::new(1.0, 0.0, 0.0);
+        let mat = MaterialId::default();
         let plane = Plane::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0,0.0), mat);
 
         let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
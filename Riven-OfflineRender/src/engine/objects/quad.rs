@@ -1,68 +1,315 @@
+use crate::engine::base::constants::constants::{random_float, INFINITY, PI};
 use crate::engine::base::interval::Interval;
 use crate::engine::base::point::Point3;
 use crate::engine::base::ray::Ray;
 use crate::engine::base::vector::Vector3;
 use crate::engine::bounding_model::aabb::AABB;
-use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::{MaterialArena, MaterialId};
+use crate::engine::objects::alpha_mask::AlphaMask;
 use crate::engine::objects::hit_record::HitRecord;
-use crate::engine::objects::object::GeometricObject;
+use crate::engine::objects::object::{GeometricObject, HitList, SHADOW_EPSILON};
+use crate::engine::objects::Objects;
+use crate::engine::objects::Objects::{List, Quads};
 
-struct Quad{
+/// The outline cut out of the plane spanned by a `Quad`'s `u` and `v` edges.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlanarShape {
+    /// The parallelogram `q + a * u + b * v` with `a` and `b` in `[0, 1]`.
+    Parallelogram,
+    /// The triangle with corners `q`, `q + u` and `q + v`.
+    Triangle,
+    /// The ellipse centered on `q` with semi-axes `u` and `v`, a disk when both have the same length.
+    Ellipse,
+}
+
+impl PlanarShape {
+    /// The area of the shape, as a fraction of the parallelogram spanned by `u` and `v`.
+    fn area_factor(&self) -> f32 {
+        match self {
+            PlanarShape::Parallelogram => 1.0,
+            PlanarShape::Triangle => 0.5,
+            PlanarShape::Ellipse => PI,
+        }
+    }
+
+    /// Maps two uniform random numbers to planar coordinates spread uniformly over the shape.
+    fn sample(&self, r1: f32, r2: f32) -> (f32, f32) {
+        match self {
+            PlanarShape::Parallelogram => (r1, r2),
+            PlanarShape::Triangle => {
+                let s = r1.sqrt();
+                (s * (1.0 - r2), s * r2)
+            }
+            PlanarShape::Ellipse => {
+                let (r, theta) = (r1.sqrt(), 2.0 * PI * r2);
+                (r * theta.cos(), r * theta.sin())
+            }
+        }
+    }
+
+    /// Tests whether the planar coordinates `(a, b)` of a hit fall inside the shape.
+    ///
+    /// # Returns
+    ///
+    /// The texture coordinates of the hit, or `None` when it's outside the shape.
+    fn interior(&self, a: f32, b: f32) -> Option<(f32, f32)> {
+        let unit = Interval::new(0.0, 1.0);
+
+        match self {
+            PlanarShape::Parallelogram => {
+                (unit.contains(a) && unit.contains(b)).then_some((a, b))
+            }
+            PlanarShape::Triangle => {
+                (a >= 0.0 && b >= 0.0 && a + b <= 1.0).then_some((a, b))
+            }
+            PlanarShape::Ellipse => {
+                (a * a + b * b <= 1.0).then_some((0.5 * (a + 1.0), 0.5 * (b + 1.0)))
+            }
+        }
+    }
+}
+
+/// A flat primitive lying in the plane through `q` spanned by `u` and `v`.
+#[derive(Clone)]
+pub struct Quad{
     q : Point3,
     u : Vector3,
     v : Vector3,
+    w : Vector3, // n / (n . n), turns plane offsets into (a, b) coordinates
     d : f32,
     normal : Vector3,
-    mat : MaterialType,
+    shape : PlanarShape,
+    area : f32,
+    mat : MaterialId,
+    alpha : Option<AlphaMask>,
     bbox : AABB
 }
 
 impl Quad{
-    pub fn new(q : Point3, u : Vector3, v : Vector3, mat : MaterialType) -> Quad {
-        let normal = u.cross(&v).unit_vector();
-        Self{
+    /// Creates the parallelogram with corner `q` and edges `u` and `v`.
+    pub fn new(q : Point3, u : Vector3, v : Vector3, mat : MaterialId) -> Objects {
+        Self::with_shape(q, u, v, PlanarShape::Parallelogram, mat)
+    }
+
+    /// Creates the triangle with corners `q`, `q + u` and `q + v`.
+    pub fn triangle(q : Point3, u : Vector3, v : Vector3, mat : MaterialId) -> Objects {
+        Self::with_shape(q, u, v, PlanarShape::Triangle, mat)
+    }
+
+    /// Creates the ellipse centered on `center` with semi-axes `u` and `v`.
+    pub fn ellipse(center : Point3, u : Vector3, v : Vector3, mat : MaterialId) -> Objects {
+        Self::with_shape(center, u, v, PlanarShape::Ellipse, mat)
+    }
+
+    /// Creates the disk centered on `center` facing `normal`.
+    pub fn disk(center : Point3, radius : f32, normal : Vector3, mat : MaterialId) -> Objects {
+        let n = normal.unit_vector();
+        let helper = if n.x.abs() > 0.9 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+        let u = helper.cross(&n).unit_vector() * radius;
+        let v = n.cross(&u);
+
+        Self::with_shape(center, u, v, PlanarShape::Ellipse, mat)
+    }
+
+    pub fn with_shape(q : Point3, u : Vector3, v : Vector3, shape : PlanarShape, mat : MaterialId) -> Objects {
+        let n = u.cross(&v);
+        let normal = n.unit_vector();
+        Quads(Self{
             q,
             u,
             v,
+            w : n / n.dot(&n),
             d : normal.dot(&(q - Point3::default())),
             normal,
+            shape,
+            area : n.len() * shape.area_factor(),
             mat,
-            bbox : Self::set_bounding_box(q , u , v)
-        }
+            alpha : None,
+            bbox : Self::set_bounding_box(q , u , v, shape)
+        })
     }
 
-    fn set_bounding_box(q : Point3, u : Vector3, v : Vector3) -> AABB {
-        let bbox_diagonal11 = AABB::from_points(q, q + u + v);
-        let bbox_diagonal12 = AABB::from_points(q + u, q + v);
+    fn set_bounding_box(q : Point3, u : Vector3, v : Vector3, shape : PlanarShape) -> AABB {
+        let corners = match shape {
+            PlanarShape::Parallelogram => [q, q + u, q + v, q + u + v],
+            PlanarShape::Triangle => [q, q + u, q + v, q],
+            PlanarShape::Ellipse => [q - u - v, q + u - v, q - u + v, q + u + v],
+        };
+
+        let axis = |component : fn(&Point3) -> f32| {
+            let values = corners.map(|corner| component(&corner));
+            Interval::new(
+                values.iter().cloned().fold(f32::INFINITY, f32::min),
+                values.iter().cloned().fold(f32::NEG_INFINITY, f32::max)
+            )
+        };
 
-        AABB::from_aabb(bbox_diagonal11, bbox_diagonal12)
+        AABB::from_intervals(axis(|p| p.x), axis(|p| p.y), axis(|p| p.z))
     }
-}
 
-impl GeometricObject for Quad{
-    fn hit(&self, ray: &Ray, ray_t: &mut Interval, rec: &mut HitRecord) -> bool {
+    pub(crate) fn material(&self) -> MaterialId {
+        self.mat
+    }
+
+    /// Picks up the opacity mask of the shape's material, if it has one.
+    pub(crate) fn bind_alpha_mask(&mut self, materials: &MaterialArena) {
+        self.alpha = materials.alpha_mask(self.mat).cloned();
+    }
+
+    /// Finds where a ray crosses the shape within `ray_t`.
+    ///
+    /// # Returns
+    ///
+    /// The ray parameter of the intersection and its `(u, v)` coordinates on the shape.
+    /// Nothing when the ray misses, or goes through a hole cut by the material's mask.
+    fn intersect(&self, ray: &Ray, ray_t: &Interval) -> Option<(f32, f32, f32)> {
         let denom = self.normal.dot(&ray.direction);
 
+        // Ray is parallel to the plane, no hit
         if denom.abs() < 1e-8{
-            return false;
+            return None;
         }
 
         let t = (self.d - self.normal.dot(&(ray.origin - Point3::default()))) / denom;
-        if !ray_t.contains(t) {
-            return false
+        if !ray_t.surrounds(t) {
+            return None
         }
 
-        let intersection = ray.at(t);
+        // Express the hit in the plane's (u, v) frame to test it against the shape
+        let planar_hit = ray.at(t) - self.q;
+        let alpha = self.w.dot(&planar_hit.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar_hit));
+
+        let (u, v) = self.shape.interior(alpha, beta)?;
+        match &self.alpha {
+            Some(mask) if !mask.blocks(u, v, ray.at(t)) => None,
+            _ => Some((t, u, v)),
+        }
+    }
+
+    /// Creates the six sides of the axis-aligned box with opposite corners `a` and `b`.
+    pub fn make_box(a : Point3, b : Point3, mat : MaterialId) -> Objects {
+        let mut sides = HitList::new();
+
+        let min = Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let max = Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+        let dx = Vector3::new(max.x - min.x, 0.0, 0.0);
+        let dy = Vector3::new(0.0, max.y - min.y, 0.0);
+        let dz = Vector3::new(0.0, 0.0, max.z - min.z);
+
+        sides.add(Quad::new(Point3::new(min.x, min.y, max.z), dx, dy, mat)); // front
+        sides.add(Quad::new(Point3::new(max.x, min.y, max.z), -dz, dy, mat)); // right
+        sides.add(Quad::new(Point3::new(max.x, min.y, min.z), -dx, dy, mat)); // back
+        sides.add(Quad::new(Point3::new(min.x, min.y, min.z), dz, dy, mat)); // left
+        sides.add(Quad::new(Point3::new(min.x, max.y, max.z), dx, -dz, mat)); // top
+        sides.add(Quad::new(Point3::new(min.x, min.y, min.z), dx, dz, mat)); // bottom
+
+        List(sides)
+    }
+}
+
+impl GeometricObject for Quad{
+    fn hit(&self, ray: &Ray, ray_t: &mut Interval, rec: &mut HitRecord) -> bool {
+        let Some((t, u, v)) = self.intersect(ray, ray_t) else {
+            return false
+        };
 
         rec.t = t;
-        rec.point = intersection;
-        rec.mat = self.mat.to_owned();
+        rec.point = ray.at(t);
+        rec.mat = self.mat;
         rec.set_face_normal(ray, self.normal);
+        rec.u = u;
+        rec.v = v;
+        // Ellipses map their [-1, 1] planar coordinates to [0, 1] texture coordinates
+        let stretch = if self.shape == PlanarShape::Ellipse { 2.0 } else { 1.0 };
+        rec.dpdu = self.u * stretch;
+        rec.dpdv = self.v * stretch;
 
         true
     }
 
+    fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        self.intersect(ray, &Interval::new(SHADOW_EPSILON, t_max)).is_some()
+    }
+
     fn bounding_box(&self) -> AABB {
         self.bbox.to_owned()
     }
-}
\ No newline at end of file
+
+    fn pdf_value(&self, origin: Point3, direction: Vector3) -> f32 {
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(origin, direction), &mut Interval::new(0.0001, INFINITY), &mut rec) {
+            return 0.0;
+        }
+
+        // Points are picked uniformly by area, converted to a density over solid angle
+        let distance_squared = rec.t * rec.t * direction.len_squared();
+        let cosine = (direction.dot(&self.normal) / direction.len()).abs();
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: Point3) -> Vector3 {
+        let (a, b) = self.shape.sample(random_float(), random_float());
+        (self.q + self.u * a + self.v * b) - origin
+    }
+}
+
+#[cfg(test)]
+mod test_quad {
+    use crate::engine::base::constants::constants;
+    use crate::engine::base::interval::Interval;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::objects::quad::Quad;
+    use crate::engine::objects::Objects;
+
+    fn shoot(object: &Objects, x: f32, y: f32) -> Option<HitRecord> {
+        let ray = Ray::new(Point3::new(x, y, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+        object.hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut rec).then_some(rec)
+    }
+
+    #[test]
+    fn quad_hits_only_inside_parallelogram(){
+        let quad = Quad::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 4.0, 0.0), MaterialId::default());
+
+        let rec = shoot(&quad, 0.5, 3.0).unwrap();
+        assert!((rec.u - 0.25).abs() < 1e-6);
+        assert!((rec.v - 0.75).abs() < 1e-6);
+
+        assert!(shoot(&quad, 2.5, 1.0).is_none());
+        assert!(shoot(&quad, 1.0, -0.5).is_none());
+    }
+
+    #[test]
+    fn triangle_rejects_far_corner(){
+        let triangle = Quad::triangle(Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), MaterialId::default());
+
+        assert!(shoot(&triangle, 0.2, 0.2).is_some());
+        assert!(shoot(&triangle, 0.8, 0.8).is_none());
+    }
+
+    #[test]
+    fn disk_is_round(){
+        let disk = Quad::disk(Point3::new(0.0, 0.0, 0.0), 1.0, Vector3::new(0.0, 0.0, 1.0), MaterialId::default());
+
+        let center = shoot(&disk, 0.0, 0.0).unwrap();
+        assert!((center.u - 0.5).abs() < 1e-6);
+        assert!((center.v - 0.5).abs() < 1e-6);
+
+        assert!(shoot(&disk, 0.65, 0.65).is_some());
+        assert!(shoot(&disk, 0.75, 0.75).is_none());
+    }
+
+    #[test]
+    fn ellipse_uses_both_axes(){
+        let ellipse = Quad::ellipse(Point3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 0.5, 0.0), MaterialId::default());
+
+        assert!(shoot(&ellipse, 1.9, 0.0).is_some());
+        assert!(shoot(&ellipse, 0.0, 0.6).is_none());
+    }
+}
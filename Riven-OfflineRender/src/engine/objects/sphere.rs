@@ -1,12 +1,14 @@
-use crate::engine::base::constants::constants::PI;
+use crate::engine::base::constants::constants::{random_float, INFINITY, PI};
 use crate::engine::base::interval::Interval;
+use crate::engine::base::onb::Onb;
 use crate::engine::base::point::Point3;
 use crate::engine::base::ray::Ray;
 use crate::engine::base::vector::Vector3;
 use crate::engine::bounding_model::aabb::AABB;
-use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::{MaterialArena, MaterialId};
+use crate::engine::objects::alpha_mask::AlphaMask;
 use crate::engine::objects::hit_record::HitRecord;
-use crate::engine::objects::object::GeometricObject;
+use crate::engine::objects::object::{GeometricObject, SHADOW_EPSILON};
 use crate::engine::objects::Objects;
 use crate::engine::objects::Objects::Spheres;
 
@@ -15,11 +17,12 @@ pub struct Sphere{
     center : Point3,
     radius : f32,
     bbox : AABB,
-    mat : MaterialType,
+    mat : MaterialId,
+    alpha : Option<AlphaMask>,
 }
 
 impl Sphere{
-    pub fn new(center: Point3, radius: f32, mat : MaterialType) -> Objects {
+    pub fn new(center: Point3, radius: f32, mat : MaterialId) -> Objects {
 
         let rvec = Vector3::new(radius, radius, radius);
 
@@ -28,6 +31,7 @@ impl Sphere{
             radius,
             bbox : AABB::from_points(center - rvec, center + rvec),
             mat,
+            alpha: None,
         })
     }
 
@@ -43,46 +47,113 @@ impl Sphere{
         let v = theta / PI;
         (u, v)
     }
-}
 
-impl GeometricObject for Sphere {
-    fn hit(&self, ray: &Ray, ray_t : &mut Interval, rec: &mut HitRecord) -> bool {
+    /// How a point on the sphere moves with its texture coordinates, `u` going around and `v` from pole to pole.
+    fn get_sphere_tangents(&self, point: Vector3) -> (Vector3, Vector3) {
+        // The distance to the axis, kept away from zero so the poles still have a frame
+        let ring = (point.x * point.x + point.z * point.z).sqrt().max(1e-4);
+        let dpdu = Vector3::new(point.z, 0.0, -point.x) * (2.0 * PI * self.radius);
+        let dpdv = Vector3::new(-point.y * point.x / ring, ring, -point.y * point.z / ring) * (PI * self.radius);
+        (dpdu, dpdv)
+    }
+
+    pub(crate) fn material(&self) -> MaterialId {
+        self.mat
+    }
+
+    /// Picks up the opacity mask of the sphere's material, if it has one.
+    pub(crate) fn bind_alpha_mask(&mut self, materials: &MaterialArena) {
+        self.alpha = materials.alpha_mask(self.mat).cloned();
+    }
+
+    /// Whether the ray stops where it crosses the sphere at `t`, rather than going through a hole.
+    fn solid_at(&self, ray: &Ray, t: f32) -> bool {
+        let Some(alpha) = &self.alpha else {
+            return true;
+        };
+        let point = ray.at(t);
+        let (u, v) = self.get_sphere_uv((point - self.center) / self.radius);
+        alpha.blocks(u, v, point)
+    }
+
+    /// The closest `t` inside `ray_t` at which the ray crosses the solid part of the sphere.
+    fn nearest_root(&self, ray: &Ray, ray_t: &Interval) -> Option<f32> {
         let oc = ray.origin - self.center;
         let a = ray.direction.len_squared();
         let h = oc.dot(&ray.direction);
         let c = oc.len_squared() - self.radius * self.radius;
         let discriminant = h * h - a * c;
 
-        if discriminant > 0.0 {
-            let sqrt_d = discriminant.sqrt();
-
-            // First root
-            let mut root = (-h - sqrt_d) / a;
-            if !ray_t.surrounds(root) {
-                // Second root
-                root = (-h + sqrt_d) / a;
-                if !ray_t.surrounds(root){
-                    return false;
-                }
-            }
-
-            rec.t = root;
-            rec.point = ray.at(rec.t);
-            let outward_normal = (rec.point - self.center) / self.radius;
-            let (u, v) = self.get_sphere_uv(outward_normal);
-
-            rec.set_face_normal(ray, outward_normal);
-            rec.mat = self.mat.clone();
-            rec.v = v;
-            rec.u = u;
-
-            return true;
+        if discriminant <= 0.0 {
+            return None;
         }
+        let sqrt_d = discriminant.sqrt();
 
-        false
+        // The first root, or the second one when the first is out of range or cut out
+        [(-h - sqrt_d) / a, (-h + sqrt_d) / a]
+            .into_iter()
+            .find(|&root| ray_t.surrounds(root) && self.solid_at(ray, root))
+    }
+
+    /// `1 - cos(theta_max)` for the cone of directions from `origin` covering the sphere, `None` from inside it.
+    fn cone_gap(&self, origin: Point3) -> Option<f32> {
+        let ratio = self.radius * self.radius / (self.center - origin).len_squared();
+        // Written as `x / (1 + sqrt(1 - x))` so small, far away spheres don't cancel out to zero
+        (ratio < 1.0).then(|| ratio / (1.0 + (1.0 - ratio).sqrt()))
+    }
+}
+
+impl GeometricObject for Sphere {
+    fn hit(&self, ray: &Ray, ray_t : &mut Interval, rec: &mut HitRecord) -> bool {
+        let Some(root) = self.nearest_root(ray, ray_t) else {
+            return false;
+        };
+
+        rec.t = root;
+        rec.point = ray.at(rec.t);
+        let outward_normal = (rec.point - self.center) / self.radius;
+        let (u, v) = self.get_sphere_uv(outward_normal);
+
+        rec.set_face_normal(ray, outward_normal);
+        rec.mat = self.mat;
+        rec.v = v;
+        rec.u = u;
+        (rec.dpdu, rec.dpdv) = self.get_sphere_tangents(outward_normal);
+
+        true
+    }
+
+    fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        self.nearest_root(ray, &Interval::new(SHADOW_EPSILON, t_max)).is_some()
     }
 
     fn bounding_box(&self) -> AABB {
         return self.bbox.to_owned()
     }
+
+    fn pdf_value(&self, origin: Point3, direction: Vector3) -> f32 {
+        let Some(cone_gap) = self.cone_gap(origin) else {
+            return 0.0;
+        };
+
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(origin, direction), &mut Interval::new(0.0001, INFINITY), &mut rec) {
+            return 0.0;
+        }
+
+        // Directions are picked uniformly within the cone
+        1.0 / (2.0 * PI * cone_gap)
+    }
+
+    fn random(&self, origin: Point3) -> Vector3 {
+        let Some(cone_gap) = self.cone_gap(origin) else {
+            return Vector3::random_unit_vector();
+        };
+
+        let phi = 2.0 * PI * random_float();
+        let z = 1.0 - random_float() * cone_gap;
+        let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+
+        Onb::new(self.center - origin).transform(Vector3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z))
+    }
 }
\ No newline at end of file
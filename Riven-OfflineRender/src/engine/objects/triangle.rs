@@ -0,0 +1,397 @@
+use std::sync::Arc;
+use crate::engine::base::constants::constants::{random_float, INFINITY};
+use crate::engine::base::interval::Interval;
+use crate::engine::base::point::Point3;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::bounding_model::aabb::AABB;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::{MaterialArena, MaterialId};
+use crate::engine::objects::alpha_mask::AlphaMask;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::objects::object::{GeometricObject, HitList, SHADOW_EPSILON};
+use crate::engine::objects::Objects;
+use crate::engine::objects::Objects::Triangles;
+
+/// An indexed triangle mesh whose vertex buffers are shared by all of its triangles.
+pub struct TriangleMesh {
+    /// Vertex positions.
+    positions: Vec<Point3>,
+    /// Per-vertex shading normals, either empty or one per position.
+    normals: Vec<Vector3>,
+    /// Per-vertex texture coordinates, either empty or one per position.
+    uvs: Vec<(f32, f32)>,
+    /// Three vertex indices per triangle.
+    indices: Vec<[u32; 3]>,
+    mat: MaterialId,
+}
+
+impl TriangleMesh {
+    /// Creates a new `TriangleMesh` from its vertex buffers.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - The vertex positions.
+    /// * `normals` - Per-vertex normals, or an empty vector to use the face normal.
+    /// * `uvs` - Per-vertex texture coordinates, or an empty vector to use barycentric coordinates.
+    /// * `indices` - Three indices into the vertex buffers per triangle.
+    /// * `mat` - The material shared by every triangle of the mesh.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if a buffer size doesn't match `positions` or if an index is out of bounds.
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vector3>,
+        uvs: Vec<(f32, f32)>,
+        indices: Vec<[u32; 3]>,
+        mat: MaterialId
+    ) -> Arc<TriangleMesh> {
+        assert!(normals.is_empty() || normals.len() == positions.len(), "Mesh normals don't match its positions");
+        assert!(uvs.is_empty() || uvs.len() == positions.len(), "Mesh uvs don't match its positions");
+        assert!(
+            indices.iter().flatten().all(|&i| (i as usize) < positions.len()),
+            "Mesh index is out of bounds"
+        );
+
+        Arc::new(Self {
+            positions,
+            normals,
+            uvs,
+            indices,
+            mat,
+        })
+    }
+
+    /// Returns the number of triangles in the mesh.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Creates one `Triangle` object per face of the mesh, ready to be added to a `HitList` or `Bvh`.
+    pub fn triangles(mesh: &Arc<TriangleMesh>) -> Vec<Objects> {
+        (0..mesh.len())
+            .map(|index| Triangle::new(Arc::clone(mesh), index))
+            .collect()
+    }
+
+    /// Creates a `HitList` holding every triangle of the mesh.
+    pub fn to_hit_list(mesh: &Arc<TriangleMesh>) -> HitList {
+        let mut list = HitList::new();
+        for triangle in Self::triangles(mesh) {
+            list.add(triangle);
+        }
+        list
+    }
+}
+
+/// A single face of a `TriangleMesh`.
+#[derive(Clone)]
+pub struct Triangle {
+    mesh: Arc<TriangleMesh>,
+    index: usize,
+    alpha: Option<AlphaMask>,
+}
+
+impl Triangle {
+    pub fn new(mesh: Arc<TriangleMesh>, index: usize) -> Objects {
+        assert!(index < mesh.len(), "Triangle index is out of bounds");
+        Triangles(Self { mesh, index, alpha: None })
+    }
+
+    #[inline]
+    fn vertices(&self) -> [usize; 3] {
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+        [i0 as usize, i1 as usize, i2 as usize]
+    }
+
+    pub(crate) fn material(&self) -> MaterialId {
+        self.mesh.mat
+    }
+
+    /// Picks up the opacity mask of the mesh's material, if it has one.
+    pub(crate) fn bind_alpha_mask(&mut self, materials: &MaterialArena) {
+        self.alpha = materials.alpha_mask(self.mesh.mat).cloned();
+    }
+
+    /// The texture coordinates at the point with barycentric weights `b0`, `b1` and `b2`.
+    fn texture_coordinates(&self, [b0, b1, b2]: [f32; 3]) -> (f32, f32) {
+        if self.mesh.uvs.is_empty() {
+            return (b1, b2);
+        }
+
+        let [i0, i1, i2] = self.vertices();
+        let (uv0, uv1, uv2) = (self.mesh.uvs[i0], self.mesh.uvs[i1], self.mesh.uvs[i2]);
+        (
+            b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+            b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1
+        )
+    }
+
+    /// Sheared and scaled edge functions of the watertight test, see Woop et al. 2013.
+    #[inline]
+    fn edge_functions(ax: f32, ay: f32, bx: f32, by: f32, cx: f32, cy: f32) -> (f32, f32, f32) {
+        let mut e0 = cx * by - cy * bx;
+        let mut e1 = ax * cy - ay * cx;
+        let mut e2 = bx * ay - by * ax;
+
+        // Fall back to double precision when a ray runs exactly through an edge
+        if e0 == 0.0 || e1 == 0.0 || e2 == 0.0 {
+            e0 = (cx as f64 * by as f64 - cy as f64 * bx as f64) as f32;
+            e1 = (ax as f64 * cy as f64 - ay as f64 * cx as f64) as f32;
+            e2 = (bx as f64 * ay as f64 - by as f64 * ax as f64) as f32;
+        }
+
+        (e0, e1, e2)
+    }
+
+    /// Watertight ray/triangle intersection, see Woop et al. 2013.
+    ///
+    /// # Returns
+    ///
+    /// The ray parameter of the intersection and the barycentric weights of the three vertices.
+    /// Nothing when the ray misses, or goes through a hole cut by the material's mask.
+    fn intersect(&self, ray: &Ray, ray_t: &Interval) -> Option<(f32, [f32; 3])> {
+        let [i0, i1, i2] = self.vertices();
+        let (p0, p1, p2) = (self.mesh.positions[i0], self.mesh.positions[i1], self.mesh.positions[i2]);
+
+        // Permute the axes so the ray direction's largest component becomes z
+        let d = ray.direction;
+        let kz = if d.x.abs() > d.y.abs() {
+            if d.x.abs() > d.z.abs() { 0 } else { 2 }
+        } else if d.y.abs() > d.z.abs() { 1 } else { 2 };
+        let mut kx = (kz + 1) % 3;
+        let mut ky = (kx + 1) % 3;
+        if d[kz] < 0.0 {
+            std::mem::swap(&mut kx, &mut ky);
+        }
+
+        // Shear so that the ray points along +z
+        let sx = d[kx] / d[kz];
+        let sy = d[ky] / d[kz];
+        let sz = 1.0 / d[kz];
+
+        let a = p0 - ray.origin;
+        let b = p1 - ray.origin;
+        let c = p2 - ray.origin;
+
+        let (ax, ay) = (a[kx] - sx * a[kz], a[ky] - sy * a[kz]);
+        let (bx, by) = (b[kx] - sx * b[kz], b[ky] - sy * b[kz]);
+        let (cx, cy) = (c[kx] - sx * c[kz], c[ky] - sy * c[kz]);
+
+        let (e0, e1, e2) = Self::edge_functions(ax, ay, bx, by, cx, cy);
+
+        if (e0 < 0.0 || e1 < 0.0 || e2 < 0.0) && (e0 > 0.0 || e1 > 0.0 || e2 > 0.0) {
+            return None;
+        }
+
+        let det = e0 + e1 + e2;
+        if det == 0.0 {
+            return None;
+        }
+
+        let t_scaled = e0 * sz * a[kz] + e1 * sz * b[kz] + e2 * sz * c[kz];
+        let t = t_scaled / det;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        // Barycentric weights of p0, p1 and p2
+        let weights = [e0 / det, e1 / det, e2 / det];
+        if let Some(mask) = &self.alpha {
+            let (u, v) = self.texture_coordinates(weights);
+            if !mask.blocks(u, v, p0 + (p1 - p0) * weights[1] + (p2 - p0) * weights[2]) {
+                return None;
+            }
+        }
+        Some((t, weights))
+    }
+
+    /// How a point on the triangle moves with its texture coordinates.
+    ///
+    /// Meshes without texture coordinates, or with degenerate ones, have no tangents: the barycentric
+    /// weights standing in for `u` and `v` restart on every triangle, so nothing could follow them.
+    fn tangents(&self, p0: Point3, p1: Point3, p2: Point3) -> (Vector3, Vector3) {
+        if self.mesh.uvs.is_empty() {
+            return (Vector3::default(), Vector3::default());
+        }
+
+        let [i0, i1, i2] = self.vertices();
+        let (uv0, uv1, uv2) = (self.mesh.uvs[i0], self.mesh.uvs[i1], self.mesh.uvs[i2]);
+        let (du1, dv1) = (uv1.0 - uv0.0, uv1.1 - uv0.1);
+        let (du2, dv2) = (uv2.0 - uv0.0, uv2.1 - uv0.1);
+        let det = du1 * dv2 - dv1 * du2;
+        if det.abs() < 1e-12 {
+            return (Vector3::default(), Vector3::default());
+        }
+
+        // Invert the map from texture offsets to edge vectors
+        let (e1, e2) = (p1 - p0, p2 - p0);
+        ((e1 * dv2 - e2 * dv1) / det, (e2 * du1 - e1 * du2) / det)
+    }
+}
+
+impl GeometricObject for Triangle {
+    fn hit(&self, ray: &Ray, ray_t: &mut Interval, rec: &mut HitRecord) -> bool {
+        let Some((t, [b0, b1, b2])) = self.intersect(ray, ray_t) else {
+            return false;
+        };
+
+        let [i0, i1, i2] = self.vertices();
+        let (p0, p1, p2) = (self.mesh.positions[i0], self.mesh.positions[i1], self.mesh.positions[i2]);
+
+        let geometric_normal = (p1 - p0).cross(&(p2 - p0)).unit_vector();
+        let outward_normal = if self.mesh.normals.is_empty() {
+            geometric_normal
+        } else {
+            let n = self.mesh.normals[i0] * b0 + self.mesh.normals[i1] * b1 + self.mesh.normals[i2] * b2;
+            if n.near_zero() { geometric_normal } else { n.unit_vector() }
+        };
+
+        let (u, v) = self.texture_coordinates([b0, b1, b2]);
+        let (dpdu, dpdv) = self.tangents(p0, p1, p2);
+
+        rec.t = t;
+        rec.point = p0 + (p1 - p0) * b1 + (p2 - p0) * b2;
+        // The face is decided by the true surface, the shading normal is only flipped to agree with it
+        rec.front_face = ray.direction.dot(&geometric_normal) < 0.0;
+        let facing = if rec.front_face { geometric_normal } else { -geometric_normal };
+        rec.normal = if outward_normal.dot(&facing) < 0.0 { -outward_normal } else { outward_normal };
+        rec.mat = self.mesh.mat;
+        rec.u = u;
+        rec.v = v;
+        rec.dpdu = dpdu;
+        rec.dpdv = dpdv;
+
+        true
+    }
+
+    fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        self.intersect(ray, &Interval::new(SHADOW_EPSILON, t_max)).is_some()
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let [i0, i1, i2] = self.vertices();
+        let (p0, p1, p2) = (self.mesh.positions[i0], self.mesh.positions[i1], self.mesh.positions[i2]);
+
+        AABB::from_intervals(
+            Interval::new(p0.x.min(p1.x).min(p2.x), p0.x.max(p1.x).max(p2.x)),
+            Interval::new(p0.y.min(p1.y).min(p2.y), p0.y.max(p1.y).max(p2.y)),
+            Interval::new(p0.z.min(p1.z).min(p2.z), p0.z.max(p1.z).max(p2.z)),
+        )
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vector3) -> f32 {
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(origin, direction), &mut Interval::new(0.0001, INFINITY), &mut rec) {
+            return 0.0;
+        }
+
+        let [i0, i1, i2] = self.vertices();
+        let (p0, p1, p2) = (self.mesh.positions[i0], self.mesh.positions[i1], self.mesh.positions[i2]);
+        let n = (p1 - p0).cross(&(p2 - p0));
+
+        // Points are picked uniformly by area, converted to a density over solid angle
+        let distance_squared = rec.t * rec.t * direction.len_squared();
+        let cosine = (direction.dot(&n) / (direction.len() * n.len())).abs();
+        distance_squared / (cosine * 0.5 * n.len())
+    }
+
+    fn random(&self, origin: Point3) -> Vector3 {
+        let [i0, i1, i2] = self.vertices();
+        let (p0, p1, p2) = (self.mesh.positions[i0], self.mesh.positions[i1], self.mesh.positions[i2]);
+
+        // Square root warping spreads the barycentric weights uniformly over the triangle
+        let (s, r) = (random_float().sqrt(), random_float());
+        let (b1, b2) = (s * (1.0 - r), s * r);
+        (p0 + (p1 - p0) * b1 + (p2 - p0) * b2) - origin
+    }
+}
+
+#[cfg(test)]
+mod test_triangle {
+    use crate::engine::base::constants::constants;
+    use crate::engine::base::interval::Interval;
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::bounding_model::bvh::Bvh;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialId;
+    use crate::engine::objects::hit_record::HitRecord;
+    use crate::engine::objects::triangle::TriangleMesh;
+
+    fn unit_square() -> std::sync::Arc<TriangleMesh> {
+        TriangleMesh::new(
+            vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(1.0, 1.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            Vec::new(),
+            vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+            vec![[0, 1, 2], [0, 2, 3]],
+            MaterialId::default()
+        )
+    }
+
+    #[test]
+    fn triangle_interpolates_uvs(){
+        let mesh = unit_square();
+        let list = TriangleMesh::to_hit_list(&mesh);
+
+        let ray = Ray::new(Point3::new(0.75, 0.25, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+
+        assert!(list.objects[0].hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut rec));
+        assert!((rec.t - 1.0).abs() < 1e-6);
+        assert!((rec.u - 0.75).abs() < 1e-6);
+        assert!((rec.v - 0.25).abs() < 1e-6);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn triangle_misses_outside_ray(){
+        let mesh = unit_square();
+        let list = TriangleMesh::to_hit_list(&mesh);
+
+        let ray = Ray::new(Point3::new(0.25, 0.75, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::default();
+
+        assert!(!list.objects[0].hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut rec));
+    }
+
+    #[test]
+    fn shared_edge_is_watertight(){
+        let mesh = unit_square();
+        let list = TriangleMesh::to_hit_list(&mesh);
+
+        // Rays through the shared diagonal must hit at least one of the two faces
+        for i in 1..100 {
+            let s = i as f32 / 100.0;
+            let ray = Ray::new(Point3::new(s, s, 1.0), Vector3::new(0.0001, -0.0002, -1.0));
+            let hits = list.objects.iter()
+                .filter(|triangle| triangle.hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut HitRecord::default()))
+                .count();
+            assert!(hits >= 1, "ray through ({s}, {s}) leaked through the mesh");
+        }
+    }
+
+    #[test]
+    fn triangles_work_inside_bvh(){
+        let mesh = unit_square();
+        let bvh = Bvh::from_world(TriangleMesh::to_hit_list(&mesh));
+
+        let ray = Ray::new(Point3::new(0.25, 0.75, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        let mut rec = HitRecord::default();
+
+        assert!(bvh.hit(&ray, &mut Interval::new(0.0001, constants::INFINITY), &mut rec));
+        assert!((rec.t - 2.0).abs() < 1e-6);
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, Vector3::new(0.0, 0.0, -1.0));
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use crate::engine::base::interval::Interval;
+use crate::engine::base::ray::Ray;
+use crate::engine::lighting::background::Background;
+use crate::engine::lighting::diffuse_lighting_model::material_arena::{MaterialArena, MaterialId};
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::lights::LightType;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::objects::object::HitList;
+use crate::engine::objects::Objects;
+
+/// Everything a tracer needs to render: the geometry, the materials it refers to and the background.
+pub struct Scene {
+    pub world: Objects,
+    pub materials: MaterialArena,
+    /// Radiance of rays that escape the scene.
+    pub background: Background,
+    /// Emissive objects sampled directly by the path tracer, they must also be part of `world`.
+    pub lights: HitList,
+    /// Point, spot and directional lights, only seen by the path tracer through shadow rays.
+    pub punctual_lights: Vec<LightType>,
+}
+
+impl Scene {
+    /// Creates a `Scene` from the world and the arena its primitives' `MaterialId`s point into,
+    /// lit by the default sky.
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - The objects of the scene, usually a `Bvh`.
+    /// * `materials` - The arena the world's materials were added to.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Scene`.
+    pub fn new(mut world: Objects, materials: MaterialArena) -> Self {
+        // Cut out primitives test their material's mask while they're traversed, so they keep a handle on it
+        if materials.has_masks() {
+            world.bind_alpha_masks(&materials, &mut HashMap::new());
+        }
+        Self { world, materials, background: Background::default(), lights: HitList::new(), punctual_lights: Vec::new() }
+    }
+
+    pub fn hit(&self, ray: &Ray, ray_t: &mut Interval, rec: &mut HitRecord) -> bool {
+        self.world.hit(ray, ray_t, rec)
+    }
+
+    /// Determines if anything in the world blocks a shadow ray before it travels `t_max`.
+    pub fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        self.world.occluded(ray, t_max)
+    }
+
+    /// The fraction of light that makes it along a shadow ray to `t_max`, through any participating media.
+    pub fn transmittance(&self, ray: &Ray, t_max: f32) -> f32 {
+        self.world.transmittance(ray, t_max)
+    }
+
+    pub fn material(&self, id: MaterialId) -> &MaterialType {
+        &self.materials[id]
+    }
+}
@@ -1,3 +1,4 @@
+use std::sync::Arc;
 use crate::engine::base::point::Point3;
 use crate::engine::textures::{Texture, TextureType};
 use crate::engine::textures::TextureType::ChessBoard;
@@ -6,8 +7,8 @@ use crate::util::color::Color;
 #[derive(Clone)]
 pub struct ChessBoardTexture{
     inv_scale : f32,
-    even : Box<TextureType>,
-    odd : Box<TextureType>
+    even : Arc<TextureType>,
+    odd : Arc<TextureType>
 }
 
 impl Texture for ChessBoardTexture{
@@ -30,8 +31,8 @@ impl ChessBoardTexture{
     pub fn new(scale : f32, even : TextureType, odd : TextureType) -> TextureType {
         ChessBoard(Self{
             inv_scale : 1.0 / scale,
-            even : Box::new(even),
-            odd  : Box::new(odd)
+            even : Arc::new(even),
+            odd  : Arc::new(odd)
         })
     }
 }
\ No newline at end of file
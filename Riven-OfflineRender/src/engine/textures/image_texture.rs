@@ -1,34 +1,79 @@
 use std::path::Path;
-use image::{DynamicImage, ImageReader, RgbImage};
+use std::sync::Arc;
+use image::{DynamicImage, ImageError, ImageReader, Rgb, RgbImage};
 use crate::engine::base::point::Point3;
 use crate::engine::textures::{Texture, TextureType};
 use crate::util::color::Color;
 
+/// A texture sampled from an image, clones share the decoded pixels.
 #[derive(Clone)]
 pub struct ImageTexture {
-    texture_image: RgbImage,
+    texture_image: Arc<RgbImage>,
 }
 
 impl ImageTexture {
     pub fn new(filename: &str) -> TextureType {
-        let path = Path::new(filename);
-
-        // Open the image file and decode
-        let binding = ImageReader::open(path)
-            .expect("Didn't find the specified file")
-            .decode()
-            .expect("Failed to decode image");
-
-        // Try to convert to RgbImage, handle other formats gracefully
-        let image = match binding {
-            DynamicImage::ImageRgb8(img) => img,
-            DynamicImage::ImageLuma8(img) => DynamicImage::ImageLuma8(img).to_rgb8(), // Convert Grayscale to RGB
-            DynamicImage::ImageLumaA8(img) => DynamicImage::ImageLumaA8(img).to_rgb8(), // Convert Grayscale+Alpha to RGB
-            _ => panic!("Unsupported image format"),
+        Self::try_new(filename).expect("Failed to load the texture image")
+    }
+
+    /// Loads an image texture, reporting missing or undecodable files instead of panicking.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The path of the image to load.
+    ///
+    /// # Returns
+    ///
+    /// The texture, or the `ImageError` raised while opening or decoding the file.
+    pub fn try_new<P: AsRef<Path>>(filename: P) -> Result<TextureType, ImageError> {
+        // Open the image file and decode, any pixel format is converted to 8-bit RGB
+        let image = ImageReader::open(filename.as_ref())?
+            .decode()?
+            .to_rgb8();
+
+        Ok(TextureType::Image(Self {
+            texture_image: Arc::new(image),
+        }))
+    }
+
+    /// Loads an image as an opacity map, like the cutout of a leaf, stored in all three channels.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The path of the image to load.
+    ///
+    /// # Returns
+    ///
+    /// The texture holding the image's alpha channel, or its luminance when it has none,
+    /// or the `ImageError` raised while opening or decoding the file.
+    pub fn try_opacity<P: AsRef<Path>>(filename: P) -> Result<TextureType, ImageError> {
+        let image = ImageReader::open(filename.as_ref())?.decode()?;
+
+        // Cutouts are usually the alpha of the color map itself
+        let opacity = if image.color().has_alpha() {
+            let rgba = image.to_rgba8();
+            RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+                let alpha = rgba.get_pixel(x, y)[3];
+                Rgb([alpha, alpha, alpha])
+            })
+        } else {
+            DynamicImage::ImageLuma8(image.to_luma8()).to_rgb8()
         };
 
+        Ok(TextureType::Image(Self {
+            texture_image: Arc::new(opacity),
+        }))
+    }
+
+    /// A copy of the texture with every channel multiplied by `factor`, clamped to one.
+    pub(crate) fn scaled(&self, factor: f32) -> TextureType {
+        let mut image = self.texture_image.as_ref().clone();
+        for pixel in image.pixels_mut() {
+            pixel.0 = pixel.0.map(|channel| (channel as f32 * factor).round().clamp(0.0, 255.0) as u8);
+        }
+
         TextureType::Image(Self {
-            texture_image: image,
+            texture_image: Arc::new(image),
         })
     }
 }
@@ -61,4 +106,4 @@ pub mod im_texture {
 
 
     pub const  NIGHT_EARTH: &str = "G:/Projects/Rust/Riven/Riven-OfflineRender/src/engine/textures/images/nightEarth.jpg";
-}
\ No newline at end of file
+}
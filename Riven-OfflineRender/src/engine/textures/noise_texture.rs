@@ -1,4 +1,5 @@
 use std::ops::{Add, Mul};
+use std::sync::Arc;
 use crate::engine::base::point::Point3;
 use crate::engine::textures::noise::perlin_noise::PerlinNoise;
 use crate::engine::textures::{Texture, TextureType};
@@ -6,7 +7,7 @@ use crate::util::color::Color;
 
 #[derive(Clone)]
 pub struct NoiseTexture{
-    noise : PerlinNoise,
+    noise : Arc<PerlinNoise>,
     scale : f32
 }
 
@@ -14,7 +15,7 @@ impl NoiseTexture{
     pub fn new(scale : f32) -> TextureType {
         TextureType::Noise(
             Self {
-                noise: PerlinNoise::new(),
+                noise: Arc::new(PerlinNoise::new()),
                 scale
             }
         )
@@ -1,11 +1,50 @@
 use crate::engine::base::ray::Ray;
-use crate::engine::objects::object::HitList;
+use crate::engine::scene::Scene;
+use crate::engine::tracers::normal_tracer::NormalTracer;
+use crate::engine::tracers::path_tracer::PathTracer;
+use crate::engine::tracers::recursive_tracer::RecursiveTracer;
+use crate::engine::tracers::TracerTypes::{Normals, Path, Recursive};
+use crate::util::color::Color;
 
+pub mod recursive_tracer;
+pub mod normal_tracer;
+pub mod path_tracer;
+
+/// An integrator, turns a camera ray into the radiance arriving along it.
+pub trait Tracer{
+    /// Computes the radiance carried back along a ray.
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - The ray to trace, usually straight from the camera.
+    /// * `scene` - The objects, materials and background the ray travels through.
+    ///
+    /// # Returns
+    ///
+    /// A `Color` holding the estimated radiance.
+    fn trace_ray(&self, ray: &Ray, scene: &Scene) -> Color;
+}
+
+/// The integrators a camera can render with.
+#[derive(Clone)]
 pub enum TracerTypes{
+    Recursive(RecursiveTracer),
+    Path(PathTracer),
+    Normals(NormalTracer),
+}
 
+impl Tracer for TracerTypes{
+    fn trace_ray(&self, ray: &Ray, scene: &Scene) -> Color {
+        match self {
+            Recursive(tracer) => tracer.trace_ray(ray, scene),
+            Path(tracer) => tracer.trace_ray(ray, scene),
+            Normals(tracer) => tracer.trace_ray(ray, scene),
+        }
+    }
 }
 
-pub trait Tracer{
-    fn new_tracer(world : &HitList);
-    fn trace_ray(ray : &Ray);
-}
\ No newline at end of file
+impl Default for TracerTypes{
+    fn default() -> Self {
+        RecursiveTracer::new(10)
+    }
+}
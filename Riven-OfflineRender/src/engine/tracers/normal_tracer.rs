@@ -0,0 +1,29 @@
+use crate::engine::base::constants::constants;
+use crate::engine::base::interval::Interval;
+use crate::engine::base::ray::Ray;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::scene::Scene;
+use crate::engine::tracers::{Tracer, TracerTypes};
+use crate::util::color::Color;
+
+/// A debug view showing the surface normal of the first hit, each axis mapped from `[-1, 1]` to `[0, 1]`.
+#[derive(Clone, Default)]
+pub struct NormalTracer;
+
+impl NormalTracer{
+    pub fn new() -> TracerTypes {
+        TracerTypes::Normals(Self)
+    }
+}
+
+impl Tracer for NormalTracer{
+    fn trace_ray(&self, ray: &Ray, scene: &Scene) -> Color {
+        let mut rec = HitRecord::default();
+        if !scene.hit(ray, &mut Interval::new(0.0001f32, constants::INFINITY), &mut rec) {
+            return Color::default();
+        }
+
+        let n = rec.normal.unit_vector();
+        Color::new(0.5 * (n.x + 1.0), 0.5 * (n.y + 1.0), 0.5 * (n.z + 1.0))
+    }
+}
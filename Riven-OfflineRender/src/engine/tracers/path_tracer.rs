@@ -0,0 +1,528 @@
+use crate::engine::base::constants::constants;
+use crate::engine::base::constants::constants::random_float;
+use crate::engine::base::interval::Interval;
+use crate::engine::base::point::Point3;
+use crate::engine::base::ray::Ray;
+use crate::engine::base::vector::Vector3;
+use crate::engine::lighting::background::Background;
+use crate::engine::lighting::diffuse_lighting_model::MaterialType;
+use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+use crate::engine::lighting::environment_map::EnvironmentMap;
+use crate::engine::lighting::lights::Light;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::objects::object::{GeometricObject, SHADOW_EPSILON};
+use crate::engine::scene::Scene;
+use crate::engine::tracers::{Tracer, TracerTypes};
+use crate::util::color::Color;
+use crate::util::spectrum::{Radiance, SampledSpectrum, SampledWavelengths};
+
+/// Bounces a path must survive before Russian roulette may end it.
+const ROULETTE_DEPTH: i32 = 3;
+
+/// A unidirectional path tracer.
+///
+/// At every non-specular bounce the scene's lights are sampled directly (next-event estimation)
+/// and combined with the material's own sampling through multiple importance sampling.
+///
+/// It renders in RGB, or in spectral mode where every camera ray carries a few wavelengths and
+/// the RGB colors of materials and lights are upsampled to spectra.
+#[derive(Clone)]
+pub struct PathTracer{
+    /// Maximum number of ray bounces into the scene.
+    pub(crate) max_depth : i32,
+    /// Whether paths carry wavelengths rather than RGB triples.
+    pub(crate) spectral : bool,
+}
+
+impl PathTracer{
+    pub fn new(max_depth : i32) -> TracerTypes {
+        TracerTypes::Path(Self { max_depth, spectral: false })
+    }
+
+    /// Creates a path tracer in spectral mode, following a hero wavelength and a few others per camera ray.
+    ///
+    /// Dispersion is then followed at every wavelength rather than split off at the first glass
+    /// surface, at the cost of more color noise.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_depth` - Maximum number of ray bounces into the scene.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `TracerTypes::Path`.
+    pub fn spectral(max_depth : i32) -> TracerTypes {
+        TracerTypes::Path(Self { max_depth, spectral: true })
+    }
+
+    /// Estimates the light arriving at a surface point straight from the scene's lights.
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - The ray that hit the surface, carrying the path's wavelengths if it has some.
+    /// * `rec` - The surface point.
+    /// * `mat` - The material at the surface point, it must not be specular.
+    /// * `scene` - The scene to sample lights from and test visibility in.
+    ///
+    /// # Returns
+    ///
+    /// The reflected radiance, already weighted against the material's sampling.
+    fn sample_lights<S: Radiance>(ray: &Ray, rec: &HitRecord, mat: &MaterialType, scene: &Scene) -> S {
+        let direction = scene.lights.random(rec.point);
+        let light_pdf = scene.lights.pdf_value(rec.point, direction);
+        if light_pdf <= 0.0 {
+            return S::constant(0.0);
+        }
+
+        let f = mat.eval(ray, rec, &direction);
+        if is_black(&f) {
+            return S::constant(0.0);
+        }
+
+        // Whatever the light ray hits first decides both the visibility and the emitted radiance
+        let light_ray = Ray::new(rec.point, direction);
+        let mut light_rec = HitRecord::default();
+        if !scene.hit(&light_ray, &mut Interval::new(0.0001f32, constants::INFINITY), &mut light_rec) {
+            return S::constant(0.0);
+        }
+
+        let emitted = scene.material(light_rec.mat).emitted(light_rec.u, light_rec.v, light_rec.point);
+        let weight = power_heuristic(light_pdf, mat.pdf(ray, rec, &direction));
+        let wavelengths = ray.wavelengths.as_ref();
+        (S::from_color(&f, wavelengths) * S::from_color(&emitted, wavelengths)).scale(weight / light_pdf)
+    }
+
+    /// Sums the light every punctual light sends straight to a surface point.
+    ///
+    /// Punctual lights can't be hit by the material's own samples, so no MIS weight is needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - The ray that hit the surface, carrying the path's wavelengths if it has some.
+    /// * `rec` - The surface point.
+    /// * `mat` - The material at the surface point, it must not be specular.
+    /// * `scene` - The scene holding the lights and anything that may cast shadows.
+    ///
+    /// # Returns
+    ///
+    /// The reflected radiance.
+    fn sample_punctual_lights<S: Radiance>(ray: &Ray, rec: &HitRecord, mat: &MaterialType, scene: &Scene) -> S {
+        let mut radiance = S::constant(0.0);
+        for light in &scene.punctual_lights {
+            let Some(sample) = light.sample(rec.point) else {
+                continue;
+            };
+
+            let f = mat.eval(ray, rec, &sample.direction);
+            if is_black(&f) {
+                continue;
+            }
+
+            let transmittance = scene.transmittance(&Ray::new(rec.point, sample.direction), sample.distance - SHADOW_EPSILON);
+            if transmittance <= 0.0 {
+                continue;
+            }
+
+            let wavelengths = ray.wavelengths.as_ref();
+            radiance = radiance + (S::from_color(&f, wavelengths) * S::from_color(&sample.radiance, wavelengths)).scale(transmittance);
+        }
+        radiance
+    }
+
+    /// Estimates the light arriving at a surface point from an importance sampled environment map.
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - The ray that hit the surface, carrying the path's wavelengths if it has some.
+    /// * `rec` - The surface point.
+    /// * `mat` - The material at the surface point, it must not be specular.
+    /// * `map` - The scene's environment map.
+    /// * `scene` - The scene to test visibility in.
+    ///
+    /// # Returns
+    ///
+    /// The reflected radiance, already weighted against the material's sampling.
+    fn sample_environment<S: Radiance>(ray: &Ray, rec: &HitRecord, mat: &MaterialType, map: &EnvironmentMap, scene: &Scene) -> S {
+        let direction = map.random();
+        let light_pdf = map.pdf_value(direction);
+        if light_pdf <= 0.0 {
+            return S::constant(0.0);
+        }
+
+        let f = mat.eval(ray, rec, &direction);
+        if is_black(&f) {
+            return S::constant(0.0);
+        }
+        let transmittance = scene.transmittance(&Ray::new(rec.point, direction), constants::INFINITY);
+        if transmittance <= 0.0 {
+            return S::constant(0.0);
+        }
+
+        let weight = power_heuristic(light_pdf, mat.pdf(ray, rec, &direction));
+        let wavelengths = ray.wavelengths.as_ref();
+        (S::from_color(&f, wavelengths) * S::from_color(&map.value(direction), wavelengths)).scale(transmittance * weight / light_pdf)
+    }
+
+    /// The weight an escaping ray found by following the material's sampling gets against environment sampling.
+    fn background_weight(scene: &Scene, direction: Vector3, material_pdf: f32) -> f32 {
+        match &scene.background {
+            Background::Environment(map) => power_heuristic(material_pdf, map.pdf_value(direction)),
+            _ => 1.0,
+        }
+    }
+
+    /// The weight emission found by following the material's sampling gets against light sampling.
+    fn emission_weight(scene: &Scene, origin: Point3, direction: Vector3, material_pdf: f32) -> f32 {
+        if scene.lights.objects.is_empty() {
+            return 1.0;
+        }
+        power_heuristic(material_pdf, scene.lights.pdf_value(origin, direction))
+    }
+
+    /// Follows a path from a camera ray, in RGB or at the wavelengths the ray carries.
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - The camera ray.
+    /// * `scene` - The scene to trace through.
+    ///
+    /// # Returns
+    ///
+    /// The radiance carried back, and the wavelengths the path ended with. Glass may have dropped
+    /// all but the hero on the way.
+    fn trace_path<S: Radiance>(&self, ray: &Ray, scene: &Scene) -> (S, Option<SampledWavelengths>) {
+        let mut radiance = S::constant(0.0);
+        let mut throughput = S::constant(1.0);
+        let mut wavelengths = ray.wavelengths;
+        let mut ray = Ray::with_wavelengths(ray.origin, ray.direction, wavelengths);
+
+        // Camera rays and specular bounces can't be matched by light sampling, they see emission in full
+        let mut specular_bounce = true;
+        let mut material_pdf = 0.0;
+        let mut previous_point = ray.origin;
+        let exit_lobe = Lambertian::new(1.0, 1.0, 1.0);
+
+        for depth in 0..self.max_depth {
+            let mut rec = HitRecord::default();
+            if !scene.hit(&ray, &mut Interval::new(0.0001f32, constants::INFINITY), &mut rec) {
+                let weight = if specular_bounce {
+                    1.0
+                } else {
+                    Self::background_weight(scene, ray.direction, material_pdf)
+                };
+                let background = S::from_color(&scene.background.value(&ray), wavelengths.as_ref());
+                radiance = radiance + (throughput * background).scale(weight);
+                break;
+            }
+
+            let mut mat = scene.material(rec.mat);
+
+            let emitted = mat.emitted(rec.u, rec.v, rec.point);
+            if !is_black(&emitted) {
+                let weight = if specular_bounce {
+                    1.0
+                } else {
+                    Self::emission_weight(scene, previous_point, ray.direction, material_pdf)
+                };
+                radiance = radiance + (throughput * S::from_color(&emitted, wavelengths.as_ref())).scale(weight);
+            }
+
+            // Light leaves a subsurface object diffusely, from wherever its walk under the surface comes out
+            if let MaterialType::Subsurface(subsurface) = mat {
+                let Some((exit, weight)) = subsurface.walk(&rec, scene) else {
+                    break;
+                };
+                throughput = throughput * S::from_color(&weight, wavelengths.as_ref());
+                rec = exit;
+                mat = &exit_lobe;
+            }
+
+            if !mat.is_specular() {
+                if !scene.lights.objects.is_empty() {
+                    radiance = radiance + throughput * Self::sample_lights::<S>(&ray, &rec, mat, scene);
+                }
+                if !scene.punctual_lights.is_empty() {
+                    radiance = radiance + throughput * Self::sample_punctual_lights::<S>(&ray, &rec, mat, scene);
+                }
+                if let Background::Environment(map) = &scene.background {
+                    radiance = radiance + throughput * Self::sample_environment::<S>(&ray, &rec, mat, map, scene);
+                }
+            }
+
+            let mut scattered = Ray::default();
+            let mut attenuation = Color::default();
+            if !mat.scatter(&ray, &mut scattered, &rec, &mut attenuation) {
+                break;
+            }
+            // A ray split by a dispersive surface keeps its wavelengths through every later bounce
+            scattered.wavelengths = scattered.wavelengths.or(wavelengths);
+            wavelengths = scattered.wavelengths;
+
+            specular_bounce = mat.is_specular();
+            material_pdf = if specular_bounce { 0.0 } else { mat.pdf(&ray, &rec, &scattered.direction) };
+            throughput = throughput * S::from_color(&attenuation, wavelengths.as_ref());
+
+            // Russian roulette, dim paths are ended early and the survivors make up for them
+            if depth >= ROULETTE_DEPTH {
+                let survival = throughput.max_component().min(0.95);
+                if random_float() >= survival {
+                    break;
+                }
+                throughput = throughput.scale(1.0 / survival);
+            }
+
+            previous_point = rec.point;
+            ray = scattered;
+        }
+
+        (radiance, wavelengths)
+    }
+}
+
+impl Tracer for PathTracer{
+    fn trace_ray(&self, ray: &Ray, scene: &Scene) -> Color {
+        if !self.spectral {
+            return self.trace_path::<Color>(ray, scene).0;
+        }
+
+        // The film's estimate at the path's wavelengths is reduced to XYZ, then to sRGB
+        let camera_ray = Ray::with_wavelengths(ray.origin, ray.direction, Some(SampledWavelengths::sample()));
+        let (radiance, wavelengths) = self.trace_path::<SampledSpectrum>(&camera_ray, scene);
+        match wavelengths {
+            Some(wavelengths) => wavelengths.to_rgb(&radiance),
+            None => Color::default(),
+        }
+    }
+}
+
+/// Veach's power heuristic with an exponent of two, for one sample from each strategy.
+#[inline]
+pub(crate) fn power_heuristic(pdf : f32, other_pdf : f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b > 0.0 { a / (a + b) } else { 0.0 }
+}
+
+#[inline]
+fn is_black(color : &Color) -> bool {
+    color.r <= 0.0 && color.g <= 0.0 && color.b <= 0.0
+}
+
+#[cfg(test)]
+mod test_path_tracer {
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::background::Background;
+    use crate::engine::lighting::diffuse_lighting_model::diffuse_light::DiffuseLight;
+    use crate::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
+    use crate::engine::lighting::environment_map::EnvironmentMap;
+    use crate::engine::lighting::lights::point_light::PointLight;
+    use crate::engine::lighting::lights::Falloff;
+    use crate::engine::objects::object::HitList;
+    use crate::engine::objects::quad::Quad;
+    use crate::engine::objects::sphere::Sphere;
+    use crate::engine::objects::Objects::List;
+    use crate::engine::scene::Scene;
+    use crate::engine::tracers::path_tracer::PathTracer;
+    use crate::engine::tracers::recursive_tracer::RecursiveTracer;
+    use crate::engine::tracers::{Tracer, TracerTypes};
+    use crate::util::color::Color;
+
+    const ALBEDO: f32 = 0.5;
+    const EMISSION: f32 = 4.0;
+    const LIGHT_HEIGHT: f32 = 2.0;
+
+    /// A diffuse floor under a one by one square light, lit by nothing else.
+    fn floor_and_light() -> Scene {
+        let mut materials = MaterialArena::new();
+        let floor = materials.add(Lambertian::new(ALBEDO, ALBEDO, ALBEDO));
+        let light = materials.add(DiffuseLight::new(EMISSION, EMISSION, EMISSION));
+
+        let lamp = Quad::new(Point3::new(-0.5, LIGHT_HEIGHT, -0.5), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0), light);
+        let mut world = HitList::new();
+        world.add(Quad::new(Point3::new(-10.0, 0.0, -10.0), Vector3::new(20.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 20.0), floor));
+        world.add(lamp.clone());
+
+        let mut scene = Scene::new(List(world), materials);
+        scene.background = Background::black();
+        scene.lights.add(lamp);
+        scene
+    }
+
+    /// The radiance leaving the floor at `(x, z)`, integrating the light by the midpoint rule.
+    fn reference_radiance(x: f32, z: f32) -> f32 {
+        let steps = 400;
+        let cell = 1.0 / steps as f32;
+        let mut irradiance = 0.0;
+        for i in 0..steps {
+            for j in 0..steps {
+                let (lx, lz) = (-0.5 + (i as f32 + 0.5) * cell, -0.5 + (j as f32 + 0.5) * cell);
+                let d2 = (lx - x).powi(2) + (lz - z).powi(2) + LIGHT_HEIGHT * LIGHT_HEIGHT;
+                // Both cosines are height / distance
+                irradiance += EMISSION * LIGHT_HEIGHT * LIGHT_HEIGHT / (d2 * d2) * cell * cell;
+            }
+        }
+        ALBEDO / crate::engine::base::constants::constants::PI * irradiance
+    }
+
+    fn estimate(tracer: &TracerTypes, scene: &Scene, x: f32, z: f32, samples: usize) -> f32 {
+        let ray = Ray::new(Point3::new(x, 1.0, z), Vector3::new(0.0, -1.0, 0.0));
+        let total: f32 = (0..samples).map(|_| tracer.trace_ray(&ray, scene).g).sum();
+        total / samples as f32
+    }
+
+    #[test]
+    fn power_heuristic_weights_sum_to_one(){
+        let (a, b) = (0.3, 1.7);
+        let sum = super::power_heuristic(a, b) + super::power_heuristic(b, a);
+        assert!((sum - 1.0).abs() < 1e-6);
+        assert_eq!(super::power_heuristic(0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn direct_lighting_matches_reference_with_fewer_samples(){
+        let scene = floor_and_light();
+        let path = PathTracer::new(8);
+        let naive = RecursiveTracer::new(8);
+        let samples = 256;
+
+        let mut path_error = 0.0;
+        let mut naive_error = 0.0;
+        for (x, z) in [(0.0, 0.0), (0.8, 0.3), (-1.5, 1.0), (2.0, -2.0)] {
+            let reference = reference_radiance(x, z);
+            let path_relative = (estimate(&path, &scene, x, z, samples) - reference) / reference;
+            let naive_relative = (estimate(&naive, &scene, x, z, samples) - reference) / reference;
+
+            assert!(path_relative.abs() < 0.05, "path tracer is {path_relative} off at ({x}, {z})");
+            path_error += path_relative * path_relative;
+            naive_error += naive_relative * naive_relative;
+        }
+
+        assert!(naive_error > 9.0 * path_error, "naive error {naive_error}, path error {path_error}");
+    }
+
+    #[test]
+    fn spectral_mode_agrees_with_rgb(){
+        let scene = floor_and_light();
+        let spectral = PathTracer::spectral(8);
+        for (x, z) in [(0.0, 0.0), (1.5, -1.0)] {
+            let reference = reference_radiance(x, z);
+            let relative = (estimate(&spectral, &scene, x, z, 4096) - reference) / reference;
+            assert!(relative.abs() < 0.05, "spectral path tracer is {relative} off at ({x}, {z})");
+        }
+
+        // A colored wall stays its color once upsampled and reduced back to RGB
+        let mut materials = MaterialArena::new();
+        let red = materials.add(Lambertian::new(0.65, 0.05, 0.05));
+        let mut world = HitList::new();
+        world.add(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, red));
+        let mut scene = Scene::new(List(world), materials);
+        scene.background = EnvironmentMap::from_image(image::Rgb32FImage::from_pixel(16, 8, image::Rgb([1.0, 1.0, 1.0])), 0.0, 1.0);
+
+        let ray = Ray::new(Point3::new(0.3, 0.2, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let samples = 8000;
+        let mut mean = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            mean = mean + (1.0 / samples as f32) * spectral.trace_ray(&ray, &scene);
+        }
+        for (got, expected) in [(mean.r, 0.65), (mean.g, 0.05), (mean.b, 0.05)] {
+            assert!((got - expected).abs() < 0.03, "red sphere reflects {mean:?}");
+        }
+    }
+
+    #[test]
+    fn indirect_lighting_agrees_with_naive_tracer(){
+        // A wall next to a large light bounces extra light onto the floor
+        let mut materials = MaterialArena::new();
+        let white = materials.add(Lambertian::new(ALBEDO, ALBEDO, ALBEDO));
+        let light = materials.add(DiffuseLight::new(1.0, 1.0, 1.0));
+
+        let lamp = Quad::new(Point3::new(-2.0, 1.0, -2.0), Vector3::new(4.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 4.0), light);
+        let mut world = HitList::new();
+        world.add(Quad::new(Point3::new(-2.0, 0.0, -2.0), Vector3::new(4.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 4.0), white));
+        world.add(Quad::new(Point3::new(-2.0, 0.0, -2.0), Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 4.0), white));
+        world.add(lamp.clone());
+
+        let mut scene = Scene::new(List(world), materials);
+        scene.background = Background::black();
+        scene.lights.add(lamp);
+
+        let ray = Ray::new(Point3::new(-1.5, 0.5, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        let mean = |tracer: TracerTypes, samples: usize| {
+            (0..samples).map(|_| tracer.trace_ray(&ray, &scene).g).sum::<f32>() / samples as f32
+        };
+
+        let naive = mean(RecursiveTracer::new(50), 40_000);
+        let path = mean(PathTracer::new(50), 4_000);
+        assert!(((path - naive) / naive).abs() < 0.03, "path tracer {path}, naive tracer {naive}");
+    }
+
+    #[test]
+    fn point_light_is_shadowed_by_occluders(){
+        let mut materials = MaterialArena::new();
+        let floor = materials.add(Lambertian::new(ALBEDO, ALBEDO, ALBEDO));
+        let mut world = HitList::new();
+        world.add(Quad::new(Point3::new(-10.0, 0.0, -10.0), Vector3::new(20.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 20.0), floor));
+        // A blocker hanging between the light and the floor under x < 0
+        world.add(Quad::new(Point3::new(-10.0, 1.0, -10.0), Vector3::new(10.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 20.0), floor));
+
+        let mut scene = Scene::new(List(world), materials);
+        scene.background = Background::black();
+        scene.punctual_lights.push(PointLight::new(Point3::new(0.0, 2.0, 0.0), Color::new(EMISSION, EMISSION, EMISSION), Falloff::InverseSquare));
+
+        // Direct light only, so one sample is exact
+        let path = PathTracer::new(1);
+        let (x, z) = (1.0, 0.0);
+        let lit = estimate(&path, &scene, x, z, 1);
+        let d2: f32 = x * x + z * z + LIGHT_HEIGHT * LIGHT_HEIGHT;
+        let cos = LIGHT_HEIGHT / d2.sqrt();
+        let expected = ALBEDO / crate::engine::base::constants::constants::PI * EMISSION * cos / d2;
+        assert!((lit - expected).abs() < 1e-4, "lit floor is {lit}, expected {expected}");
+
+        let ray = Ray::new(Point3::new(-1.0, 0.5, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        assert_eq!(path.trace_ray(&ray, &scene).g, 0.0);
+    }
+
+    #[test]
+    fn white_furnace_sphere_reflects_its_albedo(){
+        // Under uniform light a convex diffuse object reflects exactly its albedo, whatever the MIS weights
+        let mut materials = MaterialArena::new();
+        let grey = materials.add(Lambertian::new(ALBEDO, ALBEDO, ALBEDO));
+        let mut world = HitList::new();
+        world.add(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, grey));
+
+        let mut scene = Scene::new(List(world), materials);
+        scene.background = EnvironmentMap::from_image(image::Rgb32FImage::from_pixel(16, 8, image::Rgb([1.0, 1.0, 1.0])), 0.0, 1.0);
+
+        let ray = Ray::new(Point3::new(0.3, 0.2, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let samples = 4000;
+        let mean = (0..samples).map(|_| PathTracer::new(8).trace_ray(&ray, &scene).g).sum::<f32>() / samples as f32;
+        assert!((mean - ALBEDO).abs() < 0.01, "furnace sphere reflects {mean}");
+    }
+
+    #[test]
+    fn environment_lighting_agrees_with_naive_tracer(){
+        let mut image = image::Rgb32FImage::from_pixel(32, 16, image::Rgb([0.2, 0.2, 0.2]));
+        for (x, y) in [(12, 3), (13, 3), (12, 4), (13, 4)] {
+            image.put_pixel(x, y, image::Rgb([30.0, 30.0, 30.0]));
+        }
+
+        let mut materials = MaterialArena::new();
+        let floor = materials.add(Lambertian::new(ALBEDO, ALBEDO, ALBEDO));
+        let mut world = HitList::new();
+        world.add(Quad::new(Point3::new(-10.0, 0.0, -10.0), Vector3::new(20.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 20.0), floor));
+
+        let mut scene = Scene::new(List(world), materials);
+        scene.background = EnvironmentMap::from_image(image, 40.0, 1.0);
+
+        let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        let mean = |tracer: TracerTypes, samples: usize| {
+            (0..samples).map(|_| tracer.trace_ray(&ray, &scene).g).sum::<f32>() / samples as f32
+        };
+
+        let naive = mean(RecursiveTracer::new(4), 200_000);
+        let path = mean(PathTracer::new(4), 4_000);
+        assert!(((path - naive) / naive).abs() < 0.03, "path tracer {path}, naive tracer {naive}");
+    }
+}
@@ -0,0 +1,100 @@
+use crate::engine::base::constants::constants;
+use crate::engine::base::interval::Interval;
+use crate::engine::base::ray::Ray;
+use crate::engine::objects::hit_record::HitRecord;
+use crate::engine::scene::Scene;
+use crate::engine::tracers::{Tracer, TracerTypes};
+use crate::util::color::Color;
+
+/// Follows the single ray each material scatters until it escapes or runs out of bounces.
+#[derive(Clone)]
+pub struct RecursiveTracer{
+    /// Maximum number of ray bounces into the scene.
+    pub(crate) max_depth : i32,
+}
+
+impl RecursiveTracer{
+    pub fn new(max_depth : i32) -> TracerTypes {
+        TracerTypes::Recursive(Self { max_depth })
+    }
+
+    /// Computes the color of a ray based on its interaction with the scene.
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - The ray to be traced.
+    /// * `scene` - The objects to be hit by the ray, their materials and the background.
+    /// * `depth` - The number of bounces left.
+    ///
+    /// # Returns
+    ///
+    /// A `Color` representing the color of the ray.
+    fn ray_color(&self, ray: &Ray, scene: &Scene, depth : i32) -> Color {
+        if depth <= 0 {
+            return Color::default();
+        }
+
+        let mut rec = HitRecord::default();
+        // The interval is used to avoid floating point approximation
+        if scene.hit(ray, &mut Interval::new(0.0001f32, constants::INFINITY), &mut rec) {
+            let mut scatter_ray = Ray::default();
+            let mut attenuation = Color::default();
+            let mat = scene.material(rec.mat);
+            let emitted = mat.emitted(rec.u, rec.v, rec.point);
+
+            if mat.scatter(ray, &mut scatter_ray, &rec, &mut attenuation) {
+                // A ray split by a dispersive surface keeps its wavelengths through every later bounce
+                scatter_ray.wavelengths = scatter_ray.wavelengths.or(ray.wavelengths);
+                return emitted + attenuation * self.ray_color(&scatter_ray, scene, depth - 1);
+            }
+
+            return emitted
+        }
+
+        scene.background.value(ray)
+    }
+}
+
+impl Tracer for RecursiveTracer{
+    fn trace_ray(&self, ray: &Ray, scene: &Scene) -> Color {
+        self.ray_color(ray, scene, self.max_depth)
+    }
+}
+
+#[cfg(test)]
+mod test_recursive_tracer {
+    use crate::engine::base::point::Point3;
+    use crate::engine::base::ray::Ray;
+    use crate::engine::base::vector::Vector3;
+    use crate::engine::lighting::background::Background;
+    use crate::engine::lighting::diffuse_lighting_model::diffuse_light::DiffuseLight;
+    use crate::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
+    use crate::engine::objects::object::HitList;
+    use crate::engine::objects::sphere::Sphere;
+    use crate::engine::objects::Objects::List;
+    use crate::engine::scene::Scene;
+    use crate::engine::tracers::recursive_tracer::RecursiveTracer;
+    use crate::engine::tracers::Tracer;
+    use crate::util::color::Color;
+
+    #[test]
+    fn misses_return_the_background(){
+        let mut scene = Scene::new(List(HitList::new()), MaterialArena::new());
+        scene.background = Background::Solid(Color::new(0.25, 0.5, 0.75));
+
+        let color = RecursiveTracer::new(5).trace_ray(&Ray::new(Point3::default(), Vector3::new(0.0, 0.0, -1.0)), &scene);
+        assert_eq!((color.r, color.g, color.b), (0.25, 0.5, 0.75));
+    }
+
+    #[test]
+    fn lights_return_their_emission(){
+        let mut materials = MaterialArena::new();
+        let light = materials.add(DiffuseLight::new(4.0, 2.0, 1.0));
+        let mut world = HitList::new();
+        world.add(Sphere::new(Point3::new(0.0, 0.0, -3.0), 1.0, light));
+
+        let scene = Scene::new(List(world), materials);
+        let color = RecursiveTracer::new(5).trace_ray(&Ray::new(Point3::default(), Vector3::new(0.0, 0.0, -1.0)), &scene);
+        assert_eq!((color.r, color.g, color.b), (4.0, 2.0, 1.0));
+    }
+}
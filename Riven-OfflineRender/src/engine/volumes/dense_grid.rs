@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use crate::engine::base::point::Point3;
+use crate::engine::volumes::{GridType, VoxelGrid};
+
+/// A grid storing every voxel, clones share the values.
+#[derive(Clone)]
+pub struct DenseGrid {
+    /// Number of voxels along x, y and z.
+    resolution: [usize; 3],
+    /// Voxel values with x varying fastest, then y, then z.
+    values: Arc<Vec<f32>>,
+    max_value: f32,
+}
+
+impl DenseGrid {
+    /// Creates a new `DenseGrid`.
+    ///
+    /// # Arguments
+    ///
+    /// * `nx`, `ny`, `nz` - Number of voxels along each axis, none of them can be zero.
+    /// * `values` - `nx * ny * nz` voxel values, x varying fastest, then y, then z.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `GridType::Dense`.
+    pub fn new(nx: usize, ny: usize, nz: usize, values: Vec<f32>) -> GridType {
+        assert!(nx > 0 && ny > 0 && nz > 0, "A grid needs at least one voxel");
+        assert_eq!(values.len(), nx * ny * nz, "The grid must have nx * ny * nz values");
+
+        let max_value = values.iter().cloned().fold(0.0, f32::max);
+        GridType::Dense(Self { resolution: [nx, ny, nz], values: Arc::new(values), max_value })
+    }
+
+    /// Creates a grid by evaluating a function at the center of every voxel.
+    ///
+    /// # Arguments
+    ///
+    /// * `nx`, `ny`, `nz` - Number of voxels along each axis.
+    /// * `f` - The function, called with voxel centers in grid space.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `GridType::Dense`.
+    pub fn from_fn(nx: usize, ny: usize, nz: usize, f: impl Fn(Point3) -> f32) -> GridType {
+        let mut values = Vec::with_capacity(nx * ny * nz);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    values.push(f(Point3::new(
+                        (x as f32 + 0.5) / nx as f32,
+                        (y as f32 + 0.5) / ny as f32,
+                        (z as f32 + 0.5) / nz as f32,
+                    )));
+                }
+            }
+        }
+        Self::new(nx, ny, nz, values)
+    }
+
+    #[inline]
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f32 {
+        let [nx, ny, _] = self.resolution;
+        self.values[(z * ny + y) * nx + x]
+    }
+}
+
+impl VoxelGrid for DenseGrid {
+    /// Trilinear interpolation between the centers of the eight closest voxels.
+    fn value(&self, point: Point3) -> f32 {
+        if !(0.0..=1.0).contains(&point.x) || !(0.0..=1.0).contains(&point.y) || !(0.0..=1.0).contains(&point.z) {
+            return 0.0;
+        }
+
+        // Voxel centers sit at half integers, edges clamp to the outermost voxels
+        let coordinate = |p: f32, n: usize| {
+            let s = (p * n as f32 - 0.5).clamp(0.0, (n - 1) as f32);
+            let i = (s as usize).min(n.saturating_sub(2));
+            (i, (i + 1).min(n - 1), s - i as f32)
+        };
+        let [nx, ny, nz] = self.resolution;
+        let (x0, x1, fx) = coordinate(point.x, nx);
+        let (y0, y1, fy) = coordinate(point.y, ny);
+        let (z0, z1, fz) = coordinate(point.z, nz);
+
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let plane = |z: usize| lerp(
+            lerp(self.voxel(x0, y0, z), self.voxel(x1, y0, z), fx),
+            lerp(self.voxel(x0, y1, z), self.voxel(x1, y1, z), fx),
+            fy,
+        );
+        lerp(plane(z0), plane(z1), fz)
+    }
+
+    fn max_value(&self) -> f32 {
+        self.max_value
+    }
+}
+
+#[cfg(test)]
+mod test_dense_grid {
+    use crate::engine::base::point::Point3;
+    use crate::engine::volumes::dense_grid::DenseGrid;
+    use crate::engine::volumes::VoxelGrid;
+
+    #[test]
+    fn grid_interpolates_between_voxel_centers(){
+        let grid = DenseGrid::from_fn(4, 2, 1, |p| p.x * 8.0);
+
+        // Voxel centers along x hold 1, 3, 5 and 7
+        assert!((grid.value(Point3::new(0.125, 0.5, 0.5)) - 1.0).abs() < 1e-5);
+        assert!((grid.value(Point3::new(0.5, 0.3, 0.5)) - 4.0).abs() < 1e-5);
+        assert!((grid.value(Point3::new(1.0, 0.5, 0.5)) - 7.0).abs() < 1e-5);
+        assert_eq!(grid.value(Point3::new(1.1, 0.5, 0.5)), 0.0);
+        assert_eq!(grid.max_value(), 7.0);
+    }
+}
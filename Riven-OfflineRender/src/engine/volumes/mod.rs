@@ -0,0 +1,44 @@
+use crate::engine::base::point::Point3;
+use crate::engine::volumes::dense_grid::DenseGrid;
+use crate::engine::volumes::GridType::Dense;
+
+pub mod dense_grid;
+
+/// Scalar values, like density or temperature, stored on a 3D grid of voxels.
+pub trait VoxelGrid {
+    /// Looks up the grid's value at a point.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The point in grid space, the grid spans `[0, 1]` on every axis.
+    ///
+    /// # Returns
+    ///
+    /// The interpolated value, zero outside the grid.
+    fn value(&self, point: Point3) -> f32;
+
+    /// The largest value anywhere in the grid, the majorant used to track rays through it.
+    fn max_value(&self) -> f32;
+}
+
+/// The grid layouts a volume can be stored in.
+///
+/// Only dense grids exist for now, sparse grids in the spirit of OpenVDB would be another variant.
+#[derive(Clone)]
+pub enum GridType {
+    Dense(DenseGrid),
+}
+
+impl VoxelGrid for GridType {
+    fn value(&self, point: Point3) -> f32 {
+        match self {
+            Dense(grid) => grid.value(point),
+        }
+    }
+
+    fn max_value(&self) -> f32 {
+        match self {
+            Dense(grid) => grid.max_value(),
+        }
+    }
+}
@@ -1,21 +1,41 @@
+use std::sync::Arc;
 use Riven_OfflineRender::engine::base::constants::constants::{random_float, ranged_random_float};
 use Riven_OfflineRender::engine::base::point::Point3;
+use Riven_OfflineRender::engine::base::transform::Transform;
 use Riven_OfflineRender::engine::base::vector::Vector3;
-use Riven_OfflineRender::engine::bounding_model::bvh::BvhNode;
+use Riven_OfflineRender::engine::bounding_model::bvh::Bvh;
 use Riven_OfflineRender::engine::camera::rgb_camera::RGBCamera;
+use Riven_OfflineRender::engine::lighting::background::Background;
 use Riven_OfflineRender::engine::lighting::diffuse_lighting_model::dielectric::Dielectric;
+use Riven_OfflineRender::engine::lighting::diffuse_lighting_model::diffuse_light::DiffuseLight;
+use Riven_OfflineRender::engine::lighting::diffuse_lighting_model::henyey_greenstein::HenyeyGreenstein;
+use Riven_OfflineRender::engine::lighting::diffuse_lighting_model::isotropic::Isotropic;
 use Riven_OfflineRender::engine::lighting::diffuse_lighting_model::lambertian::Lambertian;
+use Riven_OfflineRender::engine::lighting::diffuse_lighting_model::material_arena::MaterialArena;
 use Riven_OfflineRender::engine::lighting::diffuse_lighting_model::metal::Metal;
+use Riven_OfflineRender::engine::lighting::lights::directional_light::DirectionalLight;
+use Riven_OfflineRender::engine::lighting::lights::point_light::PointLight;
+use Riven_OfflineRender::engine::lighting::lights::spot_light::SpotLight;
+use Riven_OfflineRender::engine::lighting::lights::Falloff;
+use Riven_OfflineRender::engine::lighting::sky::PreethamSky;
+use Riven_OfflineRender::engine::objects::constant_medium::ConstantMedium;
+use Riven_OfflineRender::engine::objects::grid_medium::GridMedium;
 use Riven_OfflineRender::engine::objects::object::HitList;
 use Riven_OfflineRender::engine::objects::Objects::{List, Spheres};
+use Riven_OfflineRender::engine::objects::instance::Instance;
+use Riven_OfflineRender::engine::objects::quad::Quad;
 use Riven_OfflineRender::engine::textures::{ TextureType};
 use Riven_OfflineRender::engine::objects::sphere::Sphere;
+use Riven_OfflineRender::engine::scene::Scene;
+use Riven_OfflineRender::engine::tracers::recursive_tracer::RecursiveTracer;
+use Riven_OfflineRender::engine::tracers::path_tracer::PathTracer;
 use Riven_OfflineRender::engine::textures::chess_board_texture::ChessBoardTexture;
 use Riven_OfflineRender::engine::textures::image_texture::{im_texture, ImageTexture};
 use Riven_OfflineRender::engine::textures::noise_texture::NoiseTexture;
 use Riven_OfflineRender::engine::textures::solid_color::SolidColor;
 use Riven_OfflineRender::util::color::Color;
 use Riven_OfflineRender::util::image::Canvas;
+use Riven_OfflineRender::engine::volumes::dense_grid::DenseGrid;
 //TODO: Multi-threading                []
 //TODO: Bounding Volume Hierarchy      []
 //TODO: Texture Mapping                []
@@ -34,13 +54,14 @@ fn noise_sphere(){
 
     // world
     let mut world = HitList::new();
+    let mut materials = MaterialArena::new();
     let per_text = NoiseTexture::new(4f32);
 
-    let mat1 = Lambertian::from_texture(per_text);
+    let mat1 = materials.add(Lambertian::from_texture(per_text));
     let sphere1 = Sphere::new(
         Point3::new(0.0, -1000.0, 0.0),
         1000.0,
-        mat1.clone()
+        mat1
     );
 
     let sphere2 = Sphere::new(
@@ -56,7 +77,7 @@ fn noise_sphere(){
     cam.aspect_ratio = 16.0 / 9.0;
     cam.image_width = 1200;
     cam.samples_per_pixel = 10;
-    cam.max_depth = 5;
+    cam.tracer = RecursiveTracer::new(5);
 
     cam.vfov = 20.0;
     cam.look_from = Point3::new(13.0, 2.0, 3.0);
@@ -66,10 +87,287 @@ fn noise_sphere(){
     cam.defocus_angle = 0.1;
     cam.focus_dist = 10.0;
 
-    cam.render(&List(world), canvas);
+    cam.render(&Scene::new(List(world), materials), canvas);
+
+}
+
+fn simple_light(){
+    let image_width = 1200;
+    let canvas = Canvas::new(image_width);
+
+    // world
+    let mut world = HitList::new();
+    let mut materials = MaterialArena::new();
+    let per_text = NoiseTexture::new(4f32);
+
+    let ground = materials.add(Lambertian::from_texture(per_text));
+    world.add(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground));
+    world.add(Sphere::new(Point3::new(0.0, 2.0, 0.0), 2.0, ground));
+
+    let light = materials.add(DiffuseLight::new(4.0, 4.0, 4.0));
+    world.add(Sphere::new(Point3::new(0.0, 7.0, 0.0), 2.0, light));
+
+    let mut cam = RGBCamera::default();
+    cam.aspect_ratio = 16.0 / 9.0;
+    cam.image_width = 1200;
+    cam.samples_per_pixel = 100;
+    cam.tracer = RecursiveTracer::new(50);
+
+    cam.vfov = 20.0;
+    cam.look_from = Point3::new(26.0, 3.0, 6.0);
+    cam.look_at = Point3::new(0.0, 2.0, 0.0);
+    cam.vup = Vector3::new(0.0, 1.0, 0.0);
+
+    cam.defocus_angle = 0.0;
+    cam.focus_dist = 10.0;
+
+    let mut scene = Scene::new(List(world), materials);
+    scene.background = Background::black();
+
+    cam.render(&scene, canvas);
+}
+
+fn punctual_lights(){
+    let image_width = 1200;
+    let canvas = Canvas::new(image_width);
+
+    // world
+    let mut world = HitList::new();
+    let mut materials = MaterialArena::new();
+
+    let ground = materials.add(Lambertian::new(0.8, 0.8, 0.8));
+    let red = materials.add(Lambertian::new(0.7, 0.2, 0.2));
+    let metal = materials.add(Metal::new(0.8, 0.8, 0.8, 0.1));
+    world.add(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground));
+    world.add(Sphere::new(Point3::new(-2.5, 1.0, 0.0), 1.0, red));
+    world.add(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, ground));
+    world.add(Sphere::new(Point3::new(2.5, 1.0, 0.0), 1.0, metal));
+
+    let mut cam = RGBCamera::default();
+    cam.aspect_ratio = 16.0 / 9.0;
+    cam.image_width = 1200;
+    cam.samples_per_pixel = 100;
+    cam.tracer = PathTracer::new(50);
+
+    cam.vfov = 30.0;
+    cam.look_from = Point3::new(0.0, 4.0, 12.0);
+    cam.look_at = Point3::new(0.0, 1.0, 0.0);
+    cam.vup = Vector3::new(0.0, 1.0, 0.0);
+
+    cam.defocus_angle = 0.0;
+    cam.focus_dist = 10.0;
+
+    let mut scene = Scene::new(List(world), materials);
+    scene.background = Background::black();
+    scene.punctual_lights.push(PointLight::new(Point3::new(-4.0, 4.0, 3.0), Color::new(20.0, 16.0, 12.0), Falloff::Windowed { range: 15.0 }));
+    scene.punctual_lights.push(SpotLight::new(Point3::new(3.0, 6.0, 2.0), Point3::new(2.5, 0.0, 0.0), Color::new(40.0, 40.0, 50.0), 15.0, 25.0, Falloff::InverseSquare));
+    scene.punctual_lights.push(DirectionalLight::new(Vector3::new(1.0, -2.0, -1.0), Color::new(0.3, 0.3, 0.35), 0.53));
+
+    cam.render(&scene, canvas);
+}
+
+fn daylight(){
+    let image_width = 1200;
+    let canvas = Canvas::new(image_width);
+
+    // world
+    let mut world = HitList::new();
+    let mut materials = MaterialArena::new();
+
+    let ground = materials.add(Lambertian::new(0.5, 0.5, 0.5));
+    let white = materials.add(Lambertian::new(0.8, 0.8, 0.8));
+    let glass = materials.add(Dielectric::new(1.5));
+    world.add(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground));
+    world.add(Quad::make_box(Point3::new(-3.0, 0.0, -2.0), Point3::new(-1.0, 3.0, 0.0), white));
+    world.add(Sphere::new(Point3::new(1.0, 1.0, 0.5), 1.0, glass));
+
+    let mut cam = RGBCamera::default();
+    cam.aspect_ratio = 16.0 / 9.0;
+    cam.image_width = 1200;
+    cam.samples_per_pixel = 100;
+    cam.tracer = PathTracer::new(50);
+
+    cam.vfov = 35.0;
+    cam.look_from = Point3::new(4.0, 3.0, 10.0);
+    cam.look_at = Point3::new(0.0, 1.0, 0.0);
+    cam.vup = Vector3::new(0.0, 1.0, 0.0);
+
+    cam.defocus_angle = 0.0;
+    cam.focus_dist = 10.0;
+
+    // Afternoon sun from the right, the sky's kcd/m² scaled down to display values
+    let sky = PreethamSky::new(35.0, 60.0, 3.0, Color::new(0.5, 0.5, 0.5));
+    let mut scene = Scene::new(Bvh::from_world(world), materials);
+    scene.background = sky.background(0.05);
+    scene.punctual_lights.push(sky.sun(0.05));
 
+    cam.render(&scene, canvas);
 }
 
+fn cornell_box(){
+    let image_width = 600;
+    let canvas = Canvas::with_size(image_width, image_width);
+
+    // world
+    let mut world = HitList::new();
+    let mut materials = MaterialArena::new();
+
+    let red = materials.add(Lambertian::new(0.65, 0.05, 0.05));
+    let white = materials.add(Lambertian::new(0.73, 0.73, 0.73));
+    let green = materials.add(Lambertian::new(0.12, 0.45, 0.15));
+    let light = materials.add(DiffuseLight::new(15.0, 15.0, 15.0));
+
+    world.add(Quad::new(Point3::new(555.0, 0.0, 0.0), Vector3::new(0.0, 555.0, 0.0), Vector3::new(0.0, 0.0, 555.0), green));
+    world.add(Quad::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 555.0, 0.0), Vector3::new(0.0, 0.0, 555.0), red));
+    let lamp = Quad::new(Point3::new(343.0, 554.0, 332.0), Vector3::new(-130.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -105.0), light);
+    world.add(lamp.clone());
+    world.add(Quad::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(555.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 555.0), white));
+    world.add(Quad::new(Point3::new(555.0, 555.0, 555.0), Vector3::new(-555.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -555.0), white));
+    world.add(Quad::new(Point3::new(0.0, 0.0, 555.0), Vector3::new(555.0, 0.0, 0.0), Vector3::new(0.0, 555.0, 0.0), white));
+
+    let tall_box = Arc::new(Quad::make_box(Point3::new(0.0, 0.0, 0.0), Point3::new(165.0, 330.0, 165.0), white));
+    world.add(Instance::new(
+        tall_box,
+        Transform::translate(Vector3::new(265.0, 0.0, 295.0)) * Transform::rotate_y(15.0)
+    ));
+
+    let short_box = Arc::new(Quad::make_box(Point3::new(0.0, 0.0, 0.0), Point3::new(165.0, 165.0, 165.0), white));
+    world.add(Instance::new(
+        short_box,
+        Transform::translate(Vector3::new(130.0, 0.0, 65.0)) * Transform::rotate_y(-18.0)
+    ));
+
+    let mut cam = RGBCamera::default();
+    cam.aspect_ratio = 1.0;
+    cam.image_width = 600;
+    cam.samples_per_pixel = 200;
+    cam.tracer = PathTracer::new(50);
+
+    cam.vfov = 40.0;
+    cam.look_from = Point3::new(278.0, 278.0, -800.0);
+    cam.look_at = Point3::new(278.0, 278.0, 0.0);
+    cam.vup = Vector3::new(0.0, 1.0, 0.0);
+
+    cam.defocus_angle = 0.0;
+    cam.focus_dist = 10.0;
+
+    let mut scene = Scene::new(Bvh::from_world(world), materials);
+    scene.background = Background::black();
+    scene.lights.add(lamp);
+
+    cam.render(&scene, canvas);
+}
+
+fn cornell_smoke(){
+    let image_width = 600;
+    let canvas = Canvas::with_size(image_width, image_width);
+
+    // world
+    let mut world = HitList::new();
+    let mut materials = MaterialArena::new();
+
+    let red = materials.add(Lambertian::new(0.65, 0.05, 0.05));
+    let white = materials.add(Lambertian::new(0.73, 0.73, 0.73));
+    let green = materials.add(Lambertian::new(0.12, 0.45, 0.15));
+    let light = materials.add(DiffuseLight::new(15.0, 15.0, 15.0));
+    let smoke = materials.add(Isotropic::new(0.0, 0.0, 0.0));
+    let fog = materials.add(Isotropic::new(1.0, 1.0, 1.0));
+
+    world.add(Quad::new(Point3::new(555.0, 0.0, 0.0), Vector3::new(0.0, 555.0, 0.0), Vector3::new(0.0, 0.0, 555.0), green));
+    world.add(Quad::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 555.0, 0.0), Vector3::new(0.0, 0.0, 555.0), red));
+    let lamp = Quad::new(Point3::new(343.0, 554.0, 332.0), Vector3::new(-130.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -105.0), light);
+    world.add(lamp.clone());
+    world.add(Quad::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(555.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 555.0), white));
+    world.add(Quad::new(Point3::new(555.0, 555.0, 555.0), Vector3::new(-555.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -555.0), white));
+    world.add(Quad::new(Point3::new(0.0, 0.0, 555.0), Vector3::new(555.0, 0.0, 0.0), Vector3::new(0.0, 555.0, 0.0), white));
+
+    let tall_box = Arc::new(Quad::make_box(Point3::new(0.0, 0.0, 0.0), Point3::new(165.0, 330.0, 165.0), white));
+    let tall_box = Instance::new(
+        tall_box,
+        Transform::translate(Vector3::new(265.0, 0.0, 295.0)) * Transform::rotate_y(15.0)
+    );
+    world.add(ConstantMedium::new(tall_box, 0.01, smoke));
+
+    let short_box = Arc::new(Quad::make_box(Point3::new(0.0, 0.0, 0.0), Point3::new(165.0, 165.0, 165.0), white));
+    let short_box = Instance::new(
+        short_box,
+        Transform::translate(Vector3::new(130.0, 0.0, 65.0)) * Transform::rotate_y(-18.0)
+    );
+    world.add(ConstantMedium::new(short_box, 0.01, fog));
+
+    let mut cam = RGBCamera::default();
+    cam.aspect_ratio = 1.0;
+    cam.image_width = 600;
+    cam.samples_per_pixel = 200;
+    cam.tracer = PathTracer::new(50);
+
+    cam.vfov = 40.0;
+    cam.look_from = Point3::new(278.0, 278.0, -800.0);
+    cam.look_at = Point3::new(278.0, 278.0, 0.0);
+    cam.vup = Vector3::new(0.0, 1.0, 0.0);
+
+    cam.defocus_angle = 0.0;
+    cam.focus_dist = 10.0;
+
+    let mut scene = Scene::new(Bvh::from_world(world), materials);
+    scene.background = Background::black();
+    scene.lights.add(lamp);
+
+    cam.render(&scene, canvas);
+}
+
+fn cornell_cloud(){
+    let image_width = 600;
+    let canvas = Canvas::with_size(image_width, image_width);
+
+    // world
+    let mut world = HitList::new();
+    let mut materials = MaterialArena::new();
+
+    let red = materials.add(Lambertian::new(0.65, 0.05, 0.05));
+    let white = materials.add(Lambertian::new(0.73, 0.73, 0.73));
+    let green = materials.add(Lambertian::new(0.12, 0.45, 0.15));
+    let light = materials.add(DiffuseLight::new(15.0, 15.0, 15.0));
+    let cloud = materials.add(HenyeyGreenstein::new(0.9, 0.9, 0.9, 0.6));
+
+    world.add(Quad::new(Point3::new(555.0, 0.0, 0.0), Vector3::new(0.0, 555.0, 0.0), Vector3::new(0.0, 0.0, 555.0), green));
+    world.add(Quad::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 555.0, 0.0), Vector3::new(0.0, 0.0, 555.0), red));
+    let lamp = Quad::new(Point3::new(343.0, 554.0, 332.0), Vector3::new(-130.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -105.0), light);
+    world.add(lamp.clone());
+    world.add(Quad::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(555.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 555.0), white));
+    world.add(Quad::new(Point3::new(555.0, 555.0, 555.0), Vector3::new(-555.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -555.0), white));
+    world.add(Quad::new(Point3::new(0.0, 0.0, 555.0), Vector3::new(555.0, 0.0, 0.0), Vector3::new(0.0, 555.0, 0.0), white));
+
+    // A lumpy blob, dense in the middle and fading out towards the grid's edges
+    let density = DenseGrid::from_fn(64, 64, 64, |p| {
+        let (x, y, z) = (p[0] - 0.5, p[1] - 0.5, p[2] - 0.5);
+        let radius = (x * x + y * y + z * z).sqrt();
+        let lumps = 0.08 * ((x * 17.0).sin() * (y * 13.0).sin() * (z * 11.0).sin());
+        (1.0 - (radius + lumps) / 0.45).clamp(0.0, 1.0)
+    });
+    let bounds = Quad::make_box(Point3::new(128.0, 100.0, 128.0), Point3::new(428.0, 400.0, 428.0), white);
+    world.add(GridMedium::new(bounds, density, 0.05, cloud));
+
+    let mut cam = RGBCamera::default();
+    cam.aspect_ratio = 1.0;
+    cam.image_width = 600;
+    cam.samples_per_pixel = 200;
+    cam.tracer = PathTracer::new(50);
+
+    cam.vfov = 40.0;
+    cam.look_from = Point3::new(278.0, 278.0, -800.0);
+    cam.look_at = Point3::new(278.0, 278.0, 0.0);
+    cam.vup = Vector3::new(0.0, 1.0, 0.0);
+
+    cam.defocus_angle = 0.0;
+    cam.focus_dist = 10.0;
+
+    let mut scene = Scene::new(Bvh::from_world(world), materials);
+    scene.background = Background::black();
+    scene.lights.add(lamp);
+
+    cam.render(&scene, canvas);
+}
 
 fn bouncing_spheres(){
     let image_width = 1200;
@@ -77,13 +375,14 @@ fn bouncing_spheres(){
 
     // world
     let mut world = HitList::new();
+    let mut materials = MaterialArena::new();
 
     let texture = ChessBoardTexture::new(
         0.32,
         SolidColor::from_rgb(0.2, 0.3, 0.1),
         SolidColor::from_rgb(0.9, 0.9, 0.9)
     );
-    let ground_mat = Lambertian::from_texture(texture);
+    let ground_mat = materials.add(Lambertian::from_texture(texture));
     world.add(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_mat));
 
     for i in -11..11{
@@ -95,42 +394,42 @@ fn bouncing_spheres(){
                 if chose_mat < 0.8{
                     // diffuse
                     let albedo = Color::random() * Color::random();
-                    let sphere_material = Lambertian::new(albedo.r, albedo.g, albedo.b);
+                    let sphere_material = materials.add(Lambertian::new(albedo.r, albedo.g, albedo.b));
                     world.add(Sphere::new(center, 0.2, sphere_material));
                 }
                 else if chose_mat < 0.95{
                     // metal
                     let albedo = Color::random();
                     let fuzz = ranged_random_float(0.0, 0.5);
-                    let sphere_material = Metal::new(albedo.r , albedo.g, albedo.b, fuzz);
+                    let sphere_material = materials.add(Metal::new(albedo.r , albedo.g, albedo.b, fuzz));
                     world.add(Sphere::new(center, 0.2, sphere_material));
                 }
                 else{
                     // glass
-                    let sphere_material = Dielectric::new(1.5);
+                    let sphere_material = materials.add(Dielectric::new(1.5));
                     world.add(Sphere::new(center, 0.2, sphere_material));
                 }
             }
         }
     }
 
-    let mat1 = Dielectric::new(1.5);
+    let mat1 = materials.add(Dielectric::new(1.5));
     world.add(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, mat1));
 
 
-    let mat2 = Lambertian::from_texture(
+    let mat2 = materials.add(Lambertian::from_texture(
         ImageTexture::new(im_texture::NIGHT_EARTH)
-    );
+    ));
     world.add(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, mat2));
 
-    let mat3 = Metal::new(0.7, 0.6, 0.5, 0.0);
+    let mat3 = materials.add(Metal::new(0.7, 0.6, 0.5, 0.0));
     world.add(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, mat3));
 
     let mut cam = RGBCamera::default();
     cam.aspect_ratio = 16.0 / 9.0;
     cam.image_width = 1200;
     cam.samples_per_pixel = 10;
-    cam.max_depth = 5;
+    cam.tracer = RecursiveTracer::new(5);
 
     cam.vfov = 20.0;
     cam.look_from = Point3::new(13.0, 2.0, 3.0);
@@ -140,9 +439,9 @@ fn bouncing_spheres(){
     cam.defocus_angle = 0.1;
     cam.focus_dist = 10.0;
 
-    let enhanced_world = BvhNode::from_world(world);
-    //cam.render(&List(world), canvas);
-    cam.render(&enhanced_world, canvas)
+    let enhanced_world = Bvh::from_world(world);
+    //cam.render(&Scene::new(List(world), materials), canvas);
+    cam.render(&Scene::new(enhanced_world, materials), canvas)
 }
 
 fn three_spheres() {
@@ -151,6 +450,7 @@ fn three_spheres() {
 
     // world
     let mut world = HitList::new();
+    let mut materials = MaterialArena::new();
 
 
     let texture = ChessBoardTexture::new(
@@ -158,14 +458,14 @@ fn three_spheres() {
         SolidColor::from_rgb(0.2, 0.3, 0.1),
         SolidColor::from_rgb(0.9, 0.9, 0.9)
     );
-    let ground_mat = Lambertian::from_texture(texture);
+    let ground_mat = materials.add(Lambertian::from_texture(texture));
     world.add(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_mat));
 
     let mut cam = RGBCamera::default();
     cam.aspect_ratio = 16.0 / 9.0;
     cam.image_width = 400;
     cam.samples_per_pixel = 50;
-    cam.max_depth = 5;
+    cam.tracer = RecursiveTracer::new(5);
 
     cam.vfov = 20.0;
     cam.look_from = Point3::new(10.0, 2.0, 3.0);
@@ -175,22 +475,22 @@ fn three_spheres() {
     cam.defocus_angle = 0.1;
     cam.focus_dist = 10.0;
 
-    let mat1 = Lambertian::new(0.0, 0.8, 0.0);
-    world.add(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, mat1.clone()));
+    let mat1 = materials.add(Lambertian::new(0.0, 0.8, 0.0));
+    world.add(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, mat1));
     //
-    let mat2 = Lambertian::new(1.0, 0.0, 0.0);
+    let mat2 = materials.add(Lambertian::new(1.0, 0.0, 0.0));
     world.add(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, mat2));
     //
 
 
     let night_earth: TextureType = ImageTexture::new("G:/Projects/Rust/Riven/Riven-OfflineRender/src/engine/textures/nightEarth.jpg");
-    let mat3 = Lambertian::from_texture(night_earth);
+    let mat3 = materials.add(Lambertian::from_texture(night_earth));
     world.add(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, mat3));
 
-    // let bvh = BvhNode::from_world(world);
+    // let bvh = Bvh::from_world(world);
 
 
-    cam.render(&List(world), canvas);
+    cam.render(&Scene::new(List(world), materials), canvas);
 }
 
 
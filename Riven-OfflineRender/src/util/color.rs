@@ -46,6 +46,21 @@ impl Color{
         [r as u8, g as u8, b as u8, 255]
     }
 
+    /// Converts CIE XYZ tristimulus values to linear sRGB.
+    pub fn from_xyz(x: f32, y: f32, z: f32) -> Color {
+        Color::new(
+            3.2406 * x - 1.5372 * y - 0.4986 * z,
+            -0.9689 * x + 1.8758 * y + 0.0415 * z,
+            0.0557 * x - 0.2040 * y + 1.0570 * z,
+        )
+    }
+
+    /// The Rec. 709 luminance, how bright the color looks to the eye.
+    #[inline]
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
     #[inline]
     pub fn random() -> Self {
         Self::new(random_float(), random_float(), random_float())
@@ -36,6 +36,24 @@ impl Canvas {
         }
     }
 
+    /// Creates a new `Canvas` with an explicit height, for images that aren't 16:9.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the canvas.
+    /// * `height` - The height of the canvas.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Canvas`.
+    pub fn with_size(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            image: RgbaImage::new(width, height),
+        }
+    }
+
     /// Saves the current image to the specified file path.
     ///
     /// # Arguments
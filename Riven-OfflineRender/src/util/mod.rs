@@ -1,3 +1,4 @@
 pub mod image;
 pub mod options;
 pub mod color;
+pub mod spectrum;
@@ -0,0 +1,509 @@
+use std::ops::{Add, Mul};
+use std::sync::OnceLock;
+
+use crate::engine::base::constants::constants::random_float;
+use crate::util::color::Color;
+
+/// The shortest wavelength rendered, in nanometers.
+pub const LAMBDA_MIN: f32 = 380.0;
+/// The longest wavelength rendered, in nanometers.
+pub const LAMBDA_MAX: f32 = 780.0;
+
+/// The wavelengths, in nanometers, that stand for the red, green and blue channels.
+pub const RGB_WAVELENGTHS: [f32; 3] = [630.0, 532.0, 465.0];
+
+/// How many wavelengths a spectral ray carries, the hero and the ones rotated from it.
+pub const WAVELENGTH_SAMPLES: usize = 4;
+
+/// The CIE 1931 color matching functions, from Wyman, Sloan and Shirley's multi-lobe Gaussian fit.
+///
+/// # Arguments
+///
+/// * `wavelength` - The wavelength in nanometers.
+///
+/// # Returns
+///
+/// The `x`, `y` and `z` responses of the standard observer.
+pub fn cie_xyz(wavelength: f32) -> (f32, f32, f32) {
+    let lobe = |mu: f32, sigma_below: f32, sigma_above: f32| {
+        let t = (wavelength - mu) / if wavelength < mu { sigma_below } else { sigma_above };
+        (-0.5 * t * t).exp()
+    };
+
+    let x = 1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7) - 0.065 * lobe(501.1, 20.4, 26.2);
+    let y = 0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1);
+    let z = 1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8);
+    (x, y, z)
+}
+
+/// Picks a wavelength uniformly in the visible range.
+pub fn sample_wavelength() -> f32 {
+    LAMBDA_MIN + random_float() * (LAMBDA_MAX - LAMBDA_MIN)
+}
+
+/// The color a single wavelength adds to an RGB ray.
+///
+/// Scaled so that, over wavelengths picked by `sample_wavelength`, the weights average to white:
+/// a ray split into wavelengths carries on average the same light as before.
+///
+/// # Arguments
+///
+/// * `wavelength` - The wavelength in nanometers.
+///
+/// # Returns
+///
+/// The weight in linear sRGB, negative in channels the wavelength is out of gamut for.
+pub fn wavelength_weight(wavelength: f32) -> Color {
+    let (x, y, z) = cie_xyz(wavelength);
+    balance((LAMBDA_MAX - LAMBDA_MIN) * Color::from_xyz(x, y, z))
+}
+
+/// Scales linear sRGB so that a flat spectrum of one is white.
+///
+/// The sRGB primaries assume a D65 white, spectra are rendered under an equal energy one.
+fn balance(color: Color) -> Color {
+    static WHITE: OnceLock<Color> = OnceLock::new();
+    let white = WHITE.get_or_init(|| {
+        let steps = 4000;
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / steps as f32;
+        let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+        for i in 0..steps {
+            let (cx, cy, cz) = cie_xyz(LAMBDA_MIN + (i as f32 + 0.5) * step);
+            (x, y, z) = (x + cx * step, y + cy * step, z + cz * step);
+        }
+        Color::from_xyz(x, y, z)
+    });
+    Color::new(color.r / white.r, color.g / white.g, color.b / white.b)
+}
+
+/// The wavelengths a spectral ray carries, a hero wavelength and others evenly spaced from it.
+///
+/// Surfaces that bend wavelengths apart, like dispersive glass, only follow the hero, and the others
+/// are dropped.
+#[derive(Clone, Copy, Debug)]
+pub struct SampledWavelengths {
+    lambda: [f32; WAVELENGTH_SAMPLES],
+    /// The density each wavelength was picked with, zero once dropped.
+    pdf: [f32; WAVELENGTH_SAMPLES],
+}
+
+impl SampledWavelengths {
+    /// Picks a hero wavelength uniformly, the others are rotated from it by even steps through the range.
+    pub fn sample() -> Self {
+        Self::from_hero(sample_wavelength())
+    }
+
+    /// A single wavelength, the others already dropped.
+    pub fn single(wavelength: f32) -> Self {
+        let mut wavelengths = Self::from_hero(wavelength);
+        wavelengths.terminate_secondary();
+        wavelengths
+    }
+
+    fn from_hero(hero: f32) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let mut lambda = [hero; WAVELENGTH_SAMPLES];
+        for (i, wavelength) in lambda.iter_mut().enumerate() {
+            *wavelength = LAMBDA_MIN + (hero - LAMBDA_MIN + i as f32 * range / WAVELENGTH_SAMPLES as f32) % range;
+        }
+        Self { lambda, pdf: [1.0 / range; WAVELENGTH_SAMPLES] }
+    }
+
+    /// The wavelength every direction is chosen for.
+    pub fn hero(&self) -> f32 {
+        self.lambda[0]
+    }
+
+    /// Drops every wavelength but the hero, for surfaces that send each wavelength its own way.
+    pub fn terminate_secondary(&mut self) {
+        if self.is_secondary_terminated() {
+            return;
+        }
+        for pdf in &mut self.pdf[1..] {
+            *pdf = 0.0;
+        }
+        self.pdf[0] /= WAVELENGTH_SAMPLES as f32;
+    }
+
+    pub fn is_secondary_terminated(&self) -> bool {
+        self.pdf[1..].iter().all(|&pdf| pdf == 0.0)
+    }
+
+    /// Reduces radiance at these wavelengths to XYZ, then to linear sRGB.
+    ///
+    /// # Arguments
+    ///
+    /// * `radiance` - The radiance carried at each wavelength.
+    ///
+    /// # Returns
+    ///
+    /// The estimate in linear sRGB, white for a flat spectrum of one.
+    pub fn to_rgb(&self, radiance: &SampledSpectrum) -> Color {
+        let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+        for i in 0..WAVELENGTH_SAMPLES {
+            if self.pdf[i] <= 0.0 {
+                continue;
+            }
+            let (cx, cy, cz) = cie_xyz(self.lambda[i]);
+            let weight = radiance.0[i] / (self.pdf[i] * WAVELENGTH_SAMPLES as f32);
+            (x, y, z) = (x + cx * weight, y + cy * weight, z + cz * weight);
+        }
+        balance(Color::from_xyz(x, y, z))
+    }
+}
+
+/// Radiance or reflectance at the wavelengths of a `SampledWavelengths`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SampledSpectrum([f32; WAVELENGTH_SAMPLES]);
+
+impl SampledSpectrum {
+    /// The same value at every wavelength.
+    pub fn constant(value: f32) -> Self {
+        Self([value; WAVELENGTH_SAMPLES])
+    }
+
+    /// Upsamples an RGB color to a smooth spectrum, following Jakob and Hanika, and reads it at some wavelengths.
+    ///
+    /// The spectrum is fitted to the color scaled to a brightest channel of one, then scaled back, so
+    /// upsampling is linear in brightness: a BSDF value or a light is the same spectrum as its color, scaled.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The color in linear sRGB, negative channels count as zero.
+    /// * `wavelengths` - Where to read the spectrum.
+    ///
+    /// # Returns
+    ///
+    /// The spectrum's values at the wavelengths.
+    pub fn from_rgb(color: &Color, wavelengths: &SampledWavelengths) -> Self {
+        let rgb = [color.r.max(0.0), color.g.max(0.0), color.b.max(0.0)];
+        let brightest = rgb[0].max(rgb[1]).max(rgb[2]);
+        if brightest <= 0.0 {
+            return Self::default();
+        }
+
+        let coefficients = rgb_to_spectrum_table().coefficients(rgb.map(|c| c / brightest));
+        Self(wavelengths.lambda.map(|lambda| brightest * sigmoid_polynomial(&coefficients, lambda)))
+    }
+
+    pub fn max_component(&self) -> f32 {
+        self.0.iter().copied().fold(f32::MIN, f32::max)
+    }
+}
+
+impl Add for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(std::array::from_fn(|i| self.0[i] + rhs.0[i]))
+    }
+}
+
+impl Mul for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self(std::array::from_fn(|i| self.0[i] * rhs.0[i]))
+    }
+}
+
+impl Mul<SampledSpectrum> for f32 {
+    type Output = SampledSpectrum;
+
+    fn mul(self, rhs: SampledSpectrum) -> Self::Output {
+        SampledSpectrum(rhs.0.map(|value| self * value))
+    }
+}
+
+/// What a path tracer carries along a path, RGB triples or a spectrum at a few wavelengths.
+pub(crate) trait Radiance: Copy + Add<Output = Self> + Mul<Output = Self> {
+    fn constant(value: f32) -> Self;
+
+    /// Converts a material's or a light's RGB color, reading it at the path's wavelengths if it has some.
+    fn from_color(color: &Color, wavelengths: Option<&SampledWavelengths>) -> Self;
+
+    fn scale(self, factor: f32) -> Self;
+
+    fn max_component(&self) -> f32;
+}
+
+impl Radiance for Color {
+    fn constant(value: f32) -> Self {
+        Color::new(value, value, value)
+    }
+
+    fn from_color(color: &Color, _: Option<&SampledWavelengths>) -> Self {
+        *color
+    }
+
+    fn scale(self, factor: f32) -> Self {
+        factor * self
+    }
+
+    fn max_component(&self) -> f32 {
+        self.r.max(self.g).max(self.b)
+    }
+}
+
+impl Radiance for SampledSpectrum {
+    fn constant(value: f32) -> Self {
+        SampledSpectrum::constant(value)
+    }
+
+    fn from_color(color: &Color, wavelengths: Option<&SampledWavelengths>) -> Self {
+        match wavelengths {
+            Some(wavelengths) => SampledSpectrum::from_rgb(color, wavelengths),
+            None => SampledSpectrum::constant(color.luminance()),
+        }
+    }
+
+    fn scale(self, factor: f32) -> Self {
+        factor * self
+    }
+
+    fn max_component(&self) -> f32 {
+        SampledSpectrum::max_component(self)
+    }
+}
+
+/// A reflectance spectrum bounded to `[0, 1]`, a sigmoid of a quadratic in the normalized wavelength.
+#[inline]
+fn sigmoid_polynomial(coefficients: &[f32; 3], wavelength: f32) -> f32 {
+    let t = (wavelength - LAMBDA_MIN) / (LAMBDA_MAX - LAMBDA_MIN);
+    let x = (coefficients[0] * t + coefficients[1]) * t + coefficients[2];
+    if x.is_infinite() {
+        return if x > 0.0 { 1.0 } else { 0.0 };
+    }
+    0.5 + x / (2.0 * (1.0 + x * x).sqrt())
+}
+
+/// Sigmoid polynomial coefficients over a grid of colors whose brightest channel is one.
+///
+/// A color is located by which channel is brightest and by its two others.
+struct RgbToSpectrumTable {
+    /// Coefficients indexed by brightest channel, then `y` and `x`.
+    coefficients: Vec<[f32; 3]>,
+}
+
+impl RgbToSpectrumTable {
+    const RESOLUTION: usize = 32;
+
+    fn index(brightest: usize, y: usize, x: usize) -> usize {
+        (brightest * Self::RESOLUTION + y) * Self::RESOLUTION + x
+    }
+
+    fn build() -> Self {
+        let r = Self::RESOLUTION;
+        let fit = SpectrumFit::new();
+
+        let mut coefficients = vec![[0.0f32; 3]; 3 * r * r];
+        for brightest in 0..3 {
+            for y in 0..r {
+                for x in 0..r {
+                    let mut rgb = [0.0; 3];
+                    rgb[brightest] = 1.0;
+                    rgb[(brightest + 1) % 3] = x as f32 / (r - 1) as f32;
+                    rgb[(brightest + 2) % 3] = y as f32 / (r - 1) as f32;
+                    coefficients[Self::index(brightest, y, x)] = fit.solve(rgb, [0.0; 3]);
+                }
+            }
+        }
+
+        Self { coefficients }
+    }
+
+    /// The coefficients of a color whose brightest channel is one, interpolated between grid points.
+    fn coefficients(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let r = Self::RESOLUTION;
+        let brightest = if rgb[0] >= rgb[1] && rgb[0] >= rgb[2] { 0 } else if rgb[1] >= rgb[2] { 1 } else { 2 };
+        let scaled = |c: f32| c.clamp(0.0, 1.0) * (r - 1) as f32;
+        let (x, y) = (scaled(rgb[(brightest + 1) % 3]), scaled(rgb[(brightest + 2) % 3]));
+
+        let (xi, yi) = ((x as usize).min(r - 2), (y as usize).min(r - 2));
+        let (dx, dy) = (x - xi as f32, y - yi as f32);
+
+        let mut result = [0.0; 3];
+        for ((cy, cx), weight) in [
+            ((0, 0), (1.0 - dy) * (1.0 - dx)),
+            ((0, 1), (1.0 - dy) * dx),
+            ((1, 0), dy * (1.0 - dx)),
+            ((1, 1), dy * dx),
+        ] {
+            let c = self.coefficients[Self::index(brightest, yi + cy, xi + cx)];
+            for k in 0..3 {
+                result[k] += weight * c[k];
+            }
+        }
+        result
+    }
+}
+
+fn rgb_to_spectrum_table() -> &'static RgbToSpectrumTable {
+    static TABLE: OnceLock<RgbToSpectrumTable> = OnceLock::new();
+    TABLE.get_or_init(RgbToSpectrumTable::build)
+}
+
+/// Fits sigmoid polynomial coefficients to colors, by Gauss-Newton iterations on the RGB error.
+struct SpectrumFit {
+    /// The normalized wavelength of each integration step.
+    t: Vec<f32>,
+    /// The balanced RGB each integration step adds, per unit of reflectance.
+    weights: Vec<[f32; 3]>,
+}
+
+impl SpectrumFit {
+    const STEPS: usize = 64;
+
+    fn new() -> Self {
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / Self::STEPS as f32;
+        let (t, weights) = (0..Self::STEPS).map(|i| {
+            let lambda = LAMBDA_MIN + (i as f32 + 0.5) * step;
+            let (x, y, z) = cie_xyz(lambda);
+            let rgb = balance(step * Color::from_xyz(x, y, z));
+            ((lambda - LAMBDA_MIN) / (LAMBDA_MAX - LAMBDA_MIN), [rgb.r, rgb.g, rgb.b])
+        }).unzip();
+        Self { t, weights }
+    }
+
+    /// The RGB of a coefficient set's spectrum, and its derivatives with respect to each coefficient.
+    fn evaluate(&self, c: &[f32; 3]) -> ([f32; 3], [[f32; 3]; 3]) {
+        let mut rgb = [0.0; 3];
+        let mut jacobian = [[0.0; 3]; 3];
+        for (t, weight) in self.t.iter().zip(&self.weights) {
+            let x = (c[0] * t + c[1]) * t + c[2];
+            let root = (1.0 + x * x).sqrt();
+            let value = 0.5 + x / (2.0 * root);
+            let slope = 0.5 / (root * root * root);
+            let powers = [t * t, *t, 1.0];
+            for channel in 0..3 {
+                rgb[channel] += value * weight[channel];
+                for k in 0..3 {
+                    jacobian[channel][k] += slope * powers[k] * weight[channel];
+                }
+            }
+        }
+        (rgb, jacobian)
+    }
+
+    fn solve(&self, target: [f32; 3], guess: [f32; 3]) -> [f32; 3] {
+        let error = |rgb: [f32; 3]| (0..3).map(|k| (rgb[k] - target[k]).powi(2)).sum::<f32>();
+
+        let mut c = guess;
+        let (mut rgb, mut jacobian) = self.evaluate(&c);
+        for _ in 0..50 {
+            let current = error(rgb);
+            if current < 1e-10 {
+                break;
+            }
+            let residual = [rgb[0] - target[0], rgb[1] - target[1], rgb[2] - target[2]];
+            let Some(step) = solve_3x3(&jacobian, &residual) else {
+                break;
+            };
+
+            // Full steps overshoot where the sigmoid saturates, halve them until the error drops
+            let mut length = 1.0;
+            let improved = loop {
+                let candidate = [0, 1, 2].map(|k| c[k] - length * step[k]);
+                let evaluated = self.evaluate(&candidate);
+                if error(evaluated.0) < current {
+                    break Some((candidate, evaluated));
+                }
+                length *= 0.5;
+                if length < 1e-4 {
+                    break None;
+                }
+            };
+            let Some((candidate, evaluated)) = improved else {
+                break;
+            };
+            c = candidate;
+            (rgb, jacobian) = evaluated;
+        }
+        c
+    }
+}
+
+/// Solves `m x = b` by Cramer's rule.
+fn solve_3x3(m: &[[f32; 3]; 3], b: &[f32; 3]) -> Option<[f32; 3]> {
+    let det = |m: &[[f32; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let d = det(m);
+    if d.abs() < 1e-20 {
+        return None;
+    }
+
+    let mut x = [0.0; 3];
+    for (k, value) in x.iter_mut().enumerate() {
+        let mut replaced = *m;
+        for row in 0..3 {
+            replaced[row][k] = b[row];
+        }
+        *value = det(&replaced) / d;
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod test_spectrum {
+    use crate::util::color::Color;
+    use crate::util::spectrum::{cie_xyz, wavelength_weight, SampledSpectrum, SampledWavelengths, LAMBDA_MAX, LAMBDA_MIN};
+
+    #[test]
+    fn wavelength_weights_average_to_white(){
+        // The luminance response peaks in the green, near 555nm
+        let peak = (400..700i32).max_by(|&a, &b| cie_xyz(a as f32).1.total_cmp(&cie_xyz(b as f32).1)).unwrap();
+        assert!((peak - 555).abs() < 10, "peak at {peak}nm");
+
+        let steps = 1000;
+        let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+        for i in 0..steps {
+            let lambda = LAMBDA_MIN + (i as f32 + 0.5) * (LAMBDA_MAX - LAMBDA_MIN) / steps as f32;
+            let weight = wavelength_weight(lambda);
+            (r, g, b) = (r + weight.r / steps as f32, g + weight.g / steps as f32, b + weight.b / steps as f32);
+        }
+        assert!((r - 1.0).abs() < 0.01 && (g - 1.0).abs() < 0.01 && (b - 1.0).abs() < 0.01, "averages to {r} {g} {b}");
+
+        // Red light is red
+        let red = wavelength_weight(640.0);
+        assert!(red.r > 0.0 && red.r > 4.0 * red.g.abs() && red.r > 4.0 * red.b.abs());
+    }
+
+    #[test]
+    fn upsampled_colors_render_back_to_themselves(){
+        let colors = [
+            Color::new(1.0, 1.0, 1.0),
+            Color::new(0.65, 0.05, 0.05),
+            Color::new(0.12, 0.45, 0.15),
+            Color::new(0.2, 0.3, 0.9),
+            Color::new(0.73, 0.73, 0.73),
+            Color::new(15.0, 12.0, 4.0),
+        ];
+
+        for color in colors {
+            // Average the spectral estimate over many hero wavelengths
+            let samples = 20_000;
+            let mut average = Color::new(0.0, 0.0, 0.0);
+            for _ in 0..samples {
+                let wavelengths = SampledWavelengths::sample();
+                let spectrum = SampledSpectrum::from_rgb(&color, &wavelengths);
+                average = average + (1.0 / samples as f32) * wavelengths.to_rgb(&spectrum);
+            }
+
+            let tolerance = 0.03 * color.r.max(color.g).max(color.b);
+            for (got, expected) in [(average.r, color.r), (average.g, color.g), (average.b, color.b)] {
+                assert!((got - expected).abs() < tolerance, "{color:?} renders as {average:?}");
+            }
+        }
+
+        // Dropping the secondary wavelengths keeps the estimate unbiased
+        let mut average = 0.0;
+        for _ in 0..40_000 {
+            let mut wavelengths = SampledWavelengths::sample();
+            wavelengths.terminate_secondary();
+            average += wavelengths.to_rgb(&SampledSpectrum::constant(1.0)).g / 40_000.0;
+        }
+        assert!((average - 1.0).abs() < 0.05, "white renders as {average}");
+    }
+}
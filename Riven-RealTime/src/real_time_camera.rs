@@ -3,7 +3,8 @@ use rayon::prelude::*;
 use Riven_OfflineRender::engine::base::point::Point3;
 use Riven_OfflineRender::engine::base::vector::Vector3;
 use Riven_OfflineRender::engine::camera::rgb_camera::RGBCamera;
-use Riven_OfflineRender::engine::objects::object::HitList;
+use Riven_OfflineRender::engine::scene::Scene;
+use Riven_OfflineRender::engine::tracers::Tracer;
 use Riven_OfflineRender::util::color::Color;
 
 
@@ -73,7 +74,7 @@ impl RealTimeWindow{
         })
     }
 
-    pub fn render(&mut self, world: &HitList, cam: &mut RGBCamera) {
+    pub fn render(&mut self, scene: &Scene, cam: &mut RGBCamera) {
         cam.initialize();
 
 
@@ -93,7 +94,7 @@ impl RealTimeWindow{
 
                             for _ in 0..cam.samples_per_pixel {
                                 let ray = cam.get_ray(x as u32, actual_y as u32);
-                                pixel_color = pixel_color + RGBCamera::ray_color(&ray, world, cam.max_depth);
+                                pixel_color = pixel_color + cam.tracer.trace_ray(&ray, scene);
                             }
 
                             pixel_color = cam.pixel_sample_scale * pixel_color;